@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+use std::fs;
+use std::time::Duration;
+
+use rand::{SeedableRng, rngs::StdRng};
+use serde_json::json;
+
+use crate::domain_description::{read_json_domain, FONDProblem};
+use crate::relaxation::RelevancePruning;
+use crate::search::{
+    to_csv, AOStarSearch, HeuristicEvaluation, HeuristicType, IWSearch, PolicyMetrics, SearchConfig, SearchResult,
+    StrongPolicy
+};
+use crate::task_network::Task;
+
+use super::*;
+
+fn read_problem(path: &str) -> Result<FONDProblem, i32> {
+    read_json_domain(path).map_err(|errors| {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        INPUT_ERROR
+    })
+}
+
+fn read_policy(path: &str, problem: &FONDProblem) -> Result<StrongPolicy, i32> {
+    let json = fs::read_to_string(path).map_err(|e| {
+        eprintln!("unable to read {}: {}", path, e);
+        INPUT_ERROR
+    })?;
+    StrongPolicy::from_json(&json, &problem.tasks).map_err(|e| {
+        eprintln!("{}", e);
+        INPUT_ERROR
+    })
+}
+
+fn exit_code(result: Result<i32, i32>) -> i32 {
+    match result {
+        Ok(code) | Err(code) => code,
+    }
+}
+
+pub fn solve(args: &SolveArgs) -> i32 {
+    exit_code(try_solve(args))
+}
+
+fn try_solve(args: &SolveArgs) -> Result<i32, i32> {
+    if args.time_limit.is_some_and(|x| !x.is_finite() || x < 0.0) {
+        eprintln!("time limit must be a non-negative number of seconds");
+        return Err(INPUT_ERROR);
+    }
+    let mut problem = read_problem(&args.problem)?;
+    let mut pruning = None;
+    if args.prune {
+        let (pruned, report) = RelevancePruning::prune(&problem);
+        problem = pruned;
+        pruning = Some(report);
+    }
+    if args.algorithm == Algorithm::Iw {
+        return Ok(solve_iw(args, &problem));
+    }
+    let config = SearchConfig {
+        tip_selection: args.tip_selection,
+        weight: args.weight,
+        node_limit: args.node_limit,
+        time_limit: args.time_limit.map(Duration::from_secs_f64),
+        cache_size: args.cache_size,
+        preferred_operators: args.preferred_operators,
+        free_decompositions: args.free_decompositions,
+    };
+    let (result, stats) = AOStarSearch::run_with_config(&problem, args.heuristic, &config);
+    if let (Some(path), SearchResult::Success(policy)) = (&args.save_policy, &result) {
+        fs::write(path, policy.to_json()).map_err(|e| {
+            eprintln!("unable to write {}: {}", path, e);
+            INPUT_ERROR
+        })?;
+    }
+    match args.format {
+        OutputFormat::Text => {
+            if let Some(report) = &pruning {
+                print!("{}", report);
+            }
+            print!("{}", stats);
+            match &result {
+                SearchResult::Success(x) => {
+                    println!("makespan: {}", x.makespan);
+                    println!("policy enteries: {}", x.transitions.len());
+                    print!("{}", PolicyMetrics::new(x));
+                    println!("compact policy enteries: {}", x.compact(&problem.facts).transitions.len());
+                },
+                SearchResult::NoSolution(_) => print!("{}", result),
+                SearchResult::LimitReached => println!("{}", result),
+            }
+        },
+        OutputFormat::Json => {
+            let mut output = json!({
+                "stats": {
+                    "max_depth": stats.max_depth,
+                    "search_nodes": stats.search_nodes,
+                    "explored_nodes": stats.explored_nodes,
+                    "search_time": stats.seach_time.as_secs_f64(),
+                    "cache_hits": stats.cache_hits,
+                    "cache_misses": stats.cache_misses,
+                    "undecomposable_nodes": stats.undecomposable_nodes,
+                }
+            });
+            if let Some(report) = &pruning {
+                output["pruning"] = json!({
+                    "unreachable_tasks": report.unreachable_tasks,
+                    "undecomposable_tasks": report.undecomposable_tasks,
+                    "inapplicable_actions": report.inapplicable_actions,
+                    "tasks_in_removed_methods": report.tasks_in_removed_methods,
+                    "removed_methods": report.removed_methods,
+                });
+            }
+            if let Some(landmarks) = &stats.landmarks {
+                output["stats"]["landmarks"] = json!({
+                    "facts": landmarks.facts,
+                    "tasks": landmarks.tasks,
+                    "methods": landmarks.methods,
+                });
+            }
+            match &result {
+                SearchResult::Success(x) => {
+                    let metrics = PolicyMetrics::new(x);
+                    output["status"] = json!("solved");
+                    output["metrics"] = json!({
+                        "worst_case_cost": metrics.worst_case_cost,
+                        "worst_case_actions": metrics.worst_case_actions,
+                        "expected_cost": metrics.expected_cost,
+                        "branches": metrics.branches,
+                        "max_decomposition_depth": metrics.max_decomposition_depth,
+                        "size": metrics.size,
+                    });
+                    output["policy"] = x.to_json_value();
+                },
+                SearchResult::NoSolution(report) => {
+                    output["status"] = json!("unsolvable");
+                    output["failure"] = json!(report.to_string());
+                },
+                SearchResult::LimitReached => {
+                    output["status"] = json!("limit_reached");
+                }
+            }
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        },
+        OutputFormat::Dot => {
+            match &result {
+                SearchResult::Success(x) => print!("{}", x.to_dot()),
+                _ => eprintln!("{}", result.to_string().trim_end()),
+            }
+        }
+    }
+    Ok(match result {
+        SearchResult::Success(_) => SOLVED,
+        SearchResult::NoSolution(_) => UNSOLVABLE,
+        SearchResult::LimitReached => LIMIT_REACHED,
+    })
+}
+
+// A plan of the deterministic fragment, not finding one does not prove that
+// the problem is unsolvable
+fn solve_iw(args: &SolveArgs, problem: &FONDProblem) -> i32 {
+    let result = IWSearch::run(problem, args.max_width);
+    match args.format {
+        OutputFormat::Json => {
+            let mut output = json!({
+                "stats": {
+                    "width": result.width,
+                    "generated_nodes": result.generated,
+                }
+            });
+            match &result.plan {
+                Some(plan) => {
+                    output["status"] = json!("solved");
+                    output["plan"] = json!(plan);
+                },
+                None => output["status"] = json!("limit_reached"),
+            }
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        },
+        _ => {
+            println!("width: {}", result.width);
+            println!("generated nodes: {}", result.generated);
+            match &result.plan {
+                Some(plan) => {
+                    for step in plan.iter() {
+                        println!("{}", step);
+                    }
+                },
+                None => println!("no plan within width {}", args.max_width),
+            }
+        }
+    }
+    match result.plan {
+        Some(_) => SOLVED,
+        None => LIMIT_REACHED,
+    }
+}
+
+pub fn validate(path: &str) -> i32 {
+    match read_problem(path) {
+        Ok(problem) => {
+            println!("{} is valid", path);
+            println!("# of facts: {}", problem.facts.count());
+            println!("# of tasks: {}", problem.tasks.count_tasks());
+            SOLVED
+        },
+        Err(code) => code,
+    }
+}
+
+pub fn stats(path: &str) -> i32 {
+    let problem = match read_problem(path) {
+        Ok(problem) => problem,
+        Err(code) => return code,
+    };
+    let (mut actions, mut nondeterministic, mut max_outcomes) = (0, 0, 0);
+    let (mut compound, mut methods, mut max_method_size) = (0, 0, 0);
+    for task in problem.tasks.get_all_tasks().iter() {
+        match &*task.borrow() {
+            Task::Primitive(action) => {
+                actions += 1;
+                if !action.is_deterministic() {
+                    nondeterministic += 1;
+                }
+                max_outcomes = max_outcomes.max(action.add_effects.len());
+            },
+            Task::Compound(task) => {
+                compound += 1;
+                methods += task.methods.len();
+                for method in task.methods.iter() {
+                    max_method_size = max_method_size.max(method.decomposition.count_tasks());
+                }
+            }
+        }
+    }
+    println!("# of facts: {}", problem.facts.count());
+    println!("# of initial facts: {}", problem.initial_state.len());
+    println!("# of primitive actions: {}", actions);
+    println!("# of non-deterministic actions: {}", nondeterministic);
+    println!("max # of outcomes: {}", max_outcomes);
+    println!("# of compound tasks: {}", compound);
+    println!("# of methods: {}", methods);
+    println!("max method size: {}", max_method_size);
+    println!("totally ordered: {}", if problem.is_totally_ordered() { "yes" } else { "no" });
+    SOLVED
+}
+
+pub fn verify_policy(problem: &str, policy: &str) -> i32 {
+    exit_code((|| {
+        let problem = read_problem(problem)?;
+        let policy = read_policy(policy, &problem)?;
+        match policy.verify(&problem) {
+            Ok(checked) => {
+                println!("policy is valid ({} reachable pairs checked)", checked);
+                Ok(SOLVED)
+            },
+            Err(e) => {
+                println!("policy is invalid: {}", e);
+                Ok(UNSOLVABLE)
+            }
+        }
+    })())
+}
+
+pub fn simulate(problem: &str, policy: &str, runs: u32, seed: Option<u64>) -> i32 {
+    exit_code((|| {
+        let problem = read_problem(problem)?;
+        let policy = read_policy(policy, &problem)?;
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let (mut successes, mut total_cost, mut max_cost) = (0, 0, 0);
+        let mut failures: HashMap<String, u32> = HashMap::new();
+        for _ in 0..runs {
+            match policy.simulate(&problem, &mut rng) {
+                Ok(trace) => {
+                    if runs == 1 {
+                        for (action, _) in trace.iter() {
+                            println!("{}", action);
+                        }
+                    }
+                    let cost: u32 = trace.iter().map(|(_, cost)| cost).sum();
+                    successes += 1;
+                    total_cost += cost as u64;
+                    max_cost = max_cost.max(cost);
+                },
+                Err(e) => {
+                    *failures.entry(e.to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+        println!("successful runs: {}/{}", successes, runs);
+        if successes > 0 {
+            println!("average cost: {:.3}", total_cost as f64 / successes as f64);
+            println!("max cost: {}", max_cost);
+        }
+        for (failure, count) in failures.iter() {
+            println!("{} ({} runs)", failure, count);
+        }
+        Ok(if successes == runs { SOLVED } else { UNSOLVABLE })
+    })())
+}
+
+pub fn export(problem: &str, policy: &str, format: ExportFormat) -> i32 {
+    exit_code((|| {
+        let problem = read_problem(problem)?;
+        let policy = read_policy(policy, &problem)?;
+        let state_machine = || policy.to_state_machine(&problem.facts).map_err(|e| {
+            eprintln!("{}", e);
+            INPUT_ERROR
+        });
+        match format {
+            ExportFormat::Json => println!("{}", policy.to_json()),
+            ExportFormat::Dot => print!("{}", policy.to_dot()),
+            ExportFormat::Rust => print!("{}", state_machine()?.to_rust_module()),
+            ExportFormat::Fsm => println!("{}", state_machine()?.to_json()),
+        }
+        Ok(SOLVED)
+    })())
+}
+
+pub fn evaluate(path: &str, depth: u16, node_limit: u32, heuristics: &[HeuristicType]) -> i32 {
+    let problem = match read_problem(path) {
+        Ok(problem) => problem,
+        Err(code) => return code,
+    };
+    let heuristics = if heuristics.is_empty() {
+        vec![
+            HeuristicType::HFF, HeuristicType::HAdd, HeuristicType::HMax, HeuristicType::HAddWC,
+            HeuristicType::HMaxWC, HeuristicType::OrderedAdd, HeuristicType::LMCut, HeuristicType::LP,
+            HeuristicType::TDGm, HeuristicType::TDGc, HeuristicType::TDGmR, HeuristicType::TDGcR,
+        ]
+    } else {
+        heuristics.to_vec()
+    };
+    if heuristics.contains(&HeuristicType::LandmarkCount) {
+        eprintln!("lmcount depends on the path to a node and can not be evaluated");
+        return INPUT_ERROR;
+    }
+    let evaluation = HeuristicEvaluation::new(&problem, depth, node_limit);
+    if evaluation.truncated {
+        eprintln!("depth or node limit reached, costs of the remaining nodes are unknown");
+    }
+    let reports: Vec<_> = heuristics.into_iter()
+        .map(|mut h| evaluation.evaluate(&h.to_string(), &mut h))
+        .collect();
+    print!("{}", to_csv(&reports));
+    SOLVED
+}
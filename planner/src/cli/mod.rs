@@ -0,0 +1,218 @@
+mod commands;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::search::{HeuristicType, TipSelection};
+
+// Exit codes
+pub const SOLVED: i32 = 0;
+pub const UNSOLVABLE: i32 = 1;
+pub const LIMIT_REACHED: i32 = 2;
+pub const INPUT_ERROR: i32 = 3;
+
+#[derive(Debug, Parser)]
+#[command(name = "planner", about = "FOND HTN planner")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Search for a strong policy
+    Solve(SolveArgs),
+    /// Check a problem file for undefined facts and tasks
+    Validate {
+        problem: String,
+    },
+    /// Print statistics about a problem
+    Stats {
+        problem: String,
+    },
+    /// Check that a policy solves a problem
+    VerifyPolicy {
+        problem: String,
+        policy: String,
+    },
+    /// Execute a policy with randomly chosen outcomes
+    Simulate {
+        problem: String,
+        policy: String,
+        #[arg(long, default_value_t = 1)]
+        runs: u32,
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Convert a policy into another format
+    Export {
+        problem: String,
+        policy: String,
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+    },
+    /// Compare heuristics with optimal costs on a bounded search space (CSV)
+    Evaluate {
+        problem: String,
+        /// Maximum number of progression steps from the initial node
+        #[arg(long, default_value_t = 12)]
+        depth: u16,
+        /// Maximum number of explored nodes
+        #[arg(long, default_value_t = 100_000)]
+        node_limit: u32,
+        /// Comma-separated heuristics, all but lmcount by default
+        #[arg(long, value_delimiter = ',')]
+        heuristics: Vec<HeuristicType>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct SolveArgs {
+    pub problem: String,
+    /// AO* (strong acyclic policies) or iw. Strong cyclic policies are not
+    /// supported, the cyclic search space is disabled
+    #[arg(long, value_enum, default_value_t = Algorithm::Aostar)]
+    pub algorithm: Algorithm,
+    /// hff, hadd, hmax, hadd-wc, hmax-wc, hadd-o, lmcut, lmcount, lp, tdgm, tdgc, tdgm-r or tdgc-r
+    #[arg(long, default_value = "hadd")]
+    pub heuristic: HeuristicType,
+    /// first-found, deepest, shallowest, min-cost, max-cost or novelty
+    #[arg(long, default_value = "first-found")]
+    pub tip_selection: TipSelection,
+    /// Factor the heuristic values are multiplied by
+    #[arg(long, default_value_t = 1.0)]
+    pub weight: f32,
+    /// Maximum number of node expansions
+    #[arg(long)]
+    pub node_limit: Option<u32>,
+    /// Time limit in seconds
+    #[arg(long)]
+    pub time_limit: Option<f64>,
+    /// Maximum number of cached heuristic values (0 disables the cache)
+    #[arg(long, default_value_t = 100_000)]
+    pub cache_size: usize,
+    /// Expand successors in the relaxed plan of a node first
+    #[arg(long)]
+    pub preferred_operators: bool,
+    /// Decompositions by methods without a cost are free (they cost 1 otherwise)
+    #[arg(long)]
+    pub free_decompositions: bool,
+    /// Remove tasks and methods that can not be part of a solution before searching
+    #[arg(long)]
+    pub prune: bool,
+    /// Largest width tried by the iw algorithm
+    #[arg(long, default_value_t = 2)]
+    pub max_width: usize,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+    /// Write the policy (as JSON) to this file
+    #[arg(long)]
+    pub save_policy: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Algorithm {
+    Aostar,
+    // iterated width over the deterministic fragment
+    Iw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Dot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+    Dot,
+    // Rust controller module
+    Rust,
+    // JSON state-machine description
+    Fsm,
+}
+
+const SUBCOMMANDS: [&str; 7] = ["solve", "validate", "stats", "verify-policy", "simulate", "export", "evaluate"];
+
+// Parses the arguments and runs the command, returns the exit code
+pub fn run(mut args: Vec<String>) -> i32 {
+    // "planner <problem>" is a shorthand for "planner solve <problem>"
+    if args.len() > 1 && !args[1].starts_with('-') && !SUBCOMMANDS.contains(&args[1].as_str()) && args[1] != "help" {
+        args.insert(1, "solve".to_string());
+    }
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return if e.use_stderr() { INPUT_ERROR } else { SOLVED };
+        }
+    };
+    match cli.command {
+        Command::Solve(args) => commands::solve(&args),
+        Command::Validate { problem } => commands::validate(&problem),
+        Command::Stats { problem } => commands::stats(&problem),
+        Command::VerifyPolicy { problem, policy } => commands::verify_policy(&problem, &policy),
+        Command::Simulate { problem, policy, runs, seed } => commands::simulate(&problem, &policy, runs, seed),
+        Command::Export { problem, policy, format } => commands::export(&problem, &policy, format),
+        Command::Evaluate { problem, depth, node_limit, heuristics } => {
+            commands::evaluate(&problem, depth, node_limit, &heuristics)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(args.iter().map(|x| x.to_string()))
+    }
+
+    #[test]
+    pub fn solve_arguments_test() {
+        let cli = parse(&[
+            "planner", "solve", "p.json", "--heuristic", "hff", "--tip-selection", "deepest",
+            "--weight", "2", "--node-limit", "10", "--format", "json"
+        ]).unwrap();
+        match cli.command {
+            Command::Solve(args) => {
+                assert_eq!(args.problem, "p.json");
+                assert_eq!(args.heuristic, HeuristicType::HFF);
+                assert_eq!(args.tip_selection, TipSelection::Deepest);
+                assert_eq!(args.weight, 2.0);
+                assert_eq!(args.node_limit, Some(10));
+                assert_eq!(args.time_limit, None);
+                assert_eq!(args.cache_size, 100_000);
+                assert!(!args.preferred_operators);
+                assert!(!args.free_decompositions);
+                assert!(!args.prune);
+                assert_eq!(args.max_width, 2);
+                assert_eq!(args.format, OutputFormat::Json);
+                assert_eq!(args.algorithm, Algorithm::Aostar);
+            },
+            _ => panic!("expected solve"),
+        }
+        assert!(parse(&["planner", "solve", "p.json", "--heuristic", "unknown"]).is_err());
+    }
+
+    #[test]
+    pub fn evaluate_arguments_test() {
+        let cli = parse(&["planner", "evaluate", "p.json", "--heuristics", "hmax,lmcut"]).unwrap();
+        match cli.command {
+            Command::Evaluate { problem, depth, heuristics, .. } => {
+                assert_eq!(problem, "p.json");
+                assert_eq!(depth, 12);
+                assert_eq!(heuristics, vec![HeuristicType::HMax, HeuristicType::LMCut]);
+            },
+            _ => panic!("expected evaluate"),
+        }
+    }
+
+    #[test]
+    pub fn exit_code_test() {
+        assert_eq!(run(vec!["planner".to_string(), "validate".to_string(), "missing.json".to_string()]), INPUT_ERROR);
+        assert_eq!(run(vec!["planner".to_string(), "missing.json".to_string()]), INPUT_ERROR);
+        assert_eq!(run(vec!["planner".to_string(), "--unknown".to_string()]), INPUT_ERROR);
+    }
+}
@@ -8,6 +8,10 @@ use super::Facts;
 pub struct ClassicalDomain {
     pub facts: Facts,
     pub actions: Vec<PrimitiveAction>,
+    // fact id -> indices of the actions having it as precondition
+    precondition_of: Vec<Vec<usize>>,
+    // indices of the actions without preconditions
+    unconditional_actions: Vec<usize>,
 }
 
 impl ClassicalDomain {
@@ -15,7 +19,21 @@ impl ClassicalDomain {
         facts: Facts,
         actions: Vec<PrimitiveAction>,
     ) -> ClassicalDomain {
-        ClassicalDomain { facts, actions}
+        let num_facts = actions.iter()
+            .flat_map(|a| a.pre_cond.iter())
+            .max()
+            .map_or(0, |x| *x as usize + 1);
+        let mut precondition_of = vec![vec![]; num_facts];
+        let mut unconditional_actions = vec![];
+        for (i, action) in actions.iter().enumerate() {
+            for f in action.pre_cond.iter() {
+                precondition_of[*f as usize].push(i);
+            }
+            if action.pre_cond.is_empty() {
+                unconditional_actions.push(i);
+            }
+        }
+        ClassicalDomain { facts, actions, precondition_of, unconditional_actions }
     }
 
     pub fn delete_relax(&self) -> ClassicalDomain {
@@ -38,4 +56,15 @@ impl ClassicalDomain {
     pub fn get_fact(&self, index: u32) -> &String {
         self.facts.get_fact(index)
     }
+
+    pub fn precondition_of(&self, fact: u32) -> &[usize] {
+        match self.precondition_of.get(fact as usize) {
+            Some(actions) => actions,
+            None => &[],
+        }
+    }
+
+    pub fn unconditional_actions(&self) -> &[usize] {
+        &self.unconditional_actions
+    }
 }
\ No newline at end of file
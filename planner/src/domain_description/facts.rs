@@ -25,16 +25,13 @@ impl Facts {
     }
 
     pub fn extend(&self, extension: Vec<String>) -> Facts {
-        let mut max_id = self.ids.iter()
-            .map(|(literal, id)| {
-                id
-            }).max().unwrap() + 1;
         let mut new_literals = self.literals.clone();
         let mut new_ids = self.ids.clone();
+        // ids are positions in the literal list, which may hold repeated
+        // names (and be empty)
         for literal in extension.into_iter() {
-            new_literals.push(literal.clone());
-            new_ids.insert(literal, max_id);
-            max_id += 1;
+            new_ids.insert(literal.clone(), new_literals.len() as u32);
+            new_literals.push(literal);
         }
         Facts { literals: new_literals, ids: new_ids }
     }
@@ -103,6 +100,12 @@ mod tests {
         assert_eq!(facts.get_fact(3), "-at[waypoint1, rover0]");
         assert_eq!(facts.get_fact(4), "Reached_t1");
         assert_eq!(facts.get_fact(5), "Reached_t2");
+        // repeated names keep the ids aligned with the literals
+        let facts = Facts::new(vec!["a".to_string(), "a".to_string()]).extend(vec!["b".to_string()]);
+        assert_eq!(facts.get_id("b"), 2);
+        assert_eq!(facts.get_fact(2), "b");
+        let facts = Facts::new(vec![]).extend(vec!["b".to_string()]);
+        assert_eq!(facts.get_id("b"), 0);
     }
 }
 
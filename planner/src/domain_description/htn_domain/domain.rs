@@ -12,6 +12,9 @@ use rand::distributions::Alphanumeric;
 use rand::distributions::DistString;
 use std::rc::Rc;
 
+// (method name, task name, subtasks, orderings, cost)
+pub type RawMethodSpec = (String, String, Vec<String>, Vec<(u32, u32)>, Option<u32>);
+
 #[derive(Debug)]
 pub struct FONDProblem{
     pub facts: Facts,
@@ -24,8 +27,7 @@ impl FONDProblem {
     pub fn new(literals: Vec<String>,
                 // Vector of tuples in the form (action name, preconds, Vec<(Vec<add>, Vec<del>)>)
                 actions: Vec<(String, Vec<String>, Vec<(Vec<String>, Vec<String>)>)>,
-                // Vector of tuples in the form (method name, task name, vec<subtasks>, vec<orderings>)
-                methods: Vec<(String, String, Vec<String>, Vec<(u32, u32)>)>,
+                methods: Vec<RawMethodSpec>,
                 abstract_tasks: Vec<String>,
                 init: HashSet<String>,
                 first_task: String
@@ -66,7 +68,7 @@ impl FONDProblem {
 
         // Process methods
         let mut parsed_methods = vec![];
-        for (name, task, subtasks, orderings) in methods.into_iter() {
+        for (name, task, subtasks, orderings, cost) in methods.into_iter() {
             let processed_orderings: Vec<(u32, u32)> = orderings.into_iter()
                     .map(|(x, y)| (&subtasks[x as usize], &subtasks[y as usize]))
                     .map(|(x, y)| (domain_tasks.get_id(x), domain_tasks.get_id(y)))
@@ -74,7 +76,10 @@ impl FONDProblem {
             let subtasks: BTreeSet<u32> = subtasks.into_iter().map(|x| domain_tasks.get_id(&x)).collect();
             let mappings = HashMap::from_iter(subtasks.iter().cloned().zip(subtasks.iter().cloned()));
             let decomposition = HTN::new(subtasks, processed_orderings, domain_tasks.clone(), mappings);
-            let method = Method::new(name, decomposition);
+            let method = match cost {
+                Some(cost) => Method::with_cost(name, decomposition, cost),
+                None => Method::new(name, decomposition),
+            };
             let task_id = domain_tasks.get_id(&task);
             parsed_methods.push((task_id, method));
         }
@@ -97,6 +102,42 @@ impl FONDProblem {
         }
     }
 
+    // Facts that are false initially and added by no action, so they never
+    // hold in any reachable state
+    pub fn never_true_facts(&self) -> HashSet<u32> {
+        let mut facts: HashSet<u32> = (0..self.facts.count())
+            .filter(|x| !self.initial_state.contains(x))
+            .collect();
+        for task in self.tasks.get_all_tasks().iter() {
+            if let Task::Primitive(action) = &*task.borrow() {
+                for effect in action.add_effects.iter() {
+                    for fact in effect.iter() {
+                        facts.remove(fact);
+                    }
+                }
+            }
+        }
+        facts
+    }
+
+    // Whether each task of the domain can ever be fully decomposed, whatever
+    // the state
+    pub fn decomposable_tasks(&self) -> Vec<bool> {
+        self.tasks.decomposable_tasks(&self.never_true_facts())
+    }
+
+    // Whether the initial network and every method are totally ordered, in
+    // which case all reachable networks are sequences
+    pub fn is_totally_ordered(&self) -> bool {
+        self.init_tn.is_totally_ordered() && self.tasks.get_all_tasks().iter().all(|task| {
+            match &*task.borrow() {
+                Task::Primitive(_) => true,
+                Task::Compound(compound) => compound.methods.iter()
+                    .all(|method| method.decomposition.is_totally_ordered()),
+            }
+        })
+    }
+
     // Converts init tn into a single compound task
     pub fn collapse_tn(&mut self) {
         let rand_s: String = Alphanumeric.sample_string(&mut rand::thread_rng(), 4);
@@ -3,6 +3,7 @@ use std::fs;
 use serde::{Deserialize, Serialize};
 
 use super::FONDProblem;
+use crate::graph_lib::Graph;
 
 #[derive(Debug, Deserialize, Serialize)]
 struct RawDomain {
@@ -41,11 +42,20 @@ struct RawMethod {
     task: String,
     subtasks: Vec<String>,
     orderings: Vec<(u32, u32)>,
+    // optional, methods without a cost are handled by the search
+    cost: Option<u32>,
 }
 
-pub fn read_json_domain(path: &str) -> FONDProblem {
-    let istream = fs::read_to_string(path).expect("Unable to read file");
-    let domain: RawDomain = serde_json::from_str(&istream).unwrap();
+// Returns the list of problems found in the input if it can not be read
+pub fn read_json_domain(path: &str) -> Result<FONDProblem, Vec<String>> {
+    let istream = fs::read_to_string(path)
+        .map_err(|e| vec![format!("unable to read {}: {}", path, e)])?;
+    let domain: RawDomain = serde_json::from_str(&istream)
+        .map_err(|e| vec![format!("malformed domain: {}", e)])?;
+    let errors = domain.check();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
     // Process actions
     let mut actions = Vec::new();
     for (name, body) in domain.actions.into_iter() {
@@ -62,17 +72,75 @@ pub fn read_json_domain(path: &str) -> FONDProblem {
     // Processed methods
     let mut methods = vec![];
     for (name, method) in domain.methods.into_iter() {
-        let processed_m = (name, method.task, method.subtasks, method.orderings);
+        let processed_m = (name, method.task, method.subtasks, method.orderings, method.cost);
         methods.push(processed_m);
     }
-    FONDProblem::new(
+    Ok(FONDProblem::new(
         domain.facts,
         actions,
         methods,
         domain.tasks,
         domain.initial_state,
         domain.initial_abstract_task
-    )
+    ))
+}
+
+impl RawDomain {
+    // Reports references to undefined facts and tasks
+    fn check(&self) -> Vec<String> {
+        let mut errors = vec![];
+        let facts: HashSet<&String> = self.facts.iter().collect();
+        let compound: HashSet<&String> = self.tasks.iter().collect();
+        let undefined_facts = |context: &str, literals: &mut dyn Iterator<Item = &String>| -> Vec<String> {
+            literals.filter(|x| !facts.contains(x))
+                .map(|x| format!("{}: undefined fact {}", context, x))
+                .collect()
+        };
+        errors.extend(undefined_facts("initial state", &mut self.initial_state.iter()));
+        let mut action_names: Vec<&String> = self.actions.keys().collect();
+        action_names.sort();
+        for name in action_names {
+            let action = &self.actions[name];
+            errors.extend(undefined_facts(&format!("action {}", name), &mut action.precond.iter()));
+            if action.effects.is_empty() {
+                errors.push(format!("action {}: no effects", name));
+            }
+            for effect in action.effects.iter() {
+                match (effect.add_eff.get("unconditional"), effect.del_eff.get("unconditional")) {
+                    (Some(add), Some(del)) => {
+                        errors.extend(undefined_facts(&format!("action {}", name), &mut add.iter().chain(del.iter())));
+                    },
+                    _ => errors.push(format!("action {}: missing unconditional effects", name)),
+                }
+            }
+        }
+        let is_task = |x: &String| compound.contains(x) || self.actions.contains_key(x);
+        if !compound.contains(&self.initial_abstract_task) {
+            errors.push(format!("initial task {} is not a compound task", self.initial_abstract_task));
+        }
+        let mut method_names: Vec<&String> = self.methods.keys().collect();
+        method_names.sort();
+        for name in method_names {
+            let method = &self.methods[name];
+            if !compound.contains(&method.task) {
+                errors.push(format!("method {}: {} is not a compound task", name, method.task));
+            }
+            for x in method.subtasks.iter().filter(|x| !is_task(x)) {
+                errors.push(format!("method {}: undefined subtask {}", name, x));
+            }
+            let n = method.subtasks.len() as u32;
+            let out_of_range: Vec<&(u32, u32)> = method.orderings.iter().filter(|(x, y)| *x >= n || *y >= n).collect();
+            for (x, y) in out_of_range.iter() {
+                errors.push(format!("method {}: ordering ({}, {}) is out of range", name, x, y));
+            }
+            if out_of_range.is_empty() {
+                if let Err(e) = Graph::try_new((0..n).collect(), method.orderings.clone()) {
+                    errors.push(format!("method {}: {}", name, e));
+                }
+            }
+        }
+        errors
+    }
 }
 
 
@@ -84,7 +152,7 @@ mod test {
 
     #[test]
     pub fn correct_count_test() {
-        let domain = read_json_domain("src/domain_description/htn_domain/test_case.json");
+        let domain = read_json_domain("src/domain_description/htn_domain/test_case.json").unwrap();
         assert_eq!(domain.facts.count(), 21);
         let facts = [
             "+at_soil_sample[waypoint0]", "+at_rock_sample[waypoint0]",
@@ -118,4 +186,51 @@ mod test {
         assert_eq!(method_counter, 46);
         assert_eq!(domain.initial_state.len(), 11);
     }
+
+    #[test]
+    pub fn undefined_references_test() {
+        let json = r#"{
+            "state_features": ["a"], "mutex_groups": [], "further_strict_mutex_groups": [],
+            "further_non_strict_mutex_groups": [], "known_invariants": [],
+            "actions": {"p": {"cost": 1, "precond": ["b"], "effects": [
+                {"add_eff": {"unconditional": ["a"]}, "del_eff": {"unconditional": []}}
+            ]}},
+            "initial_state": ["a"], "goal": [], "initial_abstract_task": "t",
+            "methods": {"m": {"task": "t", "subtasks": ["p", "q"], "orderings": [[0, 2]]}},
+            "tasks": ["t"]
+        }"#;
+        let domain: RawDomain = serde_json::from_str(json).unwrap();
+        assert_eq!(domain.check(), vec![
+            "action p: undefined fact b".to_string(),
+            "method m: undefined subtask q".to_string(),
+            "method m: ordering (0, 2) is out of range".to_string(),
+        ]);
+        assert!(read_json_domain("missing.json").is_err());
+    }
+
+    #[test]
+    pub fn cyclic_orderings_test() {
+        let json = r#"{
+            "state_features": [], "mutex_groups": [], "further_strict_mutex_groups": [],
+            "further_non_strict_mutex_groups": [], "known_invariants": [],
+            "actions": {"p": {"cost": 1, "precond": [], "effects": [
+                {"add_eff": {"unconditional": []}, "del_eff": {"unconditional": []}}
+            ]}},
+            "initial_state": [], "goal": [], "initial_abstract_task": "t",
+            "methods": {"m": {"task": "t", "subtasks": ["p", "p", "p"], "orderings": [[0, 1], [1, 2], [2, 1]]}},
+            "tasks": ["t"]
+        }"#;
+        let domain: RawDomain = serde_json::from_str(json).unwrap();
+        assert_eq!(domain.check(), vec!["method m: orderings are cyclic (1 -> 2 -> 1)".to_string()]);
+    }
+
+    #[test]
+    pub fn method_cost_test() {
+        let method: RawMethod = serde_json::from_str(
+            r#"{"task": "t", "subtasks": ["p"], "orderings": [], "cost": 3}"#
+        ).unwrap();
+        assert_eq!(method.cost, Some(3));
+        let method: RawMethod = serde_json::from_str(r#"{"task": "t", "subtasks": ["p"], "orderings": []}"#).unwrap();
+        assert_eq!(method.cost, None);
+    }
 }
\ No newline at end of file
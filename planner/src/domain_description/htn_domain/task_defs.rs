@@ -79,6 +79,41 @@ impl DomainTasks {
         let n_tasks = self.list.len() as u32;
         id < n_tasks
     }
+
+    pub fn contains_name(&self, task: &str) -> bool {
+        self.ids.contains_key(task)
+    }
+
+    // Bottom-up fixpoint of the tasks that can be decomposed into primitive
+    // tasks, indexed by task id. Actions needing one of the "never_true"
+    // facts are never executable.
+    pub fn decomposable_tasks(&self, never_true: &HashSet<u32>) -> Vec<bool> {
+        let mut decomposable: Vec<bool> = self.list.iter()
+            .map(|task| match &*task.borrow() {
+                Task::Primitive(action) => action.pre_cond.is_disjoint(never_true),
+                Task::Compound(_) => false,
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, task) in self.list.iter().enumerate() {
+                if decomposable[id] {
+                    continue;
+                }
+                if let Task::Compound(compound) = &*task.borrow() {
+                    let possible = compound.methods.iter().any(|method| {
+                        method.decomposition.mappings.values().all(|x| decomposable[*x as usize])
+                    });
+                    if possible {
+                        decomposable[id] = true;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        decomposable
+    }
 }
 
 #[cfg(test)]
@@ -125,6 +160,36 @@ mod tests {
         assert_eq!(task_defs.get_task(3).borrow().get_name(), "PayBuilder");
     }
 
+    #[test]
+    pub fn decomposable_tasks_test() {
+        let action = |name: &str, pre: Vec<u32>| Task::Primitive(PrimitiveAction::new(
+            name.to_string(), 1, pre.into_iter().collect(), vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let tasks = vec![
+            action("p", vec![]),
+            action("q", vec![1]),
+            Task::Compound(CompoundTask::new("empty".to_string(), Vec::new())),
+            Task::Compound(CompoundTask::new("loop".to_string(), Vec::new())),
+            Task::Compound(CompoundTask::new("top".to_string(), Vec::new())),
+        ];
+        let domain = Rc::new(DomainTasks::new(tasks));
+        let network = |tasks: Vec<&str>| HTN::new(
+            (1..=tasks.len() as u32).collect(),
+            vec![],
+            domain.clone(),
+            tasks.iter().enumerate().map(|(i, x)| (i as u32 + 1, domain.get_id(x))).collect()
+        );
+        let domain = domain.add_methods(vec![
+            (domain.get_id("loop"), Method::new("loop_self".to_string(), network(vec!["loop"]))),
+            (domain.get_id("loop"), Method::new("loop_empty".to_string(), network(vec!["p", "empty"]))),
+            (domain.get_id("top"), Method::new("top_loop".to_string(), network(vec!["loop"]))),
+            (domain.get_id("top"), Method::new("top_q".to_string(), network(vec!["p", "q"]))),
+        ]);
+        assert_eq!(domain.decomposable_tasks(&HashSet::new()), vec![true, true, false, false, true]);
+        // q needs 1, which never holds
+        assert_eq!(domain.decomposable_tasks(&HashSet::from([1])), vec![true, false, false, false, false]);
+    }
+
     #[test]
     pub fn add_task_test() {
         let empty = HashSet::new();
@@ -218,7 +283,7 @@ mod tests {
             Task::Compound(CompoundTask { name, methods }) => {
                 assert_eq!(methods.len(), 1);
                 let m = &methods[0];
-                assert_eq!(m.decomposition.get_nodes().len(), 2);
+                assert_eq!(m.decomposition.count_tasks(), 2);
                 assert_eq!(m.decomposition.get_task(1).borrow().get_name(), String::from("ObtainPermit"));
                 assert_eq!(m.decomposition.get_task(2).borrow().get_name(), String::from("Construct"));
             },
@@ -229,11 +294,11 @@ mod tests {
             Task::Compound(CompoundTask { name, methods }) => {
                 assert_eq!(methods.len(), 2);
                 let m1 = &methods[0];
-                assert_eq!(m1.decomposition.get_nodes().len(), 1);
+                assert_eq!(m1.decomposition.count_tasks(), 1);
                 assert_eq!(m1.decomposition.get_task(1).borrow().get_name(), String::from("abstract_t"));
 
                 let m2 = &methods[1];
-                assert_eq!(m2.decomposition.get_nodes().len(), 1);
+                assert_eq!(m2.decomposition.count_tasks(), 1);
                 assert_eq!(m2.decomposition.get_task(1).borrow().get_name(), String::from("HireBuilder"));
             },
             _ => panic!("task is not compound")
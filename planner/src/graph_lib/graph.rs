@@ -1,78 +1,120 @@
-use std::{
-    collections::{HashMap, HashSet, BTreeSet},
-    iter::repeat,
-};
+use std::collections::{HashMap, HashSet, BTreeSet};
+use std::fmt;
 
-#[derive(Debug, Clone)]
+use super::{PersistentMap, PersistentSet};
+
+// Nodes and both adjacency lists are persistent, so removing a node or
+// adding a subgraph shares the untouched parts with the original graph and
+// costs time proportional to the edges involved. A node without successors
+// (resp. predecessors) has no entry in edges (resp. predecessors).
+#[derive(Debug, Clone, Default)]
 pub struct Graph {
-    pub nodes: BTreeSet<u32>,
-    pub edges: HashMap<u32, BTreeSet<u32>>,
+    nodes: PersistentSet,
+    edges: PersistentMap<BTreeSet<u32>>,
+    predecessors: PersistentMap<BTreeSet<u32>>,
+    // nodes with an in-degree of 0
+    unconstrained: PersistentSet,
+    // number of edges, kept so that chains are recognized in constant time
+    edge_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphError {
+    // the nodes of a cycle, each one preceding the next
+    Cycle(Vec<u32>),
+    NodeExists(u32),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Cycle(nodes) => {
+                let nodes: Vec<String> = nodes.iter().chain(nodes.first()).map(|x| x.to_string()).collect();
+                write!(f, "orderings are cyclic ({})", nodes.join(" -> "))
+            },
+            Self::NodeExists(id) => write!(f, "node {} already exists", id),
+        }
+    }
 }
 
 impl Graph {
     pub fn new(nodes: BTreeSet<u32>, orderings: Vec<(u32, u32)>) -> Self {
-        let mut edges: HashMap<u32, BTreeSet<u32>> = HashMap::with_capacity(nodes.len());
-        for edge in orderings.into_iter() {
-            match edge {
-                (x, y) => match edges.get_mut(&x) {
-                    Some(val) => {
-                        val.insert(y);
-                    }
-                    None => {
-                        edges.insert(x, BTreeSet::from([y]));
-                    }
-                },
-            }
-        }
-        Graph {
-            nodes,
-            edges: edges,
+        let mut graph = Graph {
+            unconstrained: nodes.iter().cloned().collect(),
+            nodes: nodes.into_iter().collect(),
+            edges: PersistentMap::new(),
+            predecessors: PersistentMap::new(),
+            edge_count: 0,
+        };
+        for (x, y) in orderings.into_iter() {
+            graph.add_edge(x, y);
         }
+        graph
+    }
+
+    // Same as new, but rejects cyclic orderings
+    pub fn try_new(nodes: BTreeSet<u32>, orderings: Vec<(u32, u32)>) -> Result<Self, GraphError> {
+        let graph = Graph::new(nodes, orderings);
+        graph.topological_order()?;
+        Ok(graph)
     }
 
     pub fn get_edges(&self) -> Vec<(u32, u32)> {
-        self.edges
-            .clone()
-            .into_iter()
-            .map(|(k, v)| repeat(k).zip(v).collect::<Vec<_>>())
-            .flatten()
-            .collect()
+        Graph::convert_edges_to_vec(&self.edges)
     }
 
-    pub fn convert_edges_to_vec(edges: &HashMap<u32, BTreeSet<u32>>) -> Vec<(u32, u32)> {
+    pub fn convert_edges_to_vec(edges: &PersistentMap<BTreeSet<u32>>) -> Vec<(u32, u32)> {
         edges
-            .clone()
-            .into_iter()
-            .map(|(k, v)| repeat(k).zip(v).collect::<Vec<_>>())
-            .flatten()
+            .iter()
+            .flat_map(|(k, v)| v.iter().map(|x| (*k, *x)))
             .collect()
     }
 
+    pub fn nodes(&self) -> impl Iterator<Item = &u32> + '_ {
+        self.nodes.iter()
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.nodes.contains(&id)
+    }
+
     pub fn count_nodes(&self) -> usize {
         self.nodes.len()
     }
 
+    pub fn count_edges(&self) -> usize {
+        self.edge_count
+    }
+
     pub fn get_neighbors(&self, id: u32) -> Option<&BTreeSet<u32>> {
         self.edges.get(&id)
     }
 
+    pub fn get_predecessors(&self, id: u32) -> Option<&BTreeSet<u32>> {
+        self.predecessors.get(&id)
+    }
+
+    pub fn in_degree(&self, id: u32) -> usize {
+        self.predecessors.get(&id).map_or(0, |x| x.len())
+    }
+
+    pub fn is_unconstrained(&self, id: u32) -> bool {
+        self.unconstrained.contains(&id)
+    }
+
+    pub fn count_unconstrained_nodes(&self) -> usize {
+        self.unconstrained.len()
+    }
+
     pub fn get_unconstrained_nodes(&self) -> BTreeSet<u32> {
-        let mut result = self.nodes.clone();
-        for k in self.edges.keys() {
-            for val in self.edges.get(k).unwrap() {
-                result.remove(val);
-            }
-        }
-        result
+        self.unconstrained.iter().cloned().collect()
     }
 
     pub fn get_incoming_edges(&self, id: u32) -> BTreeSet<u32> {
-        BTreeSet::from_iter(
-            self.edges
-                .iter()
-                .filter(|(_, v)| v.contains(&id))
-                .map(|(k, _)| *k),
-        )
+        match self.predecessors.get(&id) {
+            Some(x) => x.clone(),
+            None => BTreeSet::new()
+        }
     }
 
     pub fn get_outgoing_edges(&self, id: u32) -> BTreeSet<u32> {
@@ -82,81 +124,101 @@ impl Graph {
         }
     }
 
-    pub fn remove_node(&self, id: u32) -> Graph {
-        if !self.nodes.contains(&id) {
-            self.clone()
-        } else {
-            let mut new_nodes = self.nodes.clone();
-            new_nodes.remove(&id);
-            let mut new_edges = Vec::with_capacity(self.edges.keys().len());
-            for (key, values) in self.edges.iter() {
-                if *key == id {
-                    continue;
+    fn add_edge(&mut self, from: u32, to: u32) {
+        match self.edges.get_mut(&from) {
+            Some(targets) => {
+                if !targets.insert(to) {
+                    return;
                 }
-                for value in values {
-                    if *value == id {
-                        continue;
-                    } else {
-                        new_edges.push((*key, *value))
-                    }
+            },
+            None => {
+                self.edges.insert(from, BTreeSet::from([to]));
+            }
+        }
+        self.edge_count += 1;
+        match self.predecessors.get_mut(&to) {
+            Some(sources) => {
+                sources.insert(from);
+            },
+            None => {
+                self.predecessors.insert(to, BTreeSet::from([from]));
+                self.unconstrained.remove(&to);
+            }
+        }
+    }
+
+    fn remove_edge(&mut self, from: u32, to: u32) {
+        if let Some(targets) = self.edges.get_mut(&from) {
+            if targets.remove(&to) {
+                self.edge_count -= 1;
+            }
+            if targets.is_empty() {
+                self.edges.remove(&from);
+            }
+        }
+        if let Some(sources) = self.predecessors.get_mut(&to) {
+            sources.remove(&from);
+            if sources.is_empty() {
+                self.predecessors.remove(&to);
+                if self.nodes.contains(&to) {
+                    self.unconstrained.insert(to);
                 }
             }
-            Graph::new(new_nodes, new_edges)
         }
     }
 
+    pub fn remove_node(&self, id: u32) -> Graph {
+        let mut result = self.clone();
+        if !result.nodes.remove(&id) {
+            return result;
+        }
+        for node in self.get_incoming_edges(id) {
+            result.remove_edge(node, id);
+        }
+        for node in self.get_outgoing_edges(id) {
+            result.remove_edge(id, node);
+        }
+        result.unconstrained.remove(&id);
+        result
+    }
+
     pub fn add_subgraph(
         &self,
         subgraph: Graph,
         incoming_edges: BTreeSet<u32>,
         outgoing_edges: BTreeSet<u32>,
     ) -> Graph {
-        let nodes: BTreeSet<u32> = self.nodes.clone().union(&subgraph.nodes).cloned().collect();
-        if !self.nodes.intersection(&subgraph.nodes).collect::<BTreeSet<&u32>>().is_empty() {
+        if subgraph.nodes.iter().any(|x| self.nodes.contains(x)) {
             panic!("The IDs of subgraph and current graph are not disjoint")
         }
-        let mut orderings = self.edges.clone();
+        let mut result = self.clone();
+        for node in subgraph.nodes.iter() {
+            result.nodes.insert(*node);
+            result.unconstrained.insert(*node);
+        }
+        for (from, to) in subgraph.get_edges() {
+            result.add_edge(from, to);
+        }
 
         // Adding incoming edges
-        let unconstrained_nodes = subgraph.get_unconstrained_nodes();
         for node in incoming_edges.iter() {
-            match orderings.contains_key(node) {
-                false => orderings.insert(*node, unconstrained_nodes.clone()),
-                true => orderings.insert(
-                    *node,
-                    unconstrained_nodes
-                        .union(orderings.get(node).unwrap())
-                        .cloned()
-                        .collect(),
-                ),
-            };
+            for target in subgraph.unconstrained.iter() {
+                result.add_edge(*node, *target);
+            }
         }
 
         // Adding outgoing edges
-        let terminal_nodes: HashSet<u32> = subgraph
-            .nodes
-            .difference(&subgraph.edges.keys().cloned().collect())
-            .cloned()
-            .collect();
-        for node in terminal_nodes.iter() {
-            orderings.insert(*node, outgoing_edges.clone());
-        }
-
-        for (key, value) in subgraph.edges {
-            orderings.insert(key, value);
+        for node in subgraph.get_leaf_nodes().iter() {
+            for target in outgoing_edges.iter() {
+                result.add_edge(*node, *target);
+            }
         }
-
-        let orderings = orderings
-            .into_iter()
-            .map(|(k, v)| repeat(k).zip(v).collect::<Vec<_>>())
-            .flatten()
-            .collect();
-        Graph::new(nodes, orderings)
+        result
     }
 
     pub fn to_layers(&self) -> Vec<HashSet<u32>> {
         let mut result: Vec<HashSet<u32>> = Vec::new();
-        let mut prev_layer = HashSet::from_iter(self.get_unconstrained_nodes().iter().cloned());
+        let mut prev_layer = HashSet::from_iter(self.unconstrained.iter().cloned());
         result.push(prev_layer.clone());
         loop {
             let mut layer: HashSet<u32> = HashSet::new();
@@ -180,72 +242,162 @@ impl Graph {
     }
 
     pub fn get_leaf_nodes(&self) -> HashSet<u32> {
-        let mut leaves = self.nodes
-                        .iter()
-                        .filter(|x| !self.edges.contains_key(x))
-                        .cloned()
-                        .collect();
-        leaves
+        self.nodes
+            .iter()
+            .filter(|x| !self.edges.contains_key(x))
+            .cloned()
+            .collect()
     }
 
     pub fn add_node(&self,
         id: u32,
         incoming_edges: BTreeSet<u32>,
         outgoing_edges: BTreeSet<u32>
-    ) -> Result<Graph, &str> {
+    ) -> Result<Graph, GraphError> {
         if self.nodes.contains(&id) {
-            return Err("Node Already Exists")
+            Err(GraphError::NodeExists(id))
         } else {
-            let mut new_nodes = self.nodes.clone();
-            new_nodes.insert(id);
-            let mut new_edges = self.edges.clone();
-            if !incoming_edges.is_empty() {
-                for v1 in incoming_edges.iter(){
-                    if new_edges.contains_key(v1) {
-                        new_edges.get_mut(v1).unwrap().insert(id);
-                    } else {
-                        new_edges.insert(*v1, BTreeSet::from([id]));
-                    }
+            let mut result = self.clone();
+            result.nodes.insert(id);
+            result.unconstrained.insert(id);
+            for v1 in incoming_edges.iter() {
+                result.add_edge(*v1, id);
+            }
+            for v2 in outgoing_edges.iter() {
+                result.add_edge(id, *v2);
+            }
+            Ok(result)
+        }
+    }
+
+    // Kahn's algorithm on the in-degrees, returns a cycle if some nodes can
+    // not be ordered
+    pub fn topological_order(&self) -> Result<Vec<u32>, GraphError> {
+        let mut in_degree: HashMap<u32, usize> = self.predecessors.iter()
+            .map(|(node, sources)| (*node, sources.len()))
+            .collect();
+        let mut working_set: Vec<u32> = self.unconstrained.iter().cloned().collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node) = working_set.pop() {
+            order.push(node);
+            for target in self.edges.get(&node).into_iter().flatten() {
+                let degree = in_degree.get_mut(target).unwrap();
+                *degree -= 1;
+                if *degree == 0 && self.nodes.contains(target) {
+                    working_set.push(*target);
                 }
             }
-            if !outgoing_edges.is_empty() {
-                new_edges.insert(id, outgoing_edges);
+        }
+        if order.len() == self.nodes.len() {
+            return Ok(order);
+        }
+        // every remaining node has a remaining predecessor, following them
+        // eventually loops
+        let remaining = |x: &u32| self.nodes.contains(x) && in_degree.get(x).is_some_and(|d| *d > 0);
+        let mut node = *self.nodes.iter().find(|x| remaining(x)).unwrap();
+        let mut path = vec![];
+        while !path.contains(&node) {
+            path.push(node);
+            node = *self.predecessors[&node].iter().find(|x| remaining(x)).unwrap();
+        }
+        let start = path.iter().position(|x| *x == node).unwrap();
+        let mut cycle = path.split_off(start);
+        cycle.reverse();
+        // starting from the smallest node
+        let smallest = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+        cycle.rotate_left(smallest);
+        Err(GraphError::Cycle(cycle))
+    }
+
+    // The nodes in their order if the edges order them totally (with or
+    // without the transitive edges)
+    pub fn total_order(&self) -> Option<Vec<u32>> {
+        // every node but the last one needs a successor, and only the first
+        // one is unconstrained
+        if self.edges.len() + 1 < self.nodes.len() || self.unconstrained.len() > 1 {
+            return None;
+        }
+        let mut in_degree: HashMap<u32, usize> = self.predecessors.iter()
+            .map(|(node, sources)| (*node, sources.len()))
+            .collect();
+        let mut unconstrained: Vec<u32> = self.unconstrained.iter().cloned().collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while unconstrained.len() == 1 {
+            let node = unconstrained.pop().unwrap();
+            order.push(node);
+            for target in self.edges.get(&node).into_iter().flatten() {
+                let degree = in_degree.get_mut(target).unwrap();
+                *degree -= 1;
+                if *degree == 0 && self.nodes.contains(target) {
+                    unconstrained.push(*target);
+                }
             }
-            Ok(Graph {
-                nodes: new_nodes,
-                edges: new_edges
-            })
+        }
+        if order.len() == self.nodes.len() {
+            Some(order)
+        } else {
+            None
         }
     }
 
-    // change IDs based on a vec of partial (i.e., not complete set of nodes) new_ids
-    pub fn change_ids(&mut self, new_ids: &HashMap<u32,u32>) {
-        let mut edges = HashMap::new();
-        // remove previous nodes & edges
-        for (prev_id, new_id) in new_ids.iter() {
-            if !self.nodes.remove(&prev_id) {
-                panic!("Node not in the graph");
+    // Whether the edges form a single path through all the nodes, which is
+    // checked in constant time. For acyclic graphs without transitive edges,
+    // this is the case if and only if the order is total.
+    pub fn is_chain(&self) -> bool {
+        let n = self.nodes.len();
+        n <= 1 || (self.unconstrained.len() == 1 && self.edges.len() + 1 == n && self.edge_count + 1 == n)
+    }
+
+    // Nodes reachable from a node through at least one edge
+    fn reachable(&self, id: u32) -> BTreeSet<u32> {
+        let mut result = BTreeSet::new();
+        let mut working_set = vec![id];
+        while let Some(node) = working_set.pop() {
+            for target in self.edges.get(&node).into_iter().flatten() {
+                if result.insert(*target) {
+                    working_set.push(*target);
+                }
             }
-            if self.edges.contains_key(prev_id) {
-                let mut processed_edges = self.edges.remove(prev_id).unwrap();
-                processed_edges = processed_edges.iter().map(|x| {
-                    if new_ids.contains_key(x) {
-                        *new_ids.get(x).unwrap()
-                    } else {
-                        *x
-                    }
-                }).collect();
-                edges.insert(*new_id, processed_edges);
+        }
+        result
+    }
+
+    // The graph with an edge between every pair of nodes connected by a path
+    pub fn transitive_closure(&self) -> Graph {
+        let mut result = self.clone();
+        for node in self.nodes.iter() {
+            for target in self.reachable(*node) {
+                result.add_edge(*node, target);
             }
         }
-        // add edges
-        for (node, connections) in edges.into_iter() {
-            self.edges.insert(node, connections);
+        result
+    }
+
+    // The smallest graph with the same paths, which is only unique for
+    // acyclic graphs
+    pub fn transitive_reduction(&self) -> Result<Graph, GraphError> {
+        self.topological_order()?;
+        let mut result = self.clone();
+        for (node, targets) in self.edges.iter() {
+            let reachable: Vec<BTreeSet<u32>> = targets.iter().map(|x| self.reachable(*x)).collect();
+            for target in targets.iter() {
+                if reachable.iter().any(|x| x.contains(target)) {
+                    result.remove_edge(*node, *target);
+                }
+            }
         }
-        // add new nodes 
-        for (_, new_id) in new_ids.iter() {
-            self.nodes.insert(*new_id);
+        Ok(result)
+    }
+
+    // change IDs based on a vec of partial (i.e., not complete set of nodes) new_ids
+    pub fn change_ids(&mut self, new_ids: &HashMap<u32,u32>) {
+        if new_ids.keys().any(|x| !self.nodes.contains(x)) {
+            panic!("Node not in the graph");
         }
+        let new_id = |x: &u32| *new_ids.get(x).unwrap_or(x);
+        let nodes = self.nodes.iter().map(new_id).collect();
+        let orderings = self.get_edges().iter().map(|(x, y)| (new_id(x), new_id(y))).collect();
+        *self = Graph::new(nodes, orderings);
     }
 }
 
@@ -329,6 +481,81 @@ mod tests {
         assert_eq!(result[2], HashSet::from([4]));
     }
 
+    #[test]
+    pub fn total_order_test() {
+        let nodes: BTreeSet<u32> = BTreeSet::from([1, 2, 3, 4]);
+        let g = Graph::new(nodes.clone(), vec![(3, 1), (1, 4), (4, 2), (3, 2)]);
+        assert_eq!(g.total_order(), Some(vec![3, 1, 4, 2]));
+        // (3, 2) is implied by the other edges
+        assert!(!g.is_chain());
+        assert!(g.transitive_reduction().unwrap().is_chain());
+        let g = Graph::new(nodes.clone(), vec![(1, 3), (2, 3), (3, 4)]);
+        assert_eq!(g.total_order(), None);
+        assert!(!g.is_chain());
+        // one unconstrained node and one leaf
+        let g = Graph::new(nodes.clone(), vec![(1, 2), (1, 3), (2, 4), (3, 4)]);
+        assert_eq!(g.total_order(), None);
+        assert!(!g.is_chain());
+        assert_eq!(g.remove_node(3).count_edges(), 2);
+        assert!(g.remove_node(3).is_chain());
+        // cycle
+        let g = Graph::new(nodes, vec![(1, 2), (2, 3), (3, 4), (4, 2)]);
+        assert_eq!(g.total_order(), None);
+        assert_eq!(Graph::new(BTreeSet::new(), vec![]).total_order(), Some(vec![]));
+    }
+
+    #[test]
+    pub fn predecessors_test() {
+        let nodes: BTreeSet<u32> = BTreeSet::from([1, 2, 3, 4]);
+        let g = Graph::new(nodes, vec![(1, 3), (2, 3), (3, 4)]);
+        assert_eq!(g.in_degree(3), 2);
+        assert_eq!(g.get_predecessors(4), Some(&BTreeSet::from([3])));
+        assert!(g.is_unconstrained(1) && !g.is_unconstrained(3));
+        let subgraph = Graph::new(BTreeSet::from([5, 6, 7]), vec![(5, 6)]);
+        let result = g.remove_node(3).add_subgraph(subgraph, BTreeSet::from([1, 2]), BTreeSet::from([4]));
+        assert_eq!(result.get_incoming_edges(5), BTreeSet::from([1, 2]));
+        assert_eq!(result.get_incoming_edges(4), BTreeSet::from([6, 7]));
+        assert_eq!(result.get_unconstrained_nodes(), BTreeSet::from([1, 2]));
+        let result = result.remove_node(1).remove_node(2);
+        assert_eq!(result.get_unconstrained_nodes(), BTreeSet::from([5, 7]));
+        assert_eq!(result.count_unconstrained_nodes(), 2);
+        assert_eq!(result.in_degree(5), 0);
+        // the original graph is unchanged
+        assert_eq!(g.get_unconstrained_nodes(), BTreeSet::from([1, 2]));
+        assert_eq!(g.count_edges(), 3);
+    }
+
+    #[test]
+    pub fn cycle_test() {
+        let nodes: BTreeSet<u32> = BTreeSet::from([1, 2, 3, 4]);
+        let g = Graph::new(nodes.clone(), vec![(1, 2), (2, 3), (3, 4), (4, 2)]);
+        assert_eq!(g.topological_order(), Err(GraphError::Cycle(vec![2, 3, 4])));
+        assert!(Graph::try_new(nodes.clone(), vec![(1, 1)]).is_err());
+        let g = Graph::try_new(nodes, vec![(3, 1), (1, 2), (3, 4)]).unwrap();
+        let order = g.topological_order().unwrap();
+        let position = |x: u32| order.iter().position(|y| *y == x).unwrap();
+        assert_eq!(order.len(), 4);
+        assert!(position(3) < position(1) && position(1) < position(2) && position(3) < position(4));
+        assert_eq!(g.add_node(4, BTreeSet::new(), BTreeSet::new()).unwrap_err(), GraphError::NodeExists(4));
+    }
+
+    #[test]
+    pub fn transitivity_test() {
+        let nodes: BTreeSet<u32> = BTreeSet::from([1, 2, 3, 4]);
+        let g = Graph::new(nodes, vec![(1, 2), (2, 3), (1, 3), (3, 4), (1, 4)]);
+        let closure = g.transitive_closure();
+        let mut edges = closure.get_edges();
+        edges.sort();
+        assert_eq!(edges, vec![(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)]);
+        let reduction = closure.transitive_reduction().unwrap();
+        let mut edges = reduction.get_edges();
+        edges.sort();
+        assert_eq!(edges, vec![(1, 2), (2, 3), (3, 4)]);
+        assert_eq!(reduction.in_degree(3), 1);
+        let cyclic = Graph::new(BTreeSet::from([1, 2]), vec![(1, 2), (2, 1)]);
+        assert!(cyclic.transitive_reduction().is_err());
+    }
+
     #[test]
     pub fn leaf_nodes_test() {
         let nodes: BTreeSet<u32> = BTreeSet::from([1, 2, 3, 4, 5]);
@@ -1,5 +1,7 @@
 mod graph;
+mod persistent_map;
 mod vf2;
 
-pub use graph::Graph;
+pub use graph::{Graph, GraphError};
+pub use persistent_map::{PersistentMap, PersistentSet};
 pub use vf2::vf2_isomorphism;
\ No newline at end of file
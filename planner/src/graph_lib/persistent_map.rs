@@ -0,0 +1,411 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Index;
+use std::rc::Rc;
+
+const BITS: u32 = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: u32 = WIDTH as u32 - 1;
+
+// Map from node ids to values stored as a trie of 32-way nodes. Cloning a
+// map only copies the root pointer and an update copies the nodes on the
+// path to the key, so successors of a network share everything they did
+// not change.
+#[derive(Clone)]
+pub struct PersistentMap<V> {
+    root: Option<Node<V>>,
+    // shift of the root level, the leaves are at shift 0
+    shift: u32,
+    len: usize,
+}
+
+// A node is a shared pointer to its slots, cloning it does not copy them
+#[derive(Clone)]
+enum Node<V> {
+    Branch(Rc<[Option<Node<V>>; WIDTH]>),
+    // keys are kept along the values so that they can be borrowed
+    Leaf(Rc<[Option<(u32, V)>; WIDTH]>),
+}
+
+impl<V> Node<V> {
+    fn empty(shift: u32) -> Node<V> {
+        if shift == 0 {
+            Node::Leaf(Rc::new(std::array::from_fn(|_| None)))
+        } else {
+            Node::Branch(Rc::new(std::array::from_fn(|_| None)))
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Node::Branch(children) => children.iter().all(|x| x.is_none()),
+            Node::Leaf(values) => values.iter().all(|x| x.is_none()),
+        }
+    }
+}
+
+fn slot(key: u32, shift: u32) -> usize {
+    ((key >> shift) & MASK) as usize
+}
+
+impl<V> Default for PersistentMap<V> {
+    fn default() -> Self {
+        PersistentMap { root: None, shift: 0, len: 0 }
+    }
+}
+
+impl<V> PersistentMap<V> {
+    pub fn new() -> Self {
+        PersistentMap::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // whether the keys fit under the current root
+    fn covers(&self, key: u32) -> bool {
+        (key as u64) < (1u64 << (self.shift + BITS))
+    }
+
+    pub fn get(&self, key: &u32) -> Option<&V> {
+        if !self.covers(*key) {
+            return None;
+        }
+        let mut node = self.root.as_ref()?;
+        let mut shift = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    node = children[slot(*key, shift)].as_ref()?;
+                    shift -= BITS;
+                },
+                Node::Leaf(values) => return values[slot(*key, 0)].as_ref().map(|(_, value)| value),
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &u32) -> bool {
+        self.get(key).is_some()
+    }
+
+    // the largest key, found by following the last child of every level
+    pub fn max_key(&self) -> Option<u32> {
+        let mut node = self.root.as_ref()?;
+        loop {
+            match node {
+                Node::Branch(children) => node = children.iter().rev().find_map(|x| x.as_ref())?,
+                Node::Leaf(values) => return values.iter().rev().find_map(|x| x.as_ref().map(|(key, _)| *key)),
+            }
+        }
+    }
+
+    // Number of leaves of the trie that are shared with "other" (their
+    // entries were not updated since one of the maps was cloned from the
+    // other), and total number of leaves
+    pub fn shared_leaves(&self, other: &Self) -> (usize, usize) {
+        let theirs: HashSet<*const ()> = other.leaves().collect();
+        let ours: Vec<*const ()> = self.leaves().collect();
+        (ours.iter().filter(|x| theirs.contains(x)).count(), ours.len())
+    }
+
+    fn leaves(&self) -> impl Iterator<Item = *const ()> + '_ {
+        let mut stack: Vec<&Node<V>> = self.root.iter().collect();
+        std::iter::from_fn(move || {
+            while let Some(node) = stack.pop() {
+                match node {
+                    Node::Branch(children) => stack.extend(children.iter().flatten()),
+                    Node::Leaf(values) => return Some(Rc::as_ptr(values) as *const ()),
+                }
+            }
+            None
+        })
+    }
+
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            stack: self.root.as_ref().map(|root| (root, 0)).into_iter().collect(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &u32> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<V: Clone> PersistentMap<V> {
+    pub fn insert(&mut self, key: u32, value: V) -> Option<V> {
+        if self.root.is_none() {
+            self.shift = 0;
+        }
+        while !self.covers(key) {
+            // the current root becomes the first child of a new level
+            let mut children: [Option<Node<V>>; WIDTH] = std::array::from_fn(|_| None);
+            children[0] = self.root.take();
+            self.root = Some(Node::Branch(Rc::new(children)));
+            self.shift += BITS;
+        }
+        let shift = self.shift;
+        let root = self.root.get_or_insert_with(|| Node::empty(shift));
+        let previous = PersistentMap::insert_at(root, shift, key, value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    fn insert_at(node: &mut Node<V>, shift: u32, key: u32, value: V) -> Option<V> {
+        match node {
+            Node::Branch(children) => {
+                let child = Rc::make_mut(children)[slot(key, shift)]
+                    .get_or_insert_with(|| Node::empty(shift - BITS));
+                PersistentMap::insert_at(child, shift - BITS, key, value)
+            },
+            Node::Leaf(values) => Rc::make_mut(values)[slot(key, 0)].replace((key, value)).map(|(_, value)| value),
+        }
+    }
+
+    // Only the nodes on the path to the key are copied, and only if they are
+    // shared with another map
+    pub fn get_mut(&mut self, key: &u32) -> Option<&mut V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let mut node = self.root.as_mut().unwrap();
+        let mut shift = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    node = Rc::make_mut(children)[slot(*key, shift)].as_mut().unwrap();
+                    shift -= BITS;
+                },
+                Node::Leaf(values) => return Rc::make_mut(values)[slot(*key, 0)].as_mut().map(|(_, value)| value),
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &u32) -> Option<V> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        let root = self.root.as_mut().unwrap();
+        let previous = PersistentMap::remove_at(root, self.shift, *key);
+        self.len -= 1;
+        if self.len == 0 {
+            self.root = None;
+            self.shift = 0;
+        }
+        previous
+    }
+
+    fn remove_at(node: &mut Node<V>, shift: u32, key: u32) -> Option<V> {
+        match node {
+            Node::Branch(children) => {
+                let child = &mut Rc::make_mut(children)[slot(key, shift)];
+                let previous = PersistentMap::remove_at(child.as_mut().unwrap(), shift - BITS, key);
+                // empty nodes are dropped so that iterating stays proportional
+                // to the number of keys
+                if child.as_ref().unwrap().is_empty() {
+                    *child = None;
+                }
+                previous
+            },
+            Node::Leaf(values) => Rc::make_mut(values)[slot(key, 0)].take().map(|(_, value)| value),
+        }
+    }
+}
+
+// Visits the entries in increasing order of keys
+pub struct Iter<'a, V> {
+    // (node, next slot)
+    stack: Vec<(&'a Node<V>, usize)>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a u32, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (node, next) = self.stack.last_mut()?;
+            if *next == WIDTH {
+                self.stack.pop();
+                continue;
+            }
+            let i = *next;
+            *next += 1;
+            match *node {
+                Node::Branch(children) => {
+                    if let Some(child) = children[i].as_ref() {
+                        self.stack.push((child, 0));
+                    }
+                },
+                Node::Leaf(values) => {
+                    if let Some((key, value)) = values[i].as_ref() {
+                        return Some((key, value));
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a PersistentMap<V> {
+    type Item = (&'a u32, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<V: Clone> FromIterator<(u32, V)> for PersistentMap<V> {
+    fn from_iter<I: IntoIterator<Item = (u32, V)>>(iter: I) -> Self {
+        let mut map = PersistentMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+impl<V> Index<&u32> for PersistentMap<V> {
+    type Output = V;
+
+    fn index(&self, key: &u32) -> &V {
+        self.get(key).expect("key not in the map")
+    }
+}
+
+impl<V: PartialEq> PartialEq for PersistentMap<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<V: Eq> Eq for PersistentMap<V> {}
+
+impl<V: fmt::Debug> fmt::Debug for PersistentMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Set of node ids with the same sharing as PersistentMap
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct PersistentSet {
+    map: PersistentMap<()>,
+}
+
+impl PersistentSet {
+    pub fn new() -> Self {
+        PersistentSet::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn contains(&self, id: &u32) -> bool {
+        self.map.contains_key(id)
+    }
+
+    // returns whether the id was not in the set
+    pub fn insert(&mut self, id: u32) -> bool {
+        self.map.insert(id, ()).is_none()
+    }
+
+    // returns whether the id was in the set
+    pub fn remove(&mut self, id: &u32) -> bool {
+        self.map.remove(id).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &u32> + '_ {
+        self.map.keys()
+    }
+}
+
+impl FromIterator<u32> for PersistentSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> Self {
+        PersistentSet { map: iter.into_iter().map(|id| (id, ())).collect() }
+    }
+}
+
+impl fmt::Debug for PersistentSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[test]
+    pub fn map_operations_test() {
+        let mut map = PersistentMap::new();
+        let mut expected = BTreeMap::new();
+        // keys spread over several levels of the trie
+        for key in [3, 40, 1, 1025, 33, 70000, 0, u32::MAX] {
+            assert_eq!(map.insert(key, key / 2), None);
+            expected.insert(key, key / 2);
+        }
+        assert_eq!(map.insert(40, 1), Some(20));
+        expected.insert(40, 1);
+        assert_eq!(map.len(), expected.len());
+        assert_eq!(map.get(&2), None);
+        assert_eq!(map[&1025], 512);
+        assert_eq!(map.max_key(), Some(u32::MAX));
+        assert!(map.iter().map(|(k, v)| (*k, *v)).eq(expected.clone().into_iter()));
+        assert_eq!(map.remove(&70000), Some(35000));
+        assert_eq!(map.remove(&70000), None);
+        expected.remove(&70000);
+        *map.get_mut(&3).unwrap() = 7;
+        expected.insert(3, 7);
+        assert!(map.iter().map(|(k, v)| (*k, *v)).eq(expected.into_iter()));
+        for key in [3, 40, 1, 1025, 33, 0, u32::MAX] {
+            map.remove(&key);
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.max_key(), None);
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    pub fn sharing_test() {
+        let map: PersistentMap<u32> = (0..100).map(|x| (x, x)).collect();
+        let mut copy = map.clone();
+        copy.insert(5, 0);
+        copy.remove(&99);
+        // the originals are not affected
+        assert_eq!(map[&5], 5);
+        assert_eq!(map.len(), 100);
+        assert_eq!(copy.len(), 99);
+        // only the leaves containing 5 and 99 were copied
+        let children = |map: &PersistentMap<u32>| match map.root.as_ref() {
+            Some(Node::Branch(children)) => children.clone(),
+            _ => panic!("100 keys need two levels"),
+        };
+        let (original, updated) = (children(&map), children(&copy));
+        for i in 0..WIDTH {
+            let shared = match (&original[i], &updated[i]) {
+                (Some(Node::Leaf(x)), Some(Node::Leaf(y))) => Rc::ptr_eq(x, y),
+                _ => false,
+            };
+            assert_eq!(shared, i == 1 || i == 2, "slot {}", i);
+        }
+    }
+}
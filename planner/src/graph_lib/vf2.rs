@@ -3,30 +3,36 @@ use super::*;
 use std::collections::{HashMap,BTreeSet, HashSet};
 
 
-pub fn vf2_isomorphism(g1: &Graph, g2: &Graph, l1: &HashMap<u32, u32>, l2: &HashMap<u32, u32>) -> bool {
+pub fn vf2_isomorphism(g1: &Graph, g2: &Graph, l1: &PersistentMap<u32>, l2: &PersistentMap<u32>) -> bool {
     let mut fringe: Vec<Vec<(u32,u32)>> = Vec::new();
     // assert total isomorphism
-    if l1.len() != l2.len() {
+    if l1.len() != l2.len() || g1.count_edges() != g2.count_edges() {
         return false
     }
     fringe.push(vec![]);
     while !fringe.is_empty() {
         let state = fringe.pop().unwrap();
-        if state.len() == g1.nodes.len() {
+        if state.len() == g1.count_nodes() {
             return true;
         }
         // Compute P
         let mut p: Vec<(u32, u32)> = vec![];
-        // immediate predecessors of state
+        let mapped_1: HashSet<u32> = state.iter().map(|(n1, _)| *n1).collect();
+        let mapped_2: HashSet<u32> = state.iter().map(|(_, n2)| *n2).collect();
+        // unmapped immediate predecessors of state
         let in_1: HashSet<u32> = state.iter()
-            .map(|(n1, _)| g1.get_incoming_edges(*n1)).flatten().collect();
+            .flat_map(|(n1, _)| g1.get_predecessors(*n1).into_iter().flatten())
+            .filter(|x| !mapped_1.contains(x)).cloned().collect();
         let in_2: HashSet<u32> = state.iter()
-            .map(|(_, n2)| g2.get_incoming_edges(*n2)).flatten().collect();
-        // immediate predecessors of state
+            .flat_map(|(_, n2)| g2.get_predecessors(*n2).into_iter().flatten())
+            .filter(|x| !mapped_2.contains(x)).cloned().collect();
+        // unmapped immediate successors of state
         let out_1: HashSet<u32> = state.iter()
-            .map(|(n1, _)| g1.get_outgoing_edges(*n1)).flatten().collect();
+            .flat_map(|(n1, _)| g1.get_neighbors(*n1).into_iter().flatten())
+            .filter(|x| !mapped_1.contains(x)).cloned().collect();
         let out_2: HashSet<u32> = state.iter()
-            .map(|(_, n2)| g2.get_outgoing_edges(*n2)).flatten().collect();
+            .flat_map(|(_, n2)| g2.get_neighbors(*n2).into_iter().flatten())
+            .filter(|x| !mapped_2.contains(x)).cloned().collect();
         // rule based construction
         // // if both "out"s are non-empty
         if (out_1.len() > 0) && (out_2.len() > 0) {
@@ -48,12 +54,12 @@ pub fn vf2_isomorphism(g1: &Graph, g2: &Graph, l1: &HashMap<u32, u32>, l2: &Hash
             }
         // // if both are empty
         } else {
-            let p1_list: Vec<u32> = g1.nodes.difference(
-                &BTreeSet::from_iter(state.iter().map(|(x, _)| *x))
-            ).cloned().collect();
-            let p2_list: Vec<u32> = g2.nodes.difference(
-                &BTreeSet::from_iter(state.iter().map(|(_, x)| *x))
-            ).cloned().collect();
+            let p1_list: Vec<u32> = g1.nodes()
+                .filter(|x| !mapped_1.contains(x))
+                .cloned().collect();
+            let p2_list: Vec<u32> = g2.nodes()
+                .filter(|x| !mapped_2.contains(x))
+                .cloned().collect();
             for p1 in p1_list.iter() {
                 for p2 in p2_list.iter() {
                     if l1.get(p1).unwrap() == l2.get(p2).unwrap() {
@@ -63,7 +69,13 @@ pub fn vf2_isomorphism(g1: &Graph, g2: &Graph, l1: &HashMap<u32, u32>, l2: &Hash
             }
         }
         for (n, m) in p.iter() {
-            if state.contains(&(*n, *m)) {
+            if mapped_1.contains(n) || mapped_2.contains(m) {
+                continue;
+            }
+            // the edges with the mapped nodes correspond
+            let edge_1 = |x: u32, y: u32| g1.get_neighbors(x).is_some_and(|z| z.contains(&y));
+            let edge_2 = |x: u32, y: u32| g2.get_neighbors(x).is_some_and(|z| z.contains(&y));
+            if state.iter().any(|(a, b)| edge_1(*a, *n) != edge_2(*b, *m) || edge_1(*n, *a) != edge_2(*m, *b)) {
                 continue;
             }
             // assert same predecesssors labels
@@ -109,18 +121,18 @@ mod tests {
     #[test]
     pub fn vf2_correctness_test() {
         let (g1, g2, g3, g4) = generate_test_instances();
-        let l1 = HashMap::from([(1,1), (2,2), (3,3), (4,2)]);
-        let l2 = HashMap::from([(4,1), (5,2), (6,3), (7,2)]);
+        let l1 = PersistentMap::from_iter([(1,1), (2,2), (3,3), (4,2)]);
+        let l2 = PersistentMap::from_iter([(4,1), (5,2), (6,3), (7,2)]);
         let result = vf2_isomorphism(&g1, &g2, &l1, &l2);
-        let l2_2 = HashMap::from([(4,1), (5,2), (6,3), (7,4)]);
+        let l2_2 = PersistentMap::from_iter([(4,1), (5,2), (6,3), (7,4)]);
         let result = vf2_isomorphism(&g1, &g2, &l1, &l2_2);
         assert_eq!(result, false);
-        let l3 = HashMap::from([(1,1), (2,3), (3,3)]);
+        let l3 = PersistentMap::from_iter([(1,1), (2,3), (3,3)]);
         let result = vf2_isomorphism(&g1, &g3, &l1, &l3);
         assert_eq!(result, false);
         let result = vf2_isomorphism(&g3, &g1, &l3, &l1);
         assert_eq!(result, false);
-        let l4 = HashMap::from([(4,1), (5,2), (6,3), (7,2)]);
+        let l4 = PersistentMap::from_iter([(4,1), (5,2), (6,3), (7,2)]);
         let result = vf2_isomorphism(&g2, &g4, &l2, &l4);
         assert_eq!(result, false);
     }
@@ -129,8 +141,8 @@ mod tests {
     pub fn vf2_correctness_test2() {
         let g1 = Graph::new(BTreeSet::from([5,6]), vec![(5,6)]);
         let g2 = Graph::new(BTreeSet::from([1,2]), vec![(1,2)]);
-        let l1 = HashMap::from([(5,1), (6,2)]);
-        let l2 = HashMap::from([(1,1), (2,2)]);
+        let l1 = PersistentMap::from_iter([(5,1), (6,2)]);
+        let l2 = PersistentMap::from_iter([(1,1), (2,2)]);
         assert_eq!(vf2_isomorphism(&g1, &g2, &l1, &l2), true);
     }
 }
\ No newline at end of file
@@ -1,42 +1,12 @@
 use super::*;
-use std::{collections::{HashSet, HashMap}, iter::repeat};
-use crate::task_network::Applicability;
+use std::collections::HashSet;
 
 pub fn h_add(domain: &ClassicalDomain, state: &HashSet<u32>, goal: &HashSet<u32>) -> f32 {
-    let mut facts: HashMap<u32, u32> = state.iter().cloned().zip(repeat(0 as u32)).collect();
-    let mut actions: HashMap<usize, u32> = HashMap::new();
-    let mut open_goals: HashSet<u32> = goal.difference(state).cloned().collect();
-    while !open_goals.is_empty() {
-        let mut changed = false;
-        let all_facts: HashSet<u32> = facts.keys().cloned().collect();
-        for (i, action) in domain.actions.iter().enumerate() {
-            if action.is_applicable(&all_facts) && (!actions.contains_key(&i)) {
-                let pre_cond = &action.pre_cond;
-                let mut action_weight = 1;
-                for (id, weight) in facts.iter() {
-                    if pre_cond.contains(id) {
-                        action_weight += weight
-                    }
-                }
-                actions.insert(i, action_weight);
-                for effect in action.add_effects[0].iter() {
-                    open_goals.remove(effect);
-                    if !facts.contains_key(effect) {
-                        facts.insert(*effect, action_weight);
-                    }
-                }
-                changed = true
-            }
-        }
-        if changed == false {
-            return f32::INFINITY;
-        }
+    let exploration = RelaxedExploration::explore(domain, state, goal, Aggregation::Sum, true);
+    match exploration.goal_cost(goal, Aggregation::Sum) {
+        Some(cost) => cost as f32,
+        None => f32::INFINITY,
     }
-    let mut total_cost = 0;
-    for f in goal.iter() {
-        total_cost += facts.get(f).unwrap();
-    }
-    total_cost as f32
 }
 
 #[cfg(test)]
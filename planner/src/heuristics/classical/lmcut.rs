@@ -0,0 +1,228 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use super::*;
+
+// LM-cut (Helmert & Domshlak, 2009) over the delete relaxation of the
+// domain. Every action is assumed to be deterministic (only the first
+// outcome is used). The value never exceeds the optimal plan cost.
+pub fn h_lmcut(domain: &ClassicalDomain, state: &HashSet<u32>, goal: &HashSet<u32>) -> f32 {
+    match lmcut_landmarks(domain, state, goal) {
+        Some(landmarks) => landmarks.iter().map(|(_, cost)| cost).sum::<u64>() as f32,
+        None => f32::INFINITY,
+    }
+}
+
+// Disjunctive action landmarks (indices of the actions) found by LM-cut
+// together with the cost assigned to each of them, None if the goal is
+// unreachable
+pub fn lmcut_landmarks(domain: &ClassicalDomain, state: &HashSet<u32>, goal: &HashSet<u32>) -> Option<Vec<(Vec<usize>, u64)>> {
+    let mut landmarks = vec![];
+    if goal.is_subset(state) {
+        return Some(landmarks);
+    }
+    let mut task = CutTask::new(domain, state, goal);
+    loop {
+        let (fact_costs, supporters) = task.h_max();
+        match fact_costs[task.goal_fact] {
+            None => return None,
+            Some(0) => break,
+            Some(_) => {}
+        }
+        let cut = task.find_cut(&supporters);
+        let min_cost = cut.iter().map(|a| task.costs[*a]).min().unwrap();
+        for a in cut.iter() {
+            task.costs[*a] -= min_cost;
+        }
+        landmarks.push((cut, min_cost));
+    }
+    Some(landmarks)
+}
+
+// Dense encoding of the relaxed task with an artificial initial fact (the
+// precondition of actions without preconditions) and an artificial goal
+// fact (achieved by a zero-cost goal action)
+struct CutTask {
+    pre: Vec<Vec<usize>>,
+    add: Vec<Vec<usize>>,
+    costs: Vec<u64>,
+    // fact -> actions having it as precondition
+    precondition_of: Vec<Vec<usize>>,
+    // fact -> actions adding it
+    achievers: Vec<Vec<usize>>,
+    // state facts together with the artificial initial fact
+    init: Vec<usize>,
+    goal_fact: usize,
+}
+
+impl CutTask {
+    fn new(domain: &ClassicalDomain, state: &HashSet<u32>, goal: &HashSet<u32>) -> CutTask {
+        let max_fact = domain.actions.iter()
+            .flat_map(|a| a.pre_cond.iter().chain(a.add_effects[0].iter()))
+            .chain(state.iter())
+            .chain(goal.iter())
+            .max()
+            .map_or(0, |x| *x as usize + 1);
+        let init_fact = max_fact;
+        let goal_fact = max_fact + 1;
+        let mut pre = vec![];
+        let mut add = vec![];
+        let mut costs = vec![];
+        for action in domain.actions.iter() {
+            let mut action_pre: Vec<usize> = action.pre_cond.iter().map(|x| *x as usize).collect();
+            if action_pre.is_empty() {
+                action_pre.push(init_fact);
+            }
+            pre.push(action_pre);
+            add.push(action.add_effects[0].iter().map(|x| *x as usize).collect());
+            costs.push(action.cost as u64);
+        }
+        pre.push(goal.iter().map(|x| *x as usize).collect());
+        add.push(vec![goal_fact]);
+        costs.push(0);
+        let mut precondition_of = vec![vec![]; goal_fact + 1];
+        let mut achievers = vec![vec![]; goal_fact + 1];
+        for (i, (action_pre, action_add)) in pre.iter().zip(add.iter()).enumerate() {
+            for f in action_pre.iter() {
+                precondition_of[*f].push(i);
+            }
+            for f in action_add.iter() {
+                achievers[*f].push(i);
+            }
+        }
+        let mut init: Vec<usize> = state.iter().map(|x| *x as usize).collect();
+        init.push(init_fact);
+        CutTask { pre, add, costs, precondition_of, achievers, init, goal_fact }
+    }
+
+    // h_max values of facts (None if unreachable) and the supporter of each
+    // reachable action, i.e. a precondition with maximal h_max value
+    fn h_max(&self) -> (Vec<Option<u64>>, Vec<Option<usize>>) {
+        let mut fact_costs = vec![None; self.goal_fact + 1];
+        let mut supporters = vec![None; self.pre.len()];
+        let mut unsatisfied: Vec<usize> = self.pre.iter().map(|x| x.len()).collect();
+        let mut queue: BinaryHeap<Reverse<(u64, usize)>> = self.init.iter().map(|f| Reverse((0, *f))).collect();
+        while let Some(Reverse((cost, fact))) = queue.pop() {
+            if fact_costs[fact].is_some() {
+                continue;
+            }
+            fact_costs[fact] = Some(cost);
+            for a in self.precondition_of[fact].iter() {
+                unsatisfied[*a] -= 1;
+                // facts are popped in order of cost, so the last precondition
+                // to be reached has the maximal cost
+                if unsatisfied[*a] == 0 {
+                    supporters[*a] = Some(fact);
+                    let action_cost = cost + self.costs[*a];
+                    for effect in self.add[*a].iter() {
+                        if fact_costs[*effect].is_none() {
+                            queue.push(Reverse((action_cost, *effect)));
+                        }
+                    }
+                }
+            }
+        }
+        (fact_costs, supporters)
+    }
+
+    // Actions leading from the part of the justification graph reachable from
+    // the initial facts into the goal zone (facts from which the goal fact is
+    // reachable with zero-cost actions)
+    fn find_cut(&self, supporters: &[Option<usize>]) -> Vec<usize> {
+        let mut goal_zone = vec![false; self.goal_fact + 1];
+        goal_zone[self.goal_fact] = true;
+        let mut stack = vec![self.goal_fact];
+        while let Some(fact) = stack.pop() {
+            for a in self.achievers[fact].iter() {
+                if let (0, Some(supporter)) = (self.costs[*a], supporters[*a]) {
+                    if !goal_zone[supporter] {
+                        goal_zone[supporter] = true;
+                        stack.push(supporter);
+                    }
+                }
+            }
+        }
+        let mut reached = vec![false; self.goal_fact + 1];
+        let mut stack = vec![];
+        for f in self.init.iter() {
+            if !reached[*f] {
+                reached[*f] = true;
+                stack.push(*f);
+            }
+        }
+        let mut cut = vec![];
+        while let Some(fact) = stack.pop() {
+            for a in self.precondition_of[fact].iter() {
+                if supporters[*a] != Some(fact) {
+                    continue;
+                }
+                if self.add[*a].iter().any(|x| goal_zone[*x]) {
+                    cut.push(*a);
+                }
+                for effect in self.add[*a].iter() {
+                    if !goal_zone[*effect] && !reached[*effect] {
+                        reached[*effect] = true;
+                        stack.push(*effect);
+                    }
+                }
+            }
+        }
+        cut
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::heuristics::PrimitiveAction;
+    use crate::domain_description::Facts;
+
+    fn action(name: &str, cost: u32, pre: Vec<u32>, add: Vec<u32>) -> PrimitiveAction {
+        PrimitiveAction::new(
+            name.to_string(),
+            cost,
+            pre.into_iter().collect(),
+            vec![add.into_iter().collect()],
+            vec![HashSet::new()]
+        )
+    }
+
+    fn facts(n: u32) -> Facts {
+        Facts::new((0..n).map(|x| x.to_string()).collect())
+    }
+
+    #[test]
+    pub fn h_val_test() {
+        // every action is a landmark
+        let domain = ClassicalDomain::new(facts(5), vec![
+            action("p1", 1, vec![0], vec![1]),
+            action("p2", 1, vec![1], vec![2]),
+            action("p3", 1, vec![1], vec![3]),
+            action("p4", 1, vec![1, 2, 3], vec![4]),
+        ]);
+        assert_eq!(h_lmcut(&domain, &HashSet::from([0]), &HashSet::from([4])), 4.0);
+        assert_eq!(h_lmcut(&domain, &HashSet::from([0, 1]), &HashSet::from([1])), 0.0);
+        assert_eq!(h_lmcut(&domain, &HashSet::from([1]), &HashSet::from([4])), 3.0);
+        assert_eq!(h_lmcut(&domain, &HashSet::from([2]), &HashSet::from([4])), f32::INFINITY);
+    }
+
+    #[test]
+    pub fn disjunctive_landmark_test() {
+        // the goal is reached by either a (cost 2) or b then c (cost 3 + 1),
+        // d is required in both cases
+        let domain = ClassicalDomain::new(facts(4), vec![
+            action("a", 2, vec![], vec![1]),
+            action("b", 3, vec![], vec![2]),
+            action("c", 1, vec![2], vec![1]),
+            action("d", 5, vec![], vec![3]),
+            action("free", 0, vec![1, 3], vec![0]),
+        ]);
+        assert_eq!(h_lmcut(&domain, &HashSet::new(), &HashSet::from([0])), 7.0);
+        assert_eq!(h_lmcut(&domain, &HashSet::from([2]), &HashSet::from([0])), 6.0);
+        let mut landmarks = lmcut_landmarks(&domain, &HashSet::from([2]), &HashSet::from([0])).unwrap();
+        for (cut, _) in landmarks.iter_mut() {
+            cut.sort();
+        }
+        landmarks.sort();
+        assert_eq!(landmarks, vec![(vec![0, 2], 1), (vec![3], 5)]);
+    }
+}
@@ -1,15 +1,13 @@
 use std::collections::HashSet;
 use super::*;
 
+// Cost of the most expensive goal fact, where reaching a fact costs the
+// cheapest achiever's cost plus its most expensive precondition
 pub fn h_max(domain: &ClassicalDomain, state: &HashSet<u32>, goal: &HashSet<u32>) -> f32  {
-    let graphplan = GraphPlan::build_graph(
-        domain,
-        state,
-        goal
-    );
-    match graphplan {
-        Some(graph) => (graph.depth / 2) as f32,
-        None => f32::INFINITY
+    let exploration = RelaxedExploration::explore(domain, state, goal, Aggregation::Max, false);
+    match exploration.goal_cost(goal, Aggregation::Max) {
+        Some(cost) => cost as f32,
+        None => f32::INFINITY,
     }
 }
 
@@ -60,5 +58,21 @@ mod test {
         let domain = generate_domain();
         let h = h_max(&domain, &HashSet::from([0]), &HashSet::from([4]));
         assert_eq!(h, 3.0);
+        let h = h_max(&domain, &HashSet::from([3]), &HashSet::from([4]));
+        assert_eq!(h, f32::INFINITY);
+        let h = h_max(&domain, &HashSet::from([0]), &HashSet::new());
+        assert_eq!(h, 0.0);
+    }
+
+    #[test]
+    pub fn action_cost_test() {
+        let mut domain = generate_domain();
+        domain.actions[0].cost = 0;
+        domain.actions[2].cost = 5;
+        // p4 needs 3, reached through p3 (cost 5) after p1 (cost 0)
+        let h = h_max(&domain, &HashSet::from([0]), &HashSet::from([4]));
+        assert_eq!(h, 6.0);
+        let h = h_max(&domain, &HashSet::from([0]), &HashSet::from([1, 2]));
+        assert_eq!(h, 1.0);
     }
 }
\ No newline at end of file
@@ -1,11 +1,15 @@
 mod add;
 mod ff;
 mod max;
+mod lmcut;
+mod worst_case;
 
 pub use add::h_add;
-pub use ff::h_ff;
+pub use ff::{h_ff, ff_relaxed_plan};
 pub use max::h_max;
+pub use lmcut::{h_lmcut, lmcut_landmarks};
+pub use worst_case::{h_add_wc, h_max_wc};
 
 
-use super::structs::GraphPlan;
+use super::structs::{RelaxedExploration, Aggregation};
 use super::*;
\ No newline at end of file
@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use super::*;
+
+// h_add where the outcome of each nondeterministic action is picked by an
+// adversary. "outcomes" groups the actions of the (all-outcome
+// determinized) domain by the nondeterministic action they come from.
+pub fn h_add_wc(domain: &ClassicalDomain, outcomes: &[Vec<usize>], state: &HashSet<u32>, goal: &HashSet<u32>) -> f32 {
+    worst_case(domain, outcomes, state, goal, Aggregation::Sum, true)
+}
+
+// h_max where the outcome of each nondeterministic action is picked by an
+// adversary. Executions in which a nondeterministic action always has the
+// same outcome are plans of the restricted domain, so the value never
+// exceeds the worst-case cost of a strong solution.
+pub fn h_max_wc(domain: &ClassicalDomain, outcomes: &[Vec<usize>], state: &HashSet<u32>, goal: &HashSet<u32>) -> f32 {
+    worst_case(domain, outcomes, state, goal, Aggregation::Max, false)
+}
+
+// Maximum, over the nondeterministic actions and each of their outcomes, of
+// the goal cost when only that outcome may occur. Actions that support no
+// fact in the all-outcome exploration cannot change the goal cost, so only
+// their groups are explored again.
+fn worst_case(
+    domain: &ClassicalDomain,
+    outcomes: &[Vec<usize>],
+    state: &HashSet<u32>,
+    goal: &HashSet<u32>,
+    aggregation: Aggregation,
+    unit_costs: bool
+) -> f32 {
+    let exploration = RelaxedExploration::explore(domain, state, goal, aggregation, unit_costs);
+    let mut value = match exploration.goal_cost(goal, aggregation) {
+        Some(cost) => cost,
+        None => return f32::INFINITY,
+    };
+    let used: HashSet<usize> = exploration.supporters.values().cloned().collect();
+    for group in outcomes.iter().filter(|g| g.iter().any(|a| used.contains(a))) {
+        for outcome in group.iter() {
+            let excluded = group.iter().filter(|a| *a != outcome).cloned().collect();
+            let restricted = RelaxedExploration::explore_excluding(
+                domain, state, goal, aggregation, unit_costs, &excluded
+            );
+            match restricted.goal_cost(goal, aggregation) {
+                Some(cost) => value = value.max(cost),
+                None => return f32::INFINITY,
+            }
+        }
+    }
+    value as f32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::heuristics::PrimitiveAction;
+    use crate::domain_description::Facts;
+
+    fn action(name: &str, cost: u32, pre: Vec<u32>, add: Vec<u32>) -> PrimitiveAction {
+        PrimitiveAction::new(
+            name.to_string(),
+            cost,
+            pre.into_iter().collect(),
+            vec![add.into_iter().collect()],
+            vec![HashSet::new()]
+        )
+    }
+
+    // a reaches 1 or 2, b turns 2 into 1 (if its precondition holds), c
+    // needs 3 which never holds
+    fn generate_domain(b_pre: Vec<u32>) -> ClassicalDomain {
+        let facts = Facts::new((0..4).map(|x| x.to_string()).collect());
+        ClassicalDomain::new(facts, vec![
+            action("a__determinized_0", 1, vec![0], vec![1]),
+            action("a__determinized_1", 1, vec![0], vec![2]),
+            action("b", 2, b_pre, vec![1]),
+            action("c__determinized_0", 1, vec![3], vec![1]),
+            action("c__determinized_1", 1, vec![3], vec![2]),
+        ])
+    }
+
+    #[test]
+    pub fn h_val_test() {
+        let domain = generate_domain(vec![2]);
+        let outcomes = vec![vec![0, 1], vec![3, 4]];
+        let state = HashSet::from([0]);
+        let goal = HashSet::from([1]);
+        // the all-outcome values rely on the first outcome of a
+        assert_eq!(h_max(&domain, &state, &goal), 1.0);
+        assert_eq!(h_add(&domain, &state, &goal), 1.0);
+        // b is needed when a ends up in 2
+        assert_eq!(h_max_wc(&domain, &outcomes, &state, &goal), 3.0);
+        assert_eq!(h_add_wc(&domain, &outcomes, &state, &goal), 2.0);
+        // without nondeterministic actions both are the usual values
+        assert_eq!(h_max_wc(&domain, &[], &state, &goal), 1.0);
+        assert_eq!(h_max_wc(&domain, &outcomes, &HashSet::from([2]), &goal), 2.0);
+    }
+
+    #[test]
+    pub fn dead_outcome_test() {
+        // 1 can not be reached once a ends up in 2
+        let domain = generate_domain(vec![2, 3]);
+        let outcomes = vec![vec![0, 1], vec![3, 4]];
+        let state = HashSet::from([0]);
+        let goal = HashSet::from([1]);
+        assert_eq!(h_max(&domain, &state, &goal), 1.0);
+        assert_eq!(h_max_wc(&domain, &outcomes, &state, &goal), f32::INFINITY);
+        assert_eq!(h_add_wc(&domain, &outcomes, &state, &goal), f32::INFINITY);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::*;
+use crate::heuristics::structs::{Aggregation, RelaxedExploration};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LandmarkStats {
+    pub facts: u32,
+    pub tasks: u32,
+    pub methods: u32,
+}
+
+// Landmarks of the initial search node
+#[derive(Debug)]
+pub struct Landmarks {
+    // task -> tasks every decomposition of it contains
+    task_landmarks: HashMap<u32, BTreeSet<u32>>,
+    // tasks and methods every decomposition of the initial network contains
+    initial_tasks: BTreeSet<u32>,
+    method_landmarks: BTreeSet<String>,
+    // state facts made true by every relaxed plan of the initial node
+    fact_landmarks: HashSet<u32>,
+}
+
+impl Landmarks {
+    // "domain" is the relaxed composition, "tasks" the (determinized) tasks
+    // of the initial network and "state"/"goal" its encoding. Facts with ids
+    // from num_facts onwards encode tasks and are covered by task landmarks.
+    pub fn new(
+        domain: &ClassicalDomain,
+        tdg: &TDG,
+        tasks: &[u32],
+        state: &HashSet<u32>,
+        goal: &HashSet<u32>,
+        num_facts: u32
+    ) -> Landmarks {
+        let task_landmarks = tdg.task_landmarks();
+        let mut initial_tasks = BTreeSet::new();
+        for task in tasks.iter() {
+            match task_landmarks.get(task) {
+                Some(landmarks) => initial_tasks.extend(landmarks.iter()),
+                None => { initial_tasks.insert(*task); },
+            }
+        }
+        let method_landmarks = initial_tasks.iter().filter_map(|x| tdg.forced_method(*x)).collect();
+        let fact_landmarks = Landmarks::fact_landmarks(domain, state, goal, num_facts);
+        Landmarks { task_landmarks, initial_tasks, method_landmarks, fact_landmarks }
+    }
+
+    // A fact is a landmark if the goal becomes unreachable without its
+    // achievers. Only facts of a relaxed plan need to be checked.
+    fn fact_landmarks(domain: &ClassicalDomain, state: &HashSet<u32>, goal: &HashSet<u32>, num_facts: u32) -> HashSet<u32> {
+        let exploration = RelaxedExploration::explore(domain, state, goal, Aggregation::Max, true);
+        if exploration.goal_cost(goal, Aggregation::Max).is_none() {
+            return HashSet::new();
+        }
+        let mut candidates: HashSet<u32> = exploration.relaxed_plan(goal).iter()
+            .flat_map(|a| domain.actions[*a].pre_cond.iter().cloned())
+            .collect();
+        candidates.extend(goal.iter());
+        candidates.retain(|x| *x < num_facts && !state.contains(x));
+        candidates.into_iter().filter(|fact| {
+            let achievers = domain.actions.iter().enumerate()
+                .filter(|(_, a)| a.add_effects[0].contains(fact))
+                .map(|(i, _)| i)
+                .collect();
+            let without = RelaxedExploration::explore_excluding(domain, state, goal, Aggregation::Max, true, &achievers);
+            without.goal_cost(goal, Aggregation::Max).is_none()
+        }).collect()
+    }
+
+    pub fn stats(&self) -> LandmarkStats {
+        LandmarkStats {
+            facts: self.fact_landmarks.len() as u32,
+            tasks: self.initial_tasks.len() as u32,
+            methods: self.method_landmarks.len() as u32,
+        }
+    }
+
+    // Fact landmarks are accepted once they have held on the path to a node
+    pub fn accept(&self, accepted: &HashSet<u32>, state: &HashSet<u32>) -> HashSet<u32> {
+        let mut result = accepted.clone();
+        result.extend(self.fact_landmarks.iter().filter(|x| state.contains(x)));
+        result
+    }
+}
+
+// Number of landmarks still to be achieved: task landmarks of the remaining
+// network (plus one for every repeated task) and fact landmarks that were
+// not accepted on the path to the node. "tasks" maps task ids to their
+// number of occurrences.
+pub fn h_lmcount(landmarks: &Landmarks, tasks: &HashMap<u32, u32>, accepted: &HashSet<u32>) -> f32 {
+    let mut required = BTreeSet::new();
+    let mut repetitions = 0;
+    for (task, count) in tasks.iter() {
+        match landmarks.task_landmarks.get(task) {
+            Some(x) => required.extend(x.iter()),
+            None => { required.insert(*task); },
+        }
+        repetitions += count - 1;
+    }
+    let facts = landmarks.fact_landmarks.iter().filter(|x| !accepted.contains(x)).count();
+    (required.len() + repetitions as usize + facts) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::domain_description::{FONDProblem, Facts};
+    use crate::relaxation::RelaxedComposition;
+    use crate::task_network::Method;
+
+    // t1 -> [p1, t2] | [t2, p1]; t2 -> [p2] | [p3]; p2 and p3 both need
+    // fact 1, which only p1 achieves
+    fn generate_problem() -> FONDProblem {
+        let p1 = Task::Primitive(PrimitiveAction::new(
+            "p1".to_string(), 1, HashSet::from([0]), vec![HashSet::from([1])], vec![HashSet::new()]
+        ));
+        let p2 = Task::Primitive(PrimitiveAction::new(
+            "p2".to_string(), 1, HashSet::from([1]), vec![HashSet::from([2])], vec![HashSet::new()]
+        ));
+        let p3 = Task::Primitive(PrimitiveAction::new(
+            "p3".to_string(), 1, HashSet::from([1]), vec![HashSet::from([3])], vec![HashSet::new()]
+        ));
+        let t1 = Task::Compound(CompoundTask::new("t1".to_string(), vec![]));
+        let t2 = Task::Compound(CompoundTask::new("t2".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![p1, p2, p3, t1, t2]));
+        let tn = |tasks: Vec<&str>| HTN::new(
+            (1..=tasks.len() as u32).collect(), vec![], domain.clone(),
+            tasks.iter().enumerate().map(|(i, x)| (i as u32 + 1, domain.get_id(x))).collect()
+        );
+        let domain = domain.add_methods(vec![
+            (domain.get_id("t1"), Method::new("m1".to_string(), tn(vec!["p1", "t2"]))),
+            (domain.get_id("t1"), Method::new("m2".to_string(), tn(vec!["t2", "p1"]))),
+            (domain.get_id("t2"), Method::new("m3".to_string(), tn(vec!["p2"]))),
+            (domain.get_id("t2"), Method::new("m4".to_string(), tn(vec!["p3"]))),
+        ]);
+        let init_tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("t1"))]));
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec!["0".to_string(), "1".to_string(), "2".to_string(), "3".to_string()]),
+            tasks: domain,
+            initial_state: HashSet::from([0]),
+            init_tn,
+        };
+        problem.collapse_tn();
+        problem
+    }
+
+    #[test]
+    pub fn landmark_extraction_test() {
+        let problem = generate_problem();
+        let (encoder, bijection) = RelaxedComposition::from_fond_problem(&problem);
+        let root = problem.init_tn.get_all_tasks()[0].borrow().get_name();
+        let tasks = vec![*bijection.get(&problem.tasks.get_id(&root)).unwrap()];
+        let state = encoder.compute_relaxed_state(&tasks, &problem.initial_state);
+        let goal = encoder.compute_goal_state(&tasks);
+        let landmarks = Landmarks::new(&encoder.domain, encoder.tdg(), &tasks, &state, &goal, problem.facts.count());
+        // fact 1 is needed by both p2 and p3, t1, t2 and p1 are in every
+        // decomposition along with the collapsed root
+        assert_eq!(landmarks.fact_landmarks, HashSet::from([1]));
+        assert_eq!(landmarks.stats(), LandmarkStats { facts: 1, tasks: 4, methods: 1 });
+        let id = |name: &str| *bijection.get(&problem.tasks.get_id(name)).unwrap();
+        let t2 = HashMap::from([(id("t2"), 1)]);
+        assert_eq!(h_lmcount(&landmarks, &t2, &HashSet::new()), 2.0);
+        let accepted = landmarks.accept(&HashSet::new(), &HashSet::from([0, 1]));
+        assert_eq!(h_lmcount(&landmarks, &t2, &accepted), 1.0);
+        let repeated = HashMap::from([(id("t2"), 2), (id("p1"), 1)]);
+        assert_eq!(h_lmcount(&landmarks, &repeated, &accepted), 3.0);
+    }
+}
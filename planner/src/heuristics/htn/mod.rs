@@ -0,0 +1,11 @@
+mod tdg_costs;
+mod landmarks;
+mod operator_counting;
+mod ordered;
+
+use super::*;
+
+pub use tdg_costs::h_tdg;
+pub use landmarks::{h_lmcount, Landmarks, LandmarkStats};
+pub use operator_counting::h_lp;
+pub use ordered::h_ordered;
@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+use crate::heuristics::structs::{LinearProgram, LPResult, Relation};
+use crate::relaxation::{AppliedTask, RelaxedComposition};
+
+// Operator-counting heuristic: the cheapest (fractional) number of action
+// and method applications such that
+//  - every occurrence of a task, in the network or added by a method, is
+//    executed or decomposed exactly once (flow over the TDG), and
+//  - every disjunctive action landmark found by LM-cut on the relaxed
+//    composition (domain, state and goal) is used at least once.
+// Any solution satisfies both, so the value never exceeds the optimal cost.
+// "tasks" maps task ids to their number of occurrences. Methods cost
+// TDG::method_cost.
+pub fn h_lp(
+    encoder: &RelaxedComposition, tasks: &HashMap<u32, u32>, state: &HashSet<u32>, goal: &HashSet<u32>,
+    free_decompositions: bool
+) -> f32 {
+    let landmarks = match lmcut_landmarks(&encoder.domain, state, goal) {
+        Some(landmarks) => landmarks,
+        None => return f32::INFINITY,
+    };
+    let tdg = encoder.tdg();
+    let task_ids = tdg.tasks();
+    // variables are the primitive tasks followed by the methods, keyed like
+    // the actions of the composition by the task they apply (and the index
+    // of the method)
+    let mut objective = vec![];
+    let mut flows: HashMap<u32, Vec<(usize, f64)>> = HashMap::new();
+    let mut variables: HashMap<AppliedTask, usize> = HashMap::new();
+    for task in task_ids.iter() {
+        let mut flow = vec![];
+        if let Task::Primitive(action) = &*tdg.domain().get_task(*task).borrow() {
+            flow.push((objective.len(), 1.0));
+            variables.insert((*task, None), objective.len());
+            objective.push(action.cost as f64);
+        }
+        flows.insert(*task, flow);
+    }
+    for task in task_ids.iter() {
+        if let Task::Compound(compound) = &*tdg.domain().get_task(*task).borrow() {
+            for (i, method) in compound.methods.iter().enumerate() {
+                let variable = objective.len();
+                objective.push(tdg.method_cost(*task, method.cost, free_decompositions) as f64);
+                variables.insert((*task, Some(i)), variable);
+                flows.get_mut(task).unwrap().push((variable, 1.0));
+                for subtask in method.decomposition.mappings.values() {
+                    flows.get_mut(subtask).unwrap().push((variable, -1.0));
+                }
+            }
+        }
+    }
+    let mut lp = LinearProgram::new(objective);
+    for task in task_ids.iter() {
+        let count = *tasks.get(task).unwrap_or(&0) as f64;
+        lp.add_constraint(flows.remove(task).unwrap(), Relation::Equal, count);
+    }
+    for (cut, _) in landmarks.iter() {
+        let mut cut_variables: Vec<(usize, f64)> = cut.iter()
+            .filter_map(|a| variables.get(&encoder.applied_task(*a)))
+            .map(|x| (*x, 1.0))
+            .collect();
+        cut_variables.sort_by_key(|(x, _)| *x);
+        // none of the actions of the landmark is reachable from the network
+        if cut_variables.is_empty() {
+            return f32::INFINITY;
+        }
+        lp.add_constraint(cut_variables, Relation::AtLeast, 1.0);
+    }
+    match lp.minimize() {
+        // costs are integers, so the optimal cost is at least the ceiling
+        LPResult::Optimal(value, _) => (value - 1e-6).ceil().max(0.0) as f32,
+        LPResult::Infeasible => f32::INFINITY,
+        LPResult::Unbounded => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::domain_description::{FONDProblem, Facts};
+    use crate::relaxation::RelaxedComposition;
+    use crate::task_network::Method;
+
+    // t1 -> [p1, p1, t2]; t2 -> [p2] | [p3, p1]; p2 needs fact 1, which
+    // no action achieves. The method of [p2] has the name of an action.
+    fn generate_problem() -> FONDProblem {
+        let p1 = Task::Primitive(PrimitiveAction::new(
+            "p1".to_string(), 2, HashSet::new(), vec![HashSet::from([0])], vec![HashSet::new()]
+        ));
+        let p2 = Task::Primitive(PrimitiveAction::new(
+            "p2".to_string(), 1, HashSet::from([1]), vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let p3 = Task::Primitive(PrimitiveAction::new(
+            "p3".to_string(), 3, HashSet::new(), vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let t1 = Task::Compound(CompoundTask::new("t1".to_string(), vec![]));
+        let t2 = Task::Compound(CompoundTask::new("t2".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![p1, p2, p3, t1, t2]));
+        let tn = |tasks: Vec<&str>| HTN::new(
+            (1..=tasks.len() as u32).collect(), vec![], domain.clone(),
+            tasks.iter().enumerate().map(|(i, x)| (i as u32 + 1, domain.get_id(x))).collect()
+        );
+        let domain = domain.add_methods(vec![
+            (domain.get_id("t1"), Method::new("m1".to_string(), tn(vec!["p1", "p1", "t2"]))),
+            (domain.get_id("t2"), Method::new("p3".to_string(), tn(vec!["p2"]))),
+            (domain.get_id("t2"), Method::new("m3".to_string(), tn(vec!["p3", "p1"]))),
+        ]);
+        let init_tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("t1"))]));
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec!["0".to_string(), "1".to_string()]),
+            tasks: domain,
+            initial_state: HashSet::new(),
+            init_tn,
+        };
+        problem.collapse_tn();
+        problem
+    }
+
+    #[test]
+    pub fn operator_counting_test() {
+        let problem = generate_problem();
+        let (encoder, bijection) = RelaxedComposition::from_fond_problem(&problem);
+        let h_charged = |names: Vec<(&str, u32)>, state: HashSet<u32>, free_decompositions: bool| {
+            let tasks: HashMap<u32, u32> = names.iter()
+                .map(|(x, count)| (*bijection.get(&problem.tasks.get_id(x)).unwrap(), *count))
+                .collect();
+            let task_ids = tasks.keys().cloned().collect();
+            let relaxed_state = encoder.compute_relaxed_state(&task_ids, &state);
+            let goal = encoder.compute_goal_state(&task_ids);
+            h_lp(&encoder, &tasks, &relaxed_state, &goal, free_decompositions)
+        };
+        let h = |names, state| h_charged(names, state, true);
+        // p1 is executed three times, t2 has to use m3
+        assert_eq!(h(vec![("t1", 1)], HashSet::new()), 9.0);
+        assert_eq!(h(vec![("t2", 1)], HashSet::new()), 5.0);
+        // m2 is possible once fact 1 holds
+        assert_eq!(h(vec![("t2", 1)], HashSet::from([1])), 1.0);
+        // counts of repeated tasks are taken into account
+        assert_eq!(h(vec![("p1", 2), ("p3", 1)], HashSet::new()), 7.0);
+        assert_eq!(h(vec![("p2", 1)], HashSet::new()), f32::INFINITY);
+        // m1 and m3 cost 1 each unless decompositions are free
+        assert_eq!(h_charged(vec![("t1", 1)], HashSet::new(), false), 11.0);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use super::*;
+use crate::heuristics::structs::{Aggregation, RelaxedExploration};
+use crate::relaxation::RelaxedComposition;
+
+// h_add along the partial order of the network. As long as the only
+// unconstrained task is a deterministic primitive one, it is executed (or
+// the node is a dead end if it is not applicable). Each remaining task is
+// then evaluated on the relaxed composition restricted to the tasks that are
+// not ordered after it, so facts added by later tasks are not available for
+// its preconditions. Every action and method costs 1, as for h_add, and
+// every occurrence of a task is counted.
+pub fn h_ordered(tn: &HTN, state: &HashSet<u32>, encoder: &RelaxedComposition, bijection: &HashMap<u32, u32>) -> f32 {
+    let mut tn = tn.clone();
+    let mut state = state.clone();
+    let mut value = 0.0;
+    loop {
+        let unconstrained = tn.get_unconstrained_tasks();
+        if unconstrained.len() != 1 {
+            break;
+        }
+        let id = *unconstrained.first().unwrap();
+        let next_state = match &*tn.get_task(id).borrow() {
+            Task::Primitive(action) if action.is_deterministic() => {
+                if !action.is_applicable(&state) {
+                    return f32::INFINITY;
+                }
+                action.transition(&state).remove(0)
+            },
+            _ => break,
+        };
+        state = next_state;
+        tn = tn.apply_action(id);
+        value += 1.0;
+    }
+    let task_of = |node: &u32| *bijection.get(tn.mappings.get(node).unwrap()).unwrap();
+    // tasks that may precede a node -> tasks of the nodes evaluated with them
+    let mut groups: HashMap<BTreeSet<u32>, Vec<u32>> = HashMap::new();
+    let successors = successors(&tn);
+    let nodes: BTreeSet<u32> = tn.get_nodes().collect();
+    for node in nodes.iter() {
+        let available = nodes.iter()
+            .filter(|x| !successors[node].contains(x))
+            .map(task_of)
+            .collect();
+        groups.entry(available).or_default().push(task_of(node));
+    }
+    for (available, tasks) in groups.into_iter() {
+        let available: Vec<u32> = available.into_iter().collect();
+        let relaxed_state = encoder.compute_relaxed_state(&available, &state);
+        let goal = encoder.compute_goal_state(&tasks);
+        let exploration = RelaxedExploration::explore(&encoder.domain, &relaxed_state, &goal, Aggregation::Sum, true);
+        for task in tasks.iter() {
+            match exploration.goal_cost(&encoder.compute_goal_state(&[*task]), Aggregation::Sum) {
+                Some(cost) => value += cost as f32,
+                None => return f32::INFINITY,
+            }
+        }
+    }
+    value
+}
+
+// Nodes of the network -> nodes ordered (transitively) after them
+fn successors(tn: &HTN) -> HashMap<u32, HashSet<u32>> {
+    let mut edges: HashMap<u32, Vec<u32>> = HashMap::new();
+    for (from, to) in tn.get_orderings() {
+        edges.entry(from).or_default().push(to);
+    }
+    let mut result = HashMap::new();
+    for node in tn.get_nodes() {
+        let mut reached = HashSet::new();
+        let mut stack = vec![node];
+        while let Some(x) = stack.pop() {
+            for next in edges.get(&x).into_iter().flatten() {
+                if reached.insert(*next) {
+                    stack.push(*next);
+                }
+            }
+        }
+        result.insert(node, reached);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::domain_description::{FONDProblem, Facts};
+    use crate::heuristics::h_add;
+    use crate::task_network::Method;
+
+    // "make" needs 0, which only "prepare" adds, "reset" deletes 0
+    fn generate_problem() -> FONDProblem {
+        let prepare = Task::Primitive(PrimitiveAction::new(
+            "prepare".to_string(), 1, HashSet::new(), vec![HashSet::from([0])], vec![HashSet::new()]
+        ));
+        let make = Task::Primitive(PrimitiveAction::new(
+            "make".to_string(), 1, HashSet::from([0]), vec![HashSet::from([1])], vec![HashSet::new()]
+        ));
+        let reset = Task::Primitive(PrimitiveAction::new(
+            "reset".to_string(), 1, HashSet::new(), vec![HashSet::new()], vec![HashSet::from([0])]
+        ));
+        let top = Task::Compound(CompoundTask::new("top".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![prepare, make, reset, top]));
+        let subtasks = HTN::new(
+            BTreeSet::from([1, 2, 3]), vec![], domain.clone(),
+            HashMap::from([(1, domain.get_id("prepare")), (2, domain.get_id("make")), (3, domain.get_id("reset"))])
+        );
+        let domain = domain.add_methods(vec![(domain.get_id("top"), Method::new("m".to_string(), subtasks))]);
+        let init_tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("top"))]));
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec!["0".to_string(), "1".to_string()]),
+            tasks: domain,
+            initial_state: HashSet::new(),
+            init_tn,
+        };
+        problem.collapse_tn();
+        problem
+    }
+
+    #[test]
+    pub fn successors_test() {
+        let problem = generate_problem();
+        let domain = problem.tasks.clone();
+        let tn = HTN::new(
+            BTreeSet::from([1, 2, 3, 4]),
+            vec![(1, 2), (2, 3)],
+            domain.clone(),
+            HashMap::from([(1, 0), (2, 1), (3, 2), (4, 2)])
+        );
+        let result = successors(&tn);
+        assert_eq!(result[&1], HashSet::from([2, 3]));
+        assert_eq!(result[&2], HashSet::from([3]));
+        assert!(result[&3].is_empty() && result[&4].is_empty());
+    }
+
+    #[test]
+    pub fn h_val_test() {
+        let problem = generate_problem();
+        let (encoder, bijection) = RelaxedComposition::from_fond_problem(&problem);
+        let domain = problem.tasks.clone();
+        let tn = |nodes: Vec<&str>, orderings: Vec<(u32, u32)>| HTN::new(
+            (1..=nodes.len() as u32).collect(),
+            orderings,
+            domain.clone(),
+            nodes.iter().enumerate().map(|(i, x)| (i as u32 + 1, domain.get_id(x))).collect()
+        );
+        let h = |tn: &HTN, state: HashSet<u32>| h_ordered(tn, &state, &encoder, &bijection);
+        // make comes first but 0 does not hold
+        let network = tn(vec!["make", "prepare"], vec![(1, 2)]);
+        assert_eq!(h(&network, HashSet::new()), f32::INFINITY);
+        assert_eq!(h(&network, HashSet::from([0])), 2.0);
+        // prepare comes after make, but not right after it
+        let network = tn(vec!["make", "reset", "prepare"], vec![(1, 3)]);
+        assert_eq!(h(&network, HashSet::new()), f32::INFINITY);
+        assert_eq!(h(&network, HashSet::from([0])), 3.0);
+        // the unordered relaxation uses prepare to support make
+        let tasks: Vec<u32> = network.count_tasks_with_frequency().keys().map(|x| bijection[x]).collect();
+        let relaxed_state = encoder.compute_relaxed_state(&tasks, &HashSet::new());
+        let goal = encoder.compute_goal_state(&tasks);
+        assert_eq!(h_add(&encoder.domain, &relaxed_state, &goal), 4.0);
+        // prepare is executed first, both makes are counted
+        let network = tn(vec!["prepare", "make", "make"], vec![(1, 2), (1, 3)]);
+        assert_eq!(h(&network, HashSet::new()), 3.0);
+        // prepare is unordered with make, so it may support it
+        let network = tn(vec!["reset", "make", "prepare"], vec![(1, 2)]);
+        assert_eq!(h(&network, HashSet::new()), 4.0);
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::{HashMap, HashSet};
+
+use super::*;
+
+// Sum of the cheapest decompositions of the tasks in the network, "tasks"
+// maps task ids to their number of occurrences. With unit costs, this is
+// TDG_m (actions and methods), otherwise TDG_c (action and method costs,
+// see TDG::method_cost). With reachability, actions that are not reachable
+// from the state under delete relaxation are excluded.
+pub fn h_tdg(
+    tdg: &TDG, tasks: &HashMap<u32, u32>, state: &HashSet<u32>, unit_costs: bool, reachability: bool,
+    free_decompositions: bool
+) -> f32 {
+    if reachability {
+        let task_ids = tasks.keys().cloned().collect();
+        let reachable = tdg.relaxed_reachable_actions(&task_ids, state);
+        let costs = tdg.decomposition_costs(unit_costs, free_decompositions, Some(&reachable));
+        tasks.iter().map(|(task, count)| {
+            *count as f32 * costs.get(task).unwrap_or(&f32::INFINITY)
+        }).sum()
+    } else {
+        tasks.iter().map(|(task, count)| {
+            let cost = match unit_costs {
+                true => tdg.min_modifications(*task),
+                false => tdg.min_cost(*task, free_decompositions),
+            };
+            *count as f32 * cost
+        }).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::task_network::Method;
+
+    // t1 -> [p1, t2] | [t1]; t2 -> [p2, p2] | [p3]; p3 requires an
+    // unreachable fact and is expensive
+    fn generate_tdg() -> (TDG, Rc<DomainTasks>) {
+        let p1 = Task::Primitive(PrimitiveAction::new(
+            "p1".to_string(), 1, HashSet::new(), vec![HashSet::from([0])], vec![HashSet::new()]
+        ));
+        let p2 = Task::Primitive(PrimitiveAction::new(
+            "p2".to_string(), 1, HashSet::from([0]), vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let p3 = Task::Primitive(PrimitiveAction::new(
+            "p3".to_string(), 5, HashSet::from([1]), vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let t1 = Task::Compound(CompoundTask::new("t1".to_string(), vec![]));
+        let t2 = Task::Compound(CompoundTask::new("t2".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![p1, p2, p3, t1, t2]));
+        let tn = |tasks: Vec<&str>| HTN::new(
+            (1..=tasks.len() as u32).collect(), vec![], domain.clone(),
+            tasks.iter().enumerate().map(|(i, x)| (i as u32 + 1, domain.get_id(x))).collect()
+        );
+        let domain = domain.add_methods(vec![
+            (domain.get_id("t1"), Method::new("m1".to_string(), tn(vec!["p1", "t2"]))),
+            (domain.get_id("t1"), Method::new("m2".to_string(), tn(vec!["t1"]))),
+            (domain.get_id("t2"), Method::new("m3".to_string(), tn(vec!["p2", "p2"]))),
+            (domain.get_id("t2"), Method::new("m4".to_string(), tn(vec!["p3"]))),
+        ]);
+        let init_tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("t1"))]));
+        (TDG::new(&init_tn), domain)
+    }
+
+    #[test]
+    pub fn static_costs_test() {
+        let (tdg, domain) = generate_tdg();
+        assert_eq!(tdg.min_modifications(domain.get_id("t2")), 2.0);
+        assert_eq!(tdg.min_modifications(domain.get_id("t1")), 4.0);
+        assert_eq!(tdg.min_cost(domain.get_id("t2"), true), 2.0);
+        assert_eq!(tdg.min_cost(domain.get_id("t1"), true), 3.0);
+        // unless decompositions are free, every method costs 1
+        assert_eq!(tdg.min_cost(domain.get_id("t2"), false), 3.0);
+        assert_eq!(tdg.min_cost(domain.get_id("t1"), false), 5.0);
+        let tasks = HashMap::from([(domain.get_id("t2"), 2), (domain.get_id("p1"), 1)]);
+        assert_eq!(h_tdg(&tdg, &tasks, &HashSet::new(), true, false, true), 5.0);
+        assert_eq!(h_tdg(&tdg, &tasks, &HashSet::new(), false, false, true), 5.0);
+        assert_eq!(h_tdg(&tdg, &tasks, &HashSet::new(), false, false, false), 7.0);
+    }
+
+    #[test]
+    pub fn reachability_test() {
+        let (tdg, domain) = generate_tdg();
+        let t2 = HashMap::from([(domain.get_id("t2"), 1)]);
+        // p2 is not reachable without p1, p3 never is
+        assert_eq!(h_tdg(&tdg, &t2, &HashSet::new(), true, true, true), f32::INFINITY);
+        assert_eq!(h_tdg(&tdg, &t2, &HashSet::from([0]), false, true, true), 2.0);
+        let t1 = HashMap::from([(domain.get_id("t1"), 1)]);
+        // p1 makes p2 reachable, t2 has to be decomposed with m3
+        assert_eq!(h_tdg(&tdg, &t1, &HashSet::new(), true, true, true), 5.0);
+        // expensive p3 is only used if it is reachable
+        let cheap = tdg.decomposition_costs(false, true, Some(&HashSet::from([domain.get_id("p3")])));
+        assert_eq!(cheap[&domain.get_id("t2")], 5.0);
+    }
+}
@@ -1,9 +1,11 @@
 mod structs;
 mod classical;
+mod htn;
 
 use crate::task_network::{HTN, Task, PrimitiveAction, CompoundTask, Applicability};
 pub use structs::TDG;
 use crate::domain_description::{ClassicalDomain, DomainTasks};
 
-pub use classical::{h_ff, h_add, h_max};
+pub use classical::{h_ff, ff_relaxed_plan, h_add, h_max, h_add_wc, h_max_wc, h_lmcut, lmcut_landmarks};
+pub use htn::{h_tdg, h_lmcount, h_lp, h_ordered, Landmarks, LandmarkStats};
 
@@ -0,0 +1,166 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use crate::domain_description::ClassicalDomain;
+
+// How the costs of an action's preconditions are combined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregation {
+    Sum,
+    Max,
+}
+
+// Generalized Dijkstra over the delete relaxation of a domain. Actions are
+// triggered by precondition counters, so every action is looked at once per
+// exploration. Only the first outcome of each action is used.
+#[derive(Debug)]
+pub struct RelaxedExploration<'a> {
+    domain: &'a ClassicalDomain,
+    // fact -> cost of reaching it
+    pub fact_costs: HashMap<u32, u32>,
+    // fact -> action through which the fact was reached at minimal cost,
+    // facts of the initial state have no supporter
+    pub supporters: HashMap<u32, usize>,
+}
+
+impl<'a> RelaxedExploration<'a> {
+    // explores until all goal facts are reached (or no new fact can be)
+    pub fn explore(
+        domain: &'a ClassicalDomain,
+        state: &HashSet<u32>,
+        goal: &HashSet<u32>,
+        aggregation: Aggregation,
+        unit_costs: bool
+    ) -> RelaxedExploration<'a> {
+        RelaxedExploration::explore_excluding(domain, state, goal, aggregation, unit_costs, &HashSet::new())
+    }
+
+    // same as explore, without ever applying the actions in "excluded"
+    pub fn explore_excluding(
+        domain: &'a ClassicalDomain,
+        state: &HashSet<u32>,
+        goal: &HashSet<u32>,
+        aggregation: Aggregation,
+        unit_costs: bool,
+        excluded: &HashSet<usize>
+    ) -> RelaxedExploration<'a> {
+        let mut unsatisfied: Vec<usize> = domain.actions.iter().map(|a| a.pre_cond.len()).collect();
+        let mut precondition_costs: Vec<u32> = vec![0; domain.actions.len()];
+        let mut tentative: HashMap<u32, u32> = state.iter().map(|f| (*f, 0)).collect();
+        let mut supporters = HashMap::new();
+        let mut queue: BinaryHeap<Reverse<(u32, u32)>> = state.iter().map(|f| Reverse((0, *f))).collect();
+        let mut step = |i: usize, base: u32, tentative: &mut HashMap<u32, u32>, queue: &mut BinaryHeap<Reverse<(u32, u32)>>| {
+            if excluded.contains(&i) {
+                return;
+            }
+            let action = &domain.actions[i];
+            let cost = base + if unit_costs { 1 } else { action.cost };
+            for effect in action.add_effects[0].iter() {
+                if tentative.get(effect).is_none_or(|x| cost < *x) {
+                    tentative.insert(*effect, cost);
+                    supporters.insert(*effect, i);
+                    queue.push(Reverse((cost, *effect)));
+                }
+            }
+        };
+        for i in domain.unconditional_actions() {
+            step(*i, 0, &mut tentative, &mut queue);
+        }
+        let mut fact_costs = HashMap::new();
+        let mut open_goals = goal.len();
+        while let Some(Reverse((cost, fact))) = queue.pop() {
+            if open_goals == 0 {
+                break;
+            }
+            if fact_costs.contains_key(&fact) {
+                continue;
+            }
+            fact_costs.insert(fact, cost);
+            if goal.contains(&fact) {
+                open_goals -= 1;
+            }
+            for i in domain.precondition_of(fact) {
+                precondition_costs[*i] = match aggregation {
+                    Aggregation::Sum => precondition_costs[*i] + cost,
+                    Aggregation::Max => precondition_costs[*i].max(cost),
+                };
+                unsatisfied[*i] -= 1;
+                if unsatisfied[*i] == 0 {
+                    step(*i, precondition_costs[*i], &mut tentative, &mut queue);
+                }
+            }
+        }
+        RelaxedExploration { domain, fact_costs, supporters }
+    }
+
+    // aggregated cost of the goal facts, None if one of them is unreachable
+    pub fn goal_cost(&self, goal: &HashSet<u32>, aggregation: Aggregation) -> Option<u32> {
+        let mut total = 0;
+        for f in goal.iter() {
+            let cost = *self.fact_costs.get(f)?;
+            total = match aggregation {
+                Aggregation::Sum => total + cost,
+                Aggregation::Max => total.max(cost),
+            };
+        }
+        Some(total)
+    }
+
+    // actions of the relaxed plan obtained by backchaining from the goal
+    // through the best supporters
+    pub fn relaxed_plan(&self, goal: &HashSet<u32>) -> HashSet<usize> {
+        let mut plan = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<u32> = goal.iter().cloned().collect();
+        while let Some(fact) = stack.pop() {
+            if !visited.insert(fact) {
+                continue;
+            }
+            if let Some(action) = self.supporters.get(&fact) {
+                if plan.insert(*action) {
+                    stack.extend(self.domain.actions[*action].pre_cond.iter());
+                }
+            }
+        }
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain_description::Facts;
+    use crate::task_network::PrimitiveAction;
+
+    fn generate_domain() -> ClassicalDomain {
+        let action = |name: &str, cost: u32, pre: Vec<u32>, add: Vec<u32>| PrimitiveAction::new(
+            name.to_string(), cost, pre.into_iter().collect(), vec![add.into_iter().collect()], vec![HashSet::new()]
+        );
+        let facts = Facts::new((0..5).map(|x| x.to_string()).collect());
+        ClassicalDomain::new(facts, vec![
+            action("p1", 1, vec![0], vec![1]),
+            action("p2", 3, vec![1], vec![2]),
+            action("p3", 1, vec![], vec![2]),
+            action("p4", 1, vec![1, 2], vec![3]),
+        ])
+    }
+
+    #[test]
+    pub fn exploration_test() {
+        let domain = generate_domain();
+        let goal = HashSet::from([3]);
+        let add = RelaxedExploration::explore(&domain, &HashSet::from([0]), &goal, Aggregation::Sum, false);
+        assert_eq!(add.fact_costs.get(&2), Some(&1));
+        assert_eq!(add.supporters.get(&2), Some(&2));
+        assert_eq!(add.goal_cost(&goal, Aggregation::Sum), Some(3));
+        assert_eq!(add.relaxed_plan(&goal), HashSet::from([0, 2, 3]));
+        let max = RelaxedExploration::explore(&domain, &HashSet::from([0]), &goal, Aggregation::Max, true);
+        assert_eq!(max.goal_cost(&goal, Aggregation::Max), Some(2));
+        let unreachable = RelaxedExploration::explore(&domain, &HashSet::new(), &goal, Aggregation::Sum, false);
+        assert_eq!(unreachable.goal_cost(&goal, Aggregation::Sum), None);
+        assert_eq!(unreachable.fact_costs.get(&2), Some(&1));
+        let excluded = RelaxedExploration::explore_excluding(
+            &domain, &HashSet::from([0]), &goal, Aggregation::Sum, false, &HashSet::from([2])
+        );
+        assert_eq!(excluded.goal_cost(&goal, Aggregation::Sum), Some(6));
+    }
+}
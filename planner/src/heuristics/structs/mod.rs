@@ -1,7 +1,9 @@
-mod graphplan;
+mod exploration;
+mod simplex;
 mod tdg;
 
 use super::*;
 
-pub use graphplan::GraphPlan;
+pub use exploration::{RelaxedExploration, Aggregation};
+pub use simplex::{LinearProgram, LPResult, Relation};
 pub use tdg::TDG;
\ No newline at end of file
@@ -0,0 +1,250 @@
+// Small dense two-phase simplex solver (with Bland's rule, so it never
+// cycles) for linear programs over non-negative variables
+
+const EPS: f64 = 1e-9;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Relation {
+    Equal,
+    AtLeast,
+    AtMost,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LPResult {
+    // objective value and variable assignment
+    Optimal(f64, Vec<f64>),
+    Infeasible,
+    Unbounded,
+}
+
+// sparse coefficients, relation and right hand side
+type Constraint = (Vec<(usize, f64)>, Relation, f64);
+
+// minimize objective * x subject to the constraints and x >= 0
+#[derive(Debug, Clone)]
+pub struct LinearProgram {
+    objective: Vec<f64>,
+    constraints: Vec<Constraint>,
+}
+
+struct Tableau {
+    // rows of the constraints, the last column is the right hand side
+    rows: Vec<Vec<f64>>,
+    basis: Vec<usize>,
+    // reduced costs, the last entry is minus the objective value
+    costs: Vec<f64>,
+}
+
+impl Tableau {
+    fn rhs(&self, row: usize) -> f64 {
+        *self.rows[row].last().unwrap()
+    }
+
+    fn pivot(&mut self, row: usize, col: usize) {
+        let value = self.rows[row][col];
+        for x in self.rows[row].iter_mut() {
+            *x /= value;
+        }
+        let pivot_row = self.rows[row].clone();
+        for (i, r) in self.rows.iter_mut().enumerate() {
+            let factor = r[col];
+            if i != row && factor.abs() > EPS {
+                for (x, p) in r.iter_mut().zip(pivot_row.iter()) {
+                    *x -= factor * p;
+                }
+            }
+        }
+        let factor = self.costs[col];
+        if factor.abs() > EPS {
+            for (x, p) in self.costs.iter_mut().zip(pivot_row.iter()) {
+                *x -= factor * p;
+            }
+        }
+        self.basis[row] = col;
+    }
+
+    // sets the reduced costs of the given cost vector for the current basis
+    fn set_costs(&mut self, costs: Vec<f64>) {
+        self.costs = costs;
+        for row in 0..self.rows.len() {
+            let factor = self.costs[self.basis[row]];
+            if factor.abs() > EPS {
+                for (x, r) in self.costs.iter_mut().zip(self.rows[row].iter()) {
+                    *x -= factor * r;
+                }
+            }
+        }
+    }
+
+    // optimizes over the columns before "allowed", returns false if unbounded
+    fn optimize(&mut self, allowed: usize) -> bool {
+        loop {
+            let entering = match (0..allowed).find(|j| self.costs[*j] < -EPS) {
+                Some(j) => j,
+                None => return true,
+            };
+            let mut leaving: Option<(usize, f64)> = None;
+            for i in 0..self.rows.len() {
+                let a = self.rows[i][entering];
+                if a > EPS {
+                    let ratio = self.rhs(i) / a;
+                    leaving = match leaving {
+                        Some((l, best)) if best < ratio - EPS => Some((l, best)),
+                        Some((l, best)) if (best - ratio).abs() <= EPS && self.basis[l] < self.basis[i] => Some((l, best)),
+                        _ => Some((i, ratio)),
+                    };
+                }
+            }
+            match leaving {
+                Some((row, _)) => self.pivot(row, entering),
+                None => return false,
+            }
+        }
+    }
+}
+
+impl LinearProgram {
+    pub fn new(objective: Vec<f64>) -> LinearProgram {
+        LinearProgram { objective, constraints: vec![] }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.objective.len()
+    }
+
+    pub fn add_constraint(&mut self, coefficients: Vec<(usize, f64)>, relation: Relation, rhs: f64) {
+        self.constraints.push((coefficients, relation, rhs));
+    }
+
+    pub fn minimize(&self) -> LPResult {
+        let n = self.num_variables();
+        let m = self.constraints.len();
+        // one slack (or surplus) column per inequality and an artificial
+        // column per row that has no slack to start the basis with
+        let slacks = self.constraints.iter().filter(|(_, r, _)| *r != Relation::Equal).count();
+        let width = n + slacks + m + 1;
+        let artificial_start = n + slacks;
+        let mut rows = vec![];
+        let mut basis = vec![];
+        let mut slack = n;
+        for (i, (coefficients, relation, rhs)) in self.constraints.iter().enumerate() {
+            let mut row = vec![0.0; width];
+            for (j, a) in coefficients.iter() {
+                row[*j] += a;
+            }
+            row[width - 1] = *rhs;
+            let mut relation = *relation;
+            if *rhs < 0.0 {
+                for x in row.iter_mut() {
+                    *x = -*x;
+                }
+                relation = match relation {
+                    Relation::AtLeast => Relation::AtMost,
+                    Relation::AtMost => Relation::AtLeast,
+                    Relation::Equal => Relation::Equal,
+                };
+            }
+            match relation {
+                Relation::AtMost => {
+                    row[slack] = 1.0;
+                    basis.push(slack);
+                    slack += 1;
+                },
+                Relation::AtLeast => {
+                    row[slack] = -1.0;
+                    slack += 1;
+                    row[artificial_start + i] = 1.0;
+                    basis.push(artificial_start + i);
+                },
+                Relation::Equal => {
+                    row[artificial_start + i] = 1.0;
+                    basis.push(artificial_start + i);
+                }
+            }
+            rows.push(row);
+        }
+        let mut tableau = Tableau { rows, basis, costs: vec![] };
+        // phase 1: minimize the sum of the artificial variables
+        let mut costs = vec![0.0; width];
+        for x in costs[artificial_start..width - 1].iter_mut() {
+            *x = 1.0;
+        }
+        tableau.set_costs(costs);
+        tableau.optimize(width - 1);
+        if -tableau.costs[width - 1] > 1e-7 {
+            return LPResult::Infeasible;
+        }
+        // drive the remaining (zero) artificial variables out of the basis
+        for row in 0..m {
+            if tableau.basis[row] >= artificial_start {
+                if let Some(col) = (0..artificial_start).find(|j| tableau.rows[row][*j].abs() > EPS) {
+                    tableau.pivot(row, col);
+                }
+            }
+        }
+        // phase 2
+        let mut costs = vec![0.0; width];
+        costs[..n].copy_from_slice(&self.objective);
+        tableau.set_costs(costs);
+        if !tableau.optimize(artificial_start) {
+            return LPResult::Unbounded;
+        }
+        let mut solution = vec![0.0; n];
+        for (row, col) in tableau.basis.iter().enumerate() {
+            if *col < n {
+                solution[*col] = tableau.rhs(row);
+            }
+        }
+        LPResult::Optimal(-tableau.costs[width - 1], solution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn optimum(lp: &LinearProgram) -> f64 {
+        match lp.minimize() {
+            LPResult::Optimal(value, _) => value,
+            x => panic!("expected an optimal solution, found {:?}", x),
+        }
+    }
+
+    #[test]
+    pub fn minimize_test() {
+        // min 2x + 3y s.t. x + y >= 4, x <= 3, x - y = 1
+        let mut lp = LinearProgram::new(vec![2.0, 3.0]);
+        lp.add_constraint(vec![(0, 1.0), (1, 1.0)], Relation::AtLeast, 4.0);
+        lp.add_constraint(vec![(0, 1.0)], Relation::AtMost, 3.0);
+        lp.add_constraint(vec![(0, 1.0), (1, -1.0)], Relation::Equal, 1.0);
+        assert!((optimum(&lp) - 9.5).abs() < 1e-6);
+        if let LPResult::Optimal(_, x) = lp.minimize() {
+            assert!((x[0] - 2.5).abs() < 1e-6 && (x[1] - 1.5).abs() < 1e-6);
+        }
+        // fractional optimum of a covering problem
+        let mut lp = LinearProgram::new(vec![1.0, 1.0, 1.0]);
+        lp.add_constraint(vec![(0, 1.0), (1, 1.0)], Relation::AtLeast, 1.0);
+        lp.add_constraint(vec![(1, 1.0), (2, 1.0)], Relation::AtLeast, 1.0);
+        lp.add_constraint(vec![(0, 1.0), (2, 1.0)], Relation::AtLeast, 1.0);
+        assert!((optimum(&lp) - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    pub fn degenerate_cases_test() {
+        let mut lp = LinearProgram::new(vec![1.0]);
+        lp.add_constraint(vec![(0, 1.0)], Relation::AtLeast, 2.0);
+        lp.add_constraint(vec![(0, 1.0)], Relation::AtMost, 1.0);
+        assert_eq!(lp.minimize(), LPResult::Infeasible);
+        let mut lp = LinearProgram::new(vec![-1.0, 0.0]);
+        lp.add_constraint(vec![(0, 1.0), (1, -1.0)], Relation::Equal, 0.0);
+        assert_eq!(lp.minimize(), LPResult::Unbounded);
+        // redundant equalities and negative right hand sides
+        let mut lp = LinearProgram::new(vec![1.0, 1.0]);
+        lp.add_constraint(vec![(0, 1.0), (1, 1.0)], Relation::Equal, 2.0);
+        lp.add_constraint(vec![(0, 2.0), (1, 2.0)], Relation::Equal, 4.0);
+        lp.add_constraint(vec![(0, -1.0)], Relation::AtMost, -1.0);
+        assert!((optimum(&lp) - 2.0).abs() < 1e-6);
+        assert!((optimum(&LinearProgram::new(vec![1.0, 2.0]))).abs() < 1e-6);
+    }
+}
@@ -9,11 +9,35 @@ pub struct TDG{
     domain: Rc<DomainTasks>,
     root: u32,
     task_vertices: HashMap<u32, Option<Vec<String>>>,
-    method_vertices: HashMap<String, BTreeSet<u32>>
+    method_vertices: HashMap<String, BTreeSet<u32>>,
+    // minimal number of actions and method applications to fully
+    // decompose a task (TDG_m)
+    min_modifications: HashMap<u32, f32>,
+    // minimal cost of the actions and methods a task decomposes into
+    // (TDG_c), with decompositions by methods without a cost being free or
+    // costing 1
+    min_costs: HashMap<u32, f32>,
+    min_charged_costs: HashMap<u32, f32>,
+    // (task, method name, subtasks with repetitions) of every method of the
+    // reachable compound tasks
+    methods: Vec<(u32, String, Vec<u32>)>,
+    // compound tasks whose methods pick an outcome of a determinized action
+    outcome_tasks: HashSet<u32>,
+}
+
+// Domain ids of the tasks of a network, in the order of its nodes
+fn subtasks(tn: &HTN) -> Vec<u32> {
+    tn.get_nodes().map(|x| tn.mappings[&x]).collect()
 }
 
 impl TDG  {
     pub fn new(tn: &HTN) -> TDG {
+        TDG::with_outcome_tasks(tn, HashSet::new())
+    }
+
+    // Choosing an outcome of the determinized actions (the methods of
+    // outcome_tasks) does not count as a modification
+    pub fn with_outcome_tasks(tn: &HTN, outcome_tasks: HashSet<u32>) -> TDG {
         if tn.count_tasks() != 1 {
             panic!("TN is not in collapsed format")
         }
@@ -22,7 +46,7 @@ impl TDG  {
                             .next()
                             .unwrap();
         let domain = tn.domain.clone();
-        let root = domain.get_id(&tn.get_task(root).borrow().get_name());
+        let root = tn.mappings[&root];
         let mut task_vertices = HashMap::new();
         let mut method_vertices = HashMap::new();
         let mut working_set = LinkedList::from([root]);
@@ -36,10 +60,7 @@ impl TDG  {
                             let mut task_connections = vec![];
                             for (i, method) in compound.methods.iter().enumerate() {
                                 let name = format!("task{}_m{}", task_id, i);
-                                let subtasks = method.decomposition.get_all_tasks();
-                                let subtasks: BTreeSet<u32> = subtasks.iter().map(|x| {
-                                    domain.get_id(&x.borrow().get_name())
-                                }).collect();
+                                let subtasks: BTreeSet<u32> = subtasks(&method.decomposition).into_iter().collect();
                                 method_vertices.insert(name.clone(),subtasks.clone());
                                 for elem in subtasks.iter() {
                                     working_set.push_back(*elem);
@@ -58,12 +79,145 @@ impl TDG  {
                 }
             }
         }
-        TDG{
+        let mut tdg = TDG{
             domain: domain,
             root: root,
             task_vertices: task_vertices,
-            method_vertices: method_vertices
+            method_vertices,
+            min_modifications: HashMap::new(),
+            min_costs: HashMap::new(),
+            min_charged_costs: HashMap::new(),
+            methods: vec![],
+            outcome_tasks,
+        };
+        let mut methods = vec![];
+        for (task, connections) in tdg.task_vertices.iter() {
+            if let Task::Compound(compound) = &*tdg.domain.get_task(*task).borrow() {
+                for method in compound.methods.iter() {
+                    methods.push((*task, method.name.clone(), subtasks(&method.decomposition)));
+                }
+            }
         }
+        methods.sort();
+        tdg.methods = methods;
+        tdg.min_modifications = tdg.decomposition_costs(true, true, None);
+        tdg.min_costs = tdg.decomposition_costs(false, true, None);
+        tdg.min_charged_costs = tdg.decomposition_costs(false, false, None);
+        tdg
+    }
+
+    pub fn min_modifications(&self, task: u32) -> f32 {
+        *self.min_modifications.get(&task).unwrap_or(&f32::INFINITY)
+    }
+
+    pub fn min_cost(&self, task: u32, free_decompositions: bool) -> f32 {
+        let costs = if free_decompositions { &self.min_costs } else { &self.min_charged_costs };
+        *costs.get(&task).unwrap_or(&f32::INFINITY)
+    }
+
+    // Cost of applying a method of "task" as in the search: methods that
+    // pick an outcome are free, the others cost their cost, or 1 if they
+    // have none and decompositions are not free
+    pub fn method_cost(&self, task: u32, cost: Option<u32>, free_decompositions: bool) -> f32 {
+        if self.outcome_tasks.contains(&task) {
+            return 0.0;
+        }
+        match cost {
+            Some(cost) => cost as f32,
+            None if free_decompositions => 0.0,
+            None => 1.0,
+        }
+    }
+
+    pub fn domain(&self) -> &Rc<DomainTasks> {
+        &self.domain
+    }
+
+    // ids of the tasks reachable from the root
+    pub fn tasks(&self) -> Vec<u32> {
+        let mut tasks: Vec<u32> = self.task_vertices.keys().cloned().collect();
+        tasks.sort();
+        tasks
+    }
+
+    pub fn methods(&self) -> &[(u32, String, Vec<u32>)] {
+        &self.methods
+    }
+
+    // Fixpoint computation of the cheapest decomposition of every reachable
+    // task. With unit costs, every action and method application costs 1
+    // (except for the methods that select an outcome of a determinized
+    // action), otherwise actions cost PrimitiveAction.cost and methods
+    // their method_cost. Actions outside of "reachable" (if given) are
+    // considered unusable.
+    pub fn decomposition_costs(&self, unit_costs: bool, free_decompositions: bool, reachable: Option<&HashSet<u32>>) -> HashMap<u32, f32> {
+        let mut costs = HashMap::new();
+        let mut methods = vec![];
+        for (task, connections) in self.task_vertices.iter() {
+            match &*self.domain.get_task(*task).borrow() {
+                Task::Primitive(action) => {
+                    let cost = match reachable {
+                        Some(r) if !r.contains(task) => f32::INFINITY,
+                        _ if unit_costs => 1.0,
+                        _ => action.cost as f32,
+                    };
+                    costs.insert(*task, cost);
+                },
+                Task::Compound(CompoundTask { methods: task_methods, .. }) => {
+                    costs.insert(*task, f32::INFINITY);
+                    for method in task_methods.iter() {
+                        let method_cost = if !unit_costs {
+                            self.method_cost(*task, method.cost, free_decompositions)
+                        } else if self.outcome_tasks.contains(task) {
+                            0.0
+                        } else {
+                            1.0
+                        };
+                        methods.push((*task, method_cost, subtasks(&method.decomposition)));
+                    }
+                }
+            }
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (task, method_cost, subtasks) in methods.iter() {
+                let cost = subtasks.iter().fold(*method_cost, |acc, x| acc + costs[x]);
+                if cost < costs[task] {
+                    costs.insert(*task, cost);
+                    changed = true;
+                }
+            }
+        }
+        costs
+    }
+
+    // Primitive actions reachable from "tasks" whose preconditions can be
+    // satisfied from "state" under delete relaxation
+    pub fn relaxed_reachable_actions(&self, tasks: &Vec<u32>, state: &HashSet<u32>) -> HashSet<u32> {
+        let mut facts = state.clone();
+        let mut remaining: Vec<u32> = self.all_reachables(tasks).into_iter()
+            .filter(|x| self.domain.get_task(*x).borrow().is_primitive())
+            .collect();
+        let mut reachable = HashSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            remaining.retain(|x| {
+                if let Task::Primitive(action) = &*self.domain.get_task(*x).borrow() {
+                    if action.is_applicable(&facts) {
+                        for effect in action.add_effects.iter() {
+                            facts.extend(effect.iter());
+                        }
+                        reachable.insert(*x);
+                        changed = true;
+                        return false;
+                    }
+                }
+                true
+            });
+        }
+        reachable
     }
 
     // Checks whether "task" can be reached from the current network
@@ -118,6 +272,54 @@ impl TDG  {
         }
         reachables
     }
+
+    // Tasks contained in every decomposition of a task (the task itself
+    // included), computed as a greatest fixpoint: the landmarks of a compound
+    // task are those shared by all of its methods
+    pub fn task_landmarks(&self) -> HashMap<u32, BTreeSet<u32>> {
+        let all_tasks: BTreeSet<u32> = self.task_vertices.keys().cloned().collect();
+        let mut landmarks: HashMap<u32, BTreeSet<u32>> = self.task_vertices.iter()
+            .map(|(task, connections)| match connections {
+                Some(_) => (*task, all_tasks.clone()),
+                None => (*task, BTreeSet::from([*task])),
+            }).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (task, connections) in self.task_vertices.iter() {
+                let methods = match connections {
+                    Some(methods) if !methods.is_empty() => methods,
+                    _ => continue,
+                };
+                let mut shared: Option<BTreeSet<u32>> = None;
+                for method in methods.iter() {
+                    let mut union = BTreeSet::new();
+                    for subtask in self.method_vertices.get(method).unwrap().iter() {
+                        union.extend(landmarks[subtask].iter());
+                    }
+                    shared = Some(match shared {
+                        Some(x) => x.intersection(&union).cloned().collect(),
+                        None => union,
+                    });
+                }
+                let mut shared = shared.unwrap();
+                shared.insert(*task);
+                if shared != landmarks[task] {
+                    landmarks.insert(*task, shared);
+                    changed = true;
+                }
+            }
+        }
+        landmarks
+    }
+
+    // The only method of a compound task, if it has exactly one
+    pub fn forced_method(&self, task: u32) -> Option<String> {
+        match &*self.domain.get_task(task).borrow() {
+            Task::Compound(CompoundTask { methods, .. }) if methods.len() == 1 => Some(methods[0].name.clone()),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for TDG  {
@@ -394,7 +596,7 @@ mod tests {
         let tn = problem.init_tn;
         let tdg = TDG::new(&tn);
         if let Task::Compound(CompoundTask { name, methods }) = &*tn.get_all_tasks()[0].borrow() {
-            let new_tn = tn.decompose(*tn.get_nodes().iter().next().unwrap(), &methods[0]);
+            let new_tn = tn.decompose(tn.get_nodes().next().unwrap(), &methods[0]);
             let p1_id = new_tn.get_all_tasks_with_ids().iter().filter(|(x,_)| {
                 x.borrow().get_name() == "p1"
             }).collect::<Vec<_>>()[0].1;
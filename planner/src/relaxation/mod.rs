@@ -1,8 +1,10 @@
 mod relaxed_composition;
 mod outcome_determinization;
+mod relevance_pruning;
 
-pub use relaxed_composition::RelaxedComposition;
+pub use relaxed_composition::{RelaxedComposition, ActionOrigin, AppliedTask};
 use crate::heuristics::TDG;
 use crate::task_network::{HTN, Task, CompoundTask, Applicability, PrimitiveAction};
-pub use outcome_determinization::OutcomeDeterminizer;
+pub use outcome_determinization::{OutcomeDeterminizer, DeterminizedAction};
+pub use relevance_pruning::{RelevancePruning, PruningReport};
 use crate::task_network::Method;
\ No newline at end of file
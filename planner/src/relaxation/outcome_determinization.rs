@@ -4,10 +4,28 @@ use std::{rc::{Rc, self}, collections::{HashSet, HashMap, BTreeSet}, ops::Index,
 
 pub struct OutcomeDeterminizer {}
 
+// Nondeterministic action replaced by the determinization
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeterminizedAction {
+    // name of the nondeterministic action
+    pub name: String,
+    // compound task that stands for the action
+    pub task: u32,
+    // primitive task of each outcome
+    pub outcomes: Vec<u32>,
+}
+
 impl OutcomeDeterminizer {
     // returns an all outcome determinization of a problem along with a task id mapping from previous domain
     // to the new one
     pub fn from_fond_problem(problem: &FONDProblem) -> (FONDProblem, HashMap<u32, u32>) {
+        let (determinized, bijection, _) = OutcomeDeterminizer::determinize(problem);
+        (determinized, bijection)
+    }
+
+    // from_fond_problem, along with the tasks that replace each
+    // nondeterministic action
+    pub fn determinize(problem: &FONDProblem) -> (FONDProblem, HashMap<u32, u32>, Vec<DeterminizedAction>) {
         // We assume a collapsed network (i.e., with only one init abstract task)
         if problem.init_tn.count_tasks() > 1 {
             panic!("tn not in collapsed format")
@@ -16,20 +34,17 @@ impl OutcomeDeterminizer {
         // find non deterministic tasks in the domain
         let nd_actions = OutcomeDeterminizer::determinize_nd_tasks(all_tasks);
         // substitue nd tasks
-        let (new_tasks, bijection) = OutcomeDeterminizer::substitue_nd_tasks(all_tasks, &nd_actions);
+        let (new_tasks, bijection, determinized) = OutcomeDeterminizer::substitue_nd_tasks(all_tasks, &nd_actions);
         let new_domain = DomainTasks::new(new_tasks);
-        let nd_act_map: Vec<(u32, Vec<u32>)> = nd_actions.iter().map(|(_, (c, det_acts))| {
-            (new_domain.get_id(&c.get_name()),
-             det_acts.iter().map(|x| new_domain.get_id(&x.get_name())).collect::<Vec<_>>())
-        }).collect();
+        let nd_act_map: Vec<(u32, Vec<u32>)> = determinized.iter().map(|x| (x.task, x.outcomes.clone())).collect();
 
         let mut rc_domain = Rc::new(new_domain);
         let methods = OutcomeDeterminizer::construct_methods(rc_domain.clone(), &nd_act_map);
         rc_domain = rc_domain.add_methods(methods).clone();
 
         // Create init tn (we know that the task is in collapsed format)
-        let top_task = problem.init_tn.get_all_tasks().iter().next().unwrap().borrow().get_name();
-        let new_top_id = rc_domain.get_id(&top_task);
+        let top_task = problem.init_tn.mappings.values().next().unwrap();
+        let new_top_id = *bijection.get(top_task).unwrap();
         let new_tn = HTN::new(
             BTreeSet::from([1]),
             vec![],
@@ -41,7 +56,7 @@ impl OutcomeDeterminizer {
             tasks: rc_domain,
             initial_state: problem.initial_state.clone(),
             init_tn: new_tn
-        }, bijection)
+        }, bijection, determinized)
     }
 
     // Converts a primitive task to an abstract one with several
@@ -96,60 +111,46 @@ impl OutcomeDeterminizer {
     }
 
     // returns a vector of new tasks that are all-outcome-determinized + a bijection from prev task ids to their corresponding AOD.
+    // The tasks replacing each nondeterministic action are also returned.
     fn substitue_nd_tasks(all_tasks: &Vec<RefCell<Task>>, nd_actions: &HashMap<usize, (Task, Vec<Task>)>)
-     -> (Vec<Task>, HashMap<u32, u32>) {
-        let mut new_tasks: Vec<Task> = all_tasks.iter()
-                                                .enumerate()
-                                                .filter(|(i, t)| {
-                                                    !nd_actions.contains_key(i)
-                                                }).map(|(i, x)| x.borrow().clone())
-                                                .collect();
+     -> (Vec<Task>, HashMap<u32, u32>, Vec<DeterminizedAction>) {
+        let mut new_tasks = vec![];
+        let mut bijection = HashMap::new();
+        for (prev_id, task) in all_tasks.iter().enumerate() {
+            if !nd_actions.contains_key(&prev_id) {
+                bijection.insert(prev_id as u32, new_tasks.len() as u32);
+                new_tasks.push(task.borrow().clone());
+            }
+        }
         // add all-outcome determinized versions to the domain
-        new_tasks.extend(nd_actions.clone()
-                                        .into_iter()
-                                        .map(|(_, (c, det_acts))| {
-                                            let mut new_tasks = vec![c];
-                                            new_tasks.extend(det_acts);
-                                            new_tasks
-                                        }).flatten()
-                                        .collect::<Vec<Task>>());
-        let bijection = OutcomeDeterminizer::compute_bijection(&new_tasks, &all_tasks);
+        let mut nd_ids: Vec<usize> = nd_actions.keys().cloned().collect();
+        nd_ids.sort();
+        let mut determinized = vec![];
+        for prev_id in nd_ids {
+            let (c, det_acts) = nd_actions.get(&prev_id).unwrap();
+            let c_id = new_tasks.len() as u32;
+            bijection.insert(prev_id as u32, c_id);
+            new_tasks.push(c.clone());
+            let outcomes = (0..det_acts.len() as u32).map(|i| c_id + 1 + i).collect();
+            new_tasks.extend(det_acts.iter().cloned());
+            determinized.push(DeterminizedAction {
+                name: all_tasks[prev_id].borrow().get_name(),
+                task: c_id,
+                outcomes,
+            });
+        }
         // Change the mappings of exisiting methods
         for t in new_tasks.iter_mut() {
             if let Task::Compound(CompoundTask { name, methods }) = t {
                 for m in methods.iter_mut() {
-                    for (node_id, task_id) in m.decomposition.mappings.iter_mut() {
-                        let new_task_id = *bijection.get(task_id).unwrap();
-                        *task_id = new_task_id;
-                    }
+                    m.decomposition.mappings = m.decomposition.mappings.iter()
+                        .map(|(node_id, task_id)| (*node_id, *bijection.get(task_id).unwrap()))
+                        .collect();
                 }
             }
         }
-        (new_tasks, bijection)
-    }
-
-    fn compute_bijection(new_tasks: &Vec<Task>, all_tasks: &Vec<RefCell<Task>>) -> HashMap<u32, u32> {
-        let mut bijection =  HashMap::new();
-        for (prev_id, prev_task) in all_tasks.iter().enumerate() {
-            let borrowed_task = prev_task.borrow();
-            let new_name = match &*borrowed_task {
-                Task::Compound(_) => {
-                    borrowed_task.get_name()
-                },
-                Task::Primitive(p) => {
-                    if !p.is_deterministic() {
-                        p.name.clone() + "__determinized"
-                    } else {
-                        p.name.clone()
-                    }
-                }
-            };
-            let new_id = new_tasks.iter().position(|x| x.get_name() == new_name).unwrap();
-            bijection.insert(prev_id as u32, new_id as u32);
-        }
-        bijection
+        (new_tasks, bijection, determinized)
     }
-    
 }
 
 #[cfg(test)]
@@ -212,7 +213,12 @@ mod tests {
             init_tn: tn
         };
         problem.collapse_tn();
-        let (relaxed, bijection) = OutcomeDeterminizer::from_fond_problem(&problem);
+        let (relaxed, bijection, determinized) = OutcomeDeterminizer::determinize(&problem);
+        assert_eq!(determinized, vec![DeterminizedAction {
+            name: "p2".to_string(),
+            task: relaxed.tasks.get_id("p2__determinized"),
+            outcomes: vec![relaxed.tasks.get_id("p2__determinized_0"), relaxed.tasks.get_id("p2__determinized_1")],
+        }]);
         assert_eq!(bijection.len(), 5);
         assert_eq!(*bijection.get(&domain.get_id("p1")).unwrap(), relaxed.tasks.get_id("p1"));
         assert_eq!(*bijection.get(&domain.get_id("p2")).unwrap(), relaxed.tasks.get_id("p2__determinized"));
@@ -234,8 +240,8 @@ mod tests {
             let mut det_acts = vec![relaxed.tasks.get_id("p2__determinized_0"), relaxed.tasks.get_id("p2__determinized_1")];
             assert_eq!(methods.len(), 2);
             for m in methods.iter() {
-                assert_eq!(m.decomposition.get_nodes().len(), 1);
-                let decomp_node = *m.decomposition.get_nodes().iter().next().unwrap();
+                assert_eq!(m.decomposition.count_tasks(), 1);
+                let decomp_node = m.decomposition.get_nodes().next().unwrap();
                 let decomp_node = m.decomposition.get_task(decomp_node).borrow().get_name();
                 let decomp_node = relaxed.tasks.get_id(&decomp_node);
                 assert_eq!(det_acts.contains(&decomp_node), true);
@@ -1,61 +1,140 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 use super::TDG;
 use crate::domain_description::{ClassicalDomain, Facts, DomainTasks};
 use crate::task_network::{PrimitiveAction, Task};
 use crate::{domain_description::FONDProblem, task_network::HTN};
-use regex::Regex;
+use super::{OutcomeDeterminizer, DeterminizedAction};
+
+// What an action of the relaxed composition stands for in the problem
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ActionOrigin {
+    // primitive task with this name
+    Action(String),
+    // method with this name
+    Method(String),
+    // choice of an outcome, introduced by the determinization
+    Outcome,
+}
+
+// Task an action executes, or task it decomposes along with the index of
+// the method
+pub type AppliedTask = (u32, Option<usize>);
 
 #[derive(Debug)]
 pub struct RelaxedComposition{
     tdg: TDG,
     htn_tasks: Rc<DomainTasks>,
     pub domain: ClassicalDomain,
+    // actions of the domain grouped by the nondeterministic action they
+    // are an outcome of
+    outcomes: Vec<Vec<usize>>,
+    // number of facts of the problem, they come before the task facts
+    num_facts: u32,
+    // action of the domain -> its origin
+    origins: Vec<ActionOrigin>,
+    // action of the domain -> task it applies
+    applied_tasks: Vec<AppliedTask>,
+    // task id -> fact that holds once the task is achieved (top-down)
+    task_facts: Vec<u32>,
+    // task id -> fact that makes the primitive task executable (bottom-up),
+    // none for compound tasks
+    reachable_facts: Vec<Option<u32>>,
 }
 
 impl RelaxedComposition  {
+    // Composition of a problem without nondeterministic actions
     pub fn new(domain: &FONDProblem) -> RelaxedComposition {
-        let mut new_facts = domain.facts.clone();
-        // top down encoding
-        let tasks = domain.tasks.get_all_tasks();
-        let top_down_facts = tasks.iter().map(|x| {x.borrow().get_name()}).collect();
-        new_facts = new_facts.extend(top_down_facts);
-        // bottom-up encoding
-        let bottom_up_facts: Vec<String> = domain.tasks.get_all_tasks().iter()
-            .filter(|x| x.borrow().is_primitive())
-            .map(|x| x.borrow().get_name() + "_reachable")
-            .collect();
-        new_facts = new_facts.extend(bottom_up_facts);
+        RelaxedComposition::with_determinization(domain, &[])
+    }
 
-        let new_actions = RelaxedComposition::encode(&domain, &new_facts);
-        let classic_domain = ClassicalDomain { facts: new_facts, actions: new_actions };
-        let tdg = TDG::new(&domain.init_tn);
-        RelaxedComposition { domain: classic_domain, htn_tasks: domain.tasks.clone(), tdg: tdg }
+    // Composition of the all-outcome determinization of a problem, along with
+    // the mapping from task ids of the problem to those of the composition
+    pub fn from_fond_problem(problem: &FONDProblem) -> (RelaxedComposition, HashMap<u32, u32>) {
+        let (outcome_det, bijection, determinized) = OutcomeDeterminizer::determinize(problem);
+        (RelaxedComposition::with_determinization(&outcome_det, &determinized), bijection)
     }
 
-    fn encode(domain: &FONDProblem, facts: &Facts) -> Vec<PrimitiveAction> {
-        let mut result = vec![];
+    // "determinized" lists the tasks of the domain that stand for the
+    // nondeterministic actions of the original problem
+    pub fn with_determinization(domain: &FONDProblem, determinized: &[DeterminizedAction]) -> RelaxedComposition {
+        let num_facts = domain.facts.count();
         let tasks = domain.tasks.get_all_tasks();
+        // problem facts, then one top-down fact per task, then one bottom-up
+        // fact per primitive task (names are only kept for printing)
+        let mut names: Vec<String> = (0..num_facts).map(|x| domain.facts.get_fact(x).clone()).collect();
+        let mut task_facts = vec![];
+        for task in tasks.iter() {
+            task_facts.push(names.len() as u32);
+            names.push(task.borrow().get_name());
+        }
+        let mut reachable_facts = vec![];
         for task in tasks.iter() {
+            if task.borrow().is_primitive() {
+                reachable_facts.push(Some(names.len() as u32));
+                names.push(task.borrow().get_name() + "_reachable");
+            } else {
+                reachable_facts.push(None);
+            }
+        }
+        let (actions, outcomes, origins, applied_tasks) = RelaxedComposition::encode(
+            &domain.tasks, &task_facts, &reachable_facts, determinized
+        );
+        RelaxedComposition {
+            tdg: TDG::with_outcome_tasks(&domain.init_tn, determinized.iter().map(|x| x.task).collect()),
+            htn_tasks: domain.tasks.clone(),
+            domain: ClassicalDomain::new(Facts::new(names), actions),
+            outcomes,
+            num_facts,
+            origins,
+            applied_tasks,
+            task_facts,
+            reachable_facts,
+        }
+    }
+
+    fn encode(
+        tasks: &DomainTasks,
+        task_facts: &[u32],
+        reachable_facts: &[Option<u32>],
+        determinized: &[DeterminizedAction]
+    ) -> (Vec<PrimitiveAction>, Vec<Vec<usize>>, Vec<ActionOrigin>, Vec<AppliedTask>) {
+        // outcome task -> (compound task standing for its action, name of the action)
+        let mut outcome_of = HashMap::new();
+        for action in determinized.iter() {
+            for outcome in action.outcomes.iter() {
+                outcome_of.insert(*outcome, (action.task, &action.name));
+            }
+        }
+        let compound_outcomes: HashSet<u32> = determinized.iter().map(|x| x.task).collect();
+        let mut outcomes: HashMap<u32, Vec<usize>> = HashMap::new();
+        let mut origins = vec![];
+        let mut applied_tasks = vec![];
+        let mut result = vec![];
+        for (task_id, task) in tasks.get_all_tasks().iter().enumerate() {
+            let task_id = task_id as u32;
             match &*task.borrow() {
                 Task::Compound(c) => {
-                    for method in c.methods.iter() {
-                        let subtasks = method.decomposition.get_all_tasks();
-                        let mut ids = HashSet::new();
-                        for subtask in subtasks.iter() {
-                            let task_name = subtask.borrow().get_name();
-                            ids.insert(facts.get_id(&task_name));
-                        }
-                        let task_id = facts.get_id(&task.borrow().get_name());
+                    for (i, method) in c.methods.iter().enumerate() {
+                        let ids = method.decomposition.mappings.values()
+                            .map(|x| task_facts[*x as usize])
+                            .collect();
                         let new_action = PrimitiveAction::new(
                             method.name.clone(),
-                            0,
+                            method.cost.unwrap_or(0),
                             ids,
-                            vec![HashSet::from([task_id])],
+                            vec![HashSet::from([task_facts[task_id as usize]])],
                             vec![HashSet::new()]
                         );
                         result.push(new_action);
+                        applied_tasks.push((task_id, Some(i)));
+                        // methods of the determinization pick an outcome
+                        if compound_outcomes.contains(&task_id) {
+                            origins.push(ActionOrigin::Outcome);
+                        } else {
+                            origins.push(ActionOrigin::Method(method.name.clone()));
+                        }
                     }
                 }
                 Task::Primitive(p) => {
@@ -63,18 +142,20 @@ impl RelaxedComposition  {
                         panic!("Relaxation assumes an all outcome determinized FOND problem");
                     }
                     // action executed effect
-                    let mut add_effects = HashSet::from([facts.get_id(&p.name)]);
+                    let mut add_effects = HashSet::from([task_facts[task_id as usize]]);
                     // canonical effects
                     if p.add_effects.len() == 1 {
                         add_effects.extend(p.add_effects[0].clone());
                     }
-                    if p.name.contains("__determinized_") {
-                        let re = Regex::new(r"__determinized_[0-9]+").unwrap();
-                        let cleansed_name = re.replace(&p.name, "__determinized").to_string();
-                        let fact_id = facts.get_id(&cleansed_name);
-                        add_effects.insert(fact_id);
+                    match outcome_of.get(&task_id) {
+                        Some((compound, name)) => {
+                            add_effects.insert(task_facts[*compound as usize]);
+                            outcomes.entry(*compound).or_default().push(result.len());
+                            origins.push(ActionOrigin::Action(name.to_string()));
+                        },
+                        None => origins.push(ActionOrigin::Action(p.name.clone())),
                     }
-                    let top_down_precond = facts.get_id(&(p.name.clone() + "_reachable"));
+                    let top_down_precond = reachable_facts[task_id as usize].unwrap();
                     let mut preconds = HashSet::from([top_down_precond]);
                     preconds.extend(p.pre_cond.clone());
                     let new_action = PrimitiveAction::new(
@@ -85,46 +166,45 @@ impl RelaxedComposition  {
                         p.del_effects.clone()
                     );
                     result.push(new_action);
+                    applied_tasks.push((task_id, None));
                 }
             }
         };
-        result
+        let mut outcomes: Vec<Vec<usize>> = outcomes.into_values().collect();
+        outcomes.sort();
+        (result, outcomes, origins, applied_tasks)
     }
 
     pub fn compute_relaxed_state(&self, task_ids: &Vec<u32>, state: &HashSet<u32>) -> HashSet<u32> {
-        let reachables = self.tdg.all_reachables(task_ids);
-        let mut satisfied_preconds = HashSet::new();
-        for task in reachables.iter() {
-            let task = self.htn_tasks.get_task(*task);
-            if let Task::Primitive(prim) = &*task.borrow() {
-                let mut fact_name = prim.name.clone();
-                if !prim.is_deterministic() {
-                    fact_name += "__determinized";
-                    let n_effects = prim.add_effects.len() as u32;
-                    for i in 0..n_effects {
-                        let outcome = fact_name.clone() + "_" + &i.to_string() + "_reachable";
-                        let fact_id = self.domain.facts.get_id(&outcome);
-                        satisfied_preconds.insert(fact_id);
-                    }
-                } else {
-                    fact_name += "_reachable";
-                    let fact_id = self.domain.facts.get_id(&fact_name);
-                    satisfied_preconds.insert(fact_id);
-                }
-            }
-        }
+        let mut satisfied_preconds: HashSet<u32> = self.tdg.all_reachables(task_ids).iter()
+            .filter_map(|x| self.reachable_facts[*x as usize])
+            .collect();
         satisfied_preconds.extend(state);
-        satisfied_preconds     
+        satisfied_preconds
     }
 
-    pub fn compute_goal_state(&self, task_ids: &Vec<u32>) -> HashSet<u32> {
-        let mut goal = HashSet::new();
-        for task in task_ids {
-            let mut name = self.htn_tasks.get_task(*task).borrow().get_name();
-            let g = self.domain.facts.get_id(&name);
-            goal.insert(g);
-        }
-        goal
+    pub fn compute_goal_state(&self, task_ids: &[u32]) -> HashSet<u32> {
+        task_ids.iter().map(|x| self.task_facts[*x as usize]).collect()
+    }
+
+    pub fn origin(&self, action: usize) -> &ActionOrigin {
+        &self.origins[action]
+    }
+
+    pub fn applied_task(&self, action: usize) -> AppliedTask {
+        self.applied_tasks[action]
+    }
+
+    pub fn num_facts(&self) -> u32 {
+        self.num_facts
+    }
+
+    pub fn outcomes(&self) -> &[Vec<usize>] {
+        &self.outcomes
+    }
+
+    pub fn tdg(&self) -> &TDG {
+        &self.tdg
     }
 
     pub fn print_tdg(&self) {
@@ -247,10 +327,10 @@ mod tests {
     pub fn encoding_test() {
         let problem = generate_problem();
         let to_classical = RelaxedComposition::new(&problem);
-        let encoded = to_classical.domain;
+        let encoded = &to_classical.domain;
         assert_eq!(encoded.facts.count(), 17);
         assert_eq!(encoded.actions.len(), 9);
-        for action in encoded.actions.iter() {
+        for (i, action) in encoded.actions.iter().enumerate() {
             let mut name = action.name.clone();
             let flag = name.ends_with("_m");
             if flag {
@@ -258,7 +338,10 @@ mod tests {
             }
             let effect_id = encoded.facts.get_id(&name);
             assert_eq!(action.add_effects[0].contains(&effect_id), true);
-            if !flag {
+            if flag {
+                assert_eq!(*to_classical.origin(i), ActionOrigin::Method(action.name.clone()));
+            } else {
+                assert_eq!(*to_classical.origin(i), ActionOrigin::Action(action.name.clone()));
                 let precond_id = encoded.facts.get_id(&(name + "_reachable"));
                 assert_eq!(action.pre_cond.contains(&precond_id), true);
             }
@@ -309,7 +392,7 @@ mod tests {
             problem.tasks.clone(),
             HashMap::from([(1, problem.tasks.get_id("t1")), (2, problem.tasks.get_id("p2"))])
         );
-        let goal = to_classical.compute_goal_state(&vec![
+        let goal = to_classical.compute_goal_state(&[
             problem.tasks.get_id("t1"), problem.tasks.get_id("p2")
         ]);
         assert_eq!(goal.len(), 2);
@@ -318,4 +401,44 @@ mod tests {
         assert_eq!(goal.contains(&id_t1), true);
         assert_eq!(goal.contains(&id_p2), true);
     }
+
+    // a deterministic action named like an outcome of a nondeterministic one
+    #[test]
+    pub fn determinized_names_test() {
+        let a = Task::Primitive(PrimitiveAction::new(
+            "a".to_string(), 1, HashSet::from([0]), vec![HashSet::from([1]), HashSet::from([2])], vec![HashSet::new(), HashSet::new()]
+        ));
+        let b = Task::Primitive(PrimitiveAction::new(
+            "a__determinized_0".to_string(), 1, HashSet::new(), vec![HashSet::from([2])], vec![HashSet::new()]
+        ));
+        let top = Task::Compound(CompoundTask { name: "top".to_string(), methods: vec![] });
+        let domain = Rc::new(DomainTasks::new(vec![a, b, top]));
+        let m = Method::new("m".to_string(), HTN::new(
+            BTreeSet::from([1, 2]), vec![(2, 1)], domain.clone(), HashMap::from([(1, 0), (2, 1)])
+        ));
+        let domain = domain.add_methods(vec![(2, m)]);
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec!["0".to_string(), "1".to_string(), "2".to_string()]),
+            tasks: domain.clone(),
+            initial_state: HashSet::from([0]),
+            init_tn: HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, 2)])),
+        };
+        problem.collapse_tn();
+        let (encoder, bijection) = RelaxedComposition::from_fond_problem(&problem);
+        // only the outcomes of a are grouped
+        assert_eq!(encoder.outcomes().len(), 1);
+        for action in encoder.outcomes()[0].iter() {
+            assert_eq!(*encoder.origin(*action), ActionOrigin::Action("a".to_string()));
+        }
+        let user_action: Vec<usize> = (0..encoder.domain.actions.len())
+            .filter(|x| *encoder.origin(*x) == ActionOrigin::Action("a__determinized_0".to_string()))
+            .collect();
+        assert_eq!(user_action.len(), 1);
+        assert!(!encoder.outcomes()[0].contains(&user_action[0]));
+        // both outcomes and the user action are reachable from the top task
+        let tasks = vec![*bijection.get(&problem.init_tn.mappings[&1]).unwrap()];
+        let state = encoder.compute_relaxed_state(&tasks, &HashSet::from([0]));
+        assert_eq!(state.len(), 4);
+        assert_eq!(encoder.compute_goal_state(&tasks).len(), 1);
+    }
 }
@@ -0,0 +1,260 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+
+use super::{Task, CompoundTask};
+use crate::domain_description::{DomainTasks, FONDProblem};
+use crate::heuristics::TDG;
+use crate::task_network::Method;
+
+pub struct RelevancePruning {}
+
+// Names of the elements removed from a problem
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PruningReport {
+    // tasks that can not be reached from the initial task
+    pub unreachable_tasks: Vec<String>,
+    // compound tasks that can not be decomposed into primitive tasks
+    pub undecomposable_tasks: Vec<String>,
+    // actions whose preconditions are unreachable under delete relaxation
+    pub inapplicable_actions: Vec<String>,
+    // tasks that only occur in removed methods
+    pub tasks_in_removed_methods: Vec<String>,
+    // methods of the removed tasks and methods containing them
+    pub removed_methods: Vec<String>,
+}
+
+impl RelevancePruning {
+    // Removes the tasks and methods that can not be part of a solution.
+    // Removing them may make other elements irrelevant, so this is repeated
+    // until nothing changes. Task ids of the result are compacted.
+    pub fn prune(problem: &FONDProblem) -> (FONDProblem, PruningReport) {
+        // We assume a collapsed network (i.e., with only one init abstract task)
+        if problem.init_tn.count_tasks() > 1 {
+            panic!("tn not in collapsed format")
+        }
+        let mut report = PruningReport::default();
+        let (mut pruned, mut changed) = RelevancePruning::prune_step(problem, &mut report);
+        while changed {
+            let (next, next_changed) = RelevancePruning::prune_step(&pruned, &mut report);
+            pruned = next;
+            changed = next_changed;
+        }
+        (pruned, report)
+    }
+
+    // One pass of the three criteria, returns whether something was removed
+    fn prune_step(problem: &FONDProblem, report: &mut PruningReport) -> (FONDProblem, bool) {
+        let domain = &problem.tasks;
+        let root = *problem.init_tn.mappings.values().next().unwrap();
+        let tdg = TDG::new(&problem.init_tn);
+        let reachable = tdg.task_reachability(root);
+        let applicable = tdg.relaxed_reachable_actions(&vec![root], &problem.initial_state);
+        // (task, method index, subtasks) of the reachable compound tasks
+        let mut methods = vec![];
+        for task in reachable.iter() {
+            if let Task::Compound(compound) = &*domain.get_task(*task).borrow() {
+                for (i, method) in compound.methods.iter().enumerate() {
+                    let subtasks: Vec<u32> = method.decomposition.mappings.values().cloned().collect();
+                    methods.push((*task, i, subtasks));
+                }
+            }
+        }
+        // bottom-up fixpoint over the methods
+        let mut decomposable = applicable.clone();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (task, _, subtasks) in methods.iter() {
+                if !decomposable.contains(task) && subtasks.iter().all(|x| decomposable.contains(x)) {
+                    decomposable.insert(*task);
+                    changed = true;
+                }
+            }
+        }
+        let kept_methods: HashSet<(u32, usize)> = methods.iter()
+            .filter(|(_, _, subtasks)| subtasks.iter().all(|x| decomposable.contains(x)))
+            .map(|(task, i, _)| (*task, *i))
+            .collect();
+        // top-down reachability over the remaining methods, the initial task
+        // is kept even if it can not be decomposed
+        let mut kept = BTreeSet::from([root]);
+        let mut working_set = vec![root];
+        while let Some(task) = working_set.pop() {
+            for (parent, i, subtasks) in methods.iter() {
+                if *parent == task && kept_methods.contains(&(*parent, *i)) {
+                    for subtask in subtasks.iter() {
+                        if kept.insert(*subtask) {
+                            working_set.push(*subtask);
+                        }
+                    }
+                }
+            }
+        }
+        let mut changed = false;
+        for (id, task) in domain.get_all_tasks().iter().enumerate() {
+            let id = id as u32;
+            let task = task.borrow();
+            if !kept.contains(&id) {
+                changed = true;
+                let name = task.get_name();
+                if !reachable.contains(&id) {
+                    report.unreachable_tasks.push(name);
+                } else if task.is_primitive() && !applicable.contains(&id) {
+                    report.inapplicable_actions.push(name);
+                } else if !task.is_primitive() && !decomposable.contains(&id) {
+                    report.undecomposable_tasks.push(name);
+                } else {
+                    report.tasks_in_removed_methods.push(name);
+                }
+            }
+            if let Task::Compound(compound) = &*task {
+                for (i, method) in compound.methods.iter().enumerate() {
+                    if !kept.contains(&id) || !kept_methods.contains(&(id, i)) {
+                        changed = true;
+                        report.removed_methods.push(method.name.clone());
+                    }
+                }
+            }
+        }
+        (RelevancePruning::restrict(problem, &kept, &kept_methods), changed)
+    }
+
+    // The problem with only the given tasks and methods, along with compacted
+    // task ids
+    fn restrict(problem: &FONDProblem, kept: &BTreeSet<u32>, kept_methods: &HashSet<(u32, usize)>) -> FONDProblem {
+        let bijection: HashMap<u32, u32> = kept.iter().enumerate()
+            .map(|(new_id, prev_id)| (*prev_id, new_id as u32))
+            .collect();
+        let mut new_tasks = vec![];
+        let mut methods: Vec<(u32, Method)> = vec![];
+        for prev_id in kept.iter() {
+            let task = problem.tasks.get_task(*prev_id).borrow();
+            match &*task {
+                Task::Primitive(_) => new_tasks.push(task.clone()),
+                Task::Compound(compound) => {
+                    for (i, method) in compound.methods.iter().enumerate() {
+                        if kept_methods.contains(&(*prev_id, i)) {
+                            let mut method = method.clone();
+                            method.decomposition.mappings = method.decomposition.mappings.iter()
+                                .map(|(node, task_id)| (*node, bijection[task_id]))
+                                .collect();
+                            methods.push((bijection[prev_id], method));
+                        }
+                    }
+                    new_tasks.push(Task::Compound(CompoundTask::new(compound.name.clone(), vec![])));
+                }
+            }
+        }
+        let domain = Rc::new(DomainTasks::new(new_tasks)).add_methods(methods);
+        let mut init_tn = problem.init_tn.clone();
+        init_tn.mappings = init_tn.mappings.iter()
+            .map(|(node, task_id)| (*node, bijection[task_id]))
+            .collect();
+        init_tn.change_domain(domain.clone());
+        FONDProblem {
+            facts: problem.facts.clone(),
+            tasks: domain,
+            initial_state: problem.initial_state.clone(),
+            init_tn,
+        }
+    }
+}
+
+impl std::fmt::Display for PruningReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "# of unreachable tasks: {}", self.unreachable_tasks.len())?;
+        writeln!(f, "# of undecomposable tasks: {}", self.undecomposable_tasks.len())?;
+        writeln!(f, "# of inapplicable actions: {}", self.inapplicable_actions.len())?;
+        writeln!(f, "# of tasks only in removed methods: {}", self.tasks_in_removed_methods.len())?;
+        writeln!(f, "# of removed methods: {}", self.removed_methods.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap, HashSet};
+
+    use super::*;
+    use crate::domain_description::Facts;
+    use crate::search::{AOStarSearch, HeuristicType};
+    use crate::task_network::{HTN, PrimitiveAction};
+
+    fn action(name: &str, pre: Vec<u32>, add: Vec<u32>) -> Task {
+        Task::Primitive(PrimitiveAction::new(
+            name.to_string(), 1, pre.into_iter().collect(), vec![add.into_iter().collect()], vec![HashSet::new()]
+        ))
+    }
+
+    // "top" is decomposed by "m_a" into a, by "m_b" into b and d (b needs 2,
+    // which is never added), or by "m_loop" into "loop", which only
+    // decomposes into itself. "c" appears in no method.
+    fn generate_problem() -> FONDProblem {
+        let tasks = vec![
+            action("a", vec![], vec![0]),
+            action("b", vec![2], vec![1]),
+            action("c", vec![], vec![2]),
+            action("d", vec![], vec![1]),
+            Task::Compound(CompoundTask::new("loop".to_string(), vec![])),
+            Task::Compound(CompoundTask::new("top".to_string(), vec![])),
+        ];
+        let domain = Rc::new(DomainTasks::new(tasks));
+        let network = |tasks: Vec<&str>| HTN::new(
+            (1..=tasks.len() as u32).collect(),
+            vec![],
+            domain.clone(),
+            tasks.iter().enumerate().map(|(i, x)| (i as u32 + 1, domain.get_id(x))).collect()
+        );
+        let top = domain.get_id("top");
+        let domain = domain.add_methods(vec![
+            (top, Method::new("m_a".to_string(), network(vec!["a"]))),
+            (top, Method::new("m_b".to_string(), network(vec!["b", "d"]))),
+            (top, Method::new("m_loop".to_string(), network(vec!["loop"]))),
+            (domain.get_id("loop"), Method::new("loop_m".to_string(), network(vec!["loop"]))),
+        ]);
+        let init_tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, top)]));
+        FONDProblem {
+            facts: Facts::new(vec!["0".to_string(), "1".to_string(), "2".to_string()]),
+            tasks: domain,
+            initial_state: HashSet::new(),
+            init_tn,
+        }
+    }
+
+    #[test]
+    pub fn pruning_test() {
+        let problem = generate_problem();
+        let (pruned, report) = RelevancePruning::prune(&problem);
+        assert_eq!(report.inapplicable_actions, vec!["b".to_string()]);
+        assert_eq!(report.undecomposable_tasks, vec!["loop".to_string()]);
+        assert_eq!(report.unreachable_tasks, vec!["c".to_string()]);
+        // d is only reachable through m_b
+        assert_eq!(report.tasks_in_removed_methods, vec!["d".to_string()]);
+        let mut methods = report.removed_methods.clone();
+        methods.sort();
+        assert_eq!(methods, vec!["loop_m".to_string(), "m_b".to_string(), "m_loop".to_string()]);
+        // ids are compacted
+        assert_eq!(pruned.tasks.count_tasks(), 2);
+        assert_eq!(pruned.tasks.get_id("a"), 0);
+        assert_eq!(pruned.tasks.get_id("top"), 1);
+        assert_eq!(pruned.init_tn.mappings[&1], 1);
+        match &*pruned.tasks.get_task(1).borrow() {
+            Task::Compound(compound) => {
+                assert_eq!(compound.methods.len(), 1);
+                assert_eq!(compound.methods[0].decomposition.mappings[&1], 0);
+            },
+            _ => panic!("top is compound"),
+        }
+        // nothing else can be removed
+        let (_, report) = RelevancePruning::prune(&pruned);
+        assert_eq!(report, PruningReport::default());
+    }
+
+    #[test]
+    pub fn pruned_search_test() {
+        let mut problem = generate_problem();
+        problem.collapse_tn();
+        let (pruned, _) = RelevancePruning::prune(&problem);
+        let (result, _) = AOStarSearch::run(&pruned, HeuristicType::HAdd);
+        assert!(result.is_success());
+    }
+}
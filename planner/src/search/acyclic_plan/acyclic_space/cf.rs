@@ -1,7 +1,7 @@
 use std::collections::{BinaryHeap, HashSet, HashMap};
 use crate::{domain_description::FONDProblem, task_network::HTN};
 
-use super::{SearchResult, SearchGraph, SearchStats, h_type, HeuristicType};
+use super::{SearchResult, SearchGraph, SearchStats, SearchConfig, HeuristicCache, Heuristic, HeuristicType, TipSelection, NOVELTY_WIDTH};
 use std::time::{Instant, Duration};
 
 pub struct AOStarSearch {
@@ -10,13 +10,37 @@ pub struct AOStarSearch {
 impl AOStarSearch {
     // the initial TN is assumed to be in collapsed format (i.e., with a single abstract task)
     pub fn run(problem: &FONDProblem, h_type: HeuristicType) -> (SearchResult, SearchStats) {
+        AOStarSearch::run_with_config(problem, h_type, &SearchConfig::default())
+    }
+
+    pub fn run_with_config(problem: &FONDProblem, h_type: HeuristicType, config: &SearchConfig) -> (SearchResult, SearchStats) {
+        AOStarSearch::run_with_heuristic(problem, Box::new(h_type), config)
+    }
+
+    pub fn run_with_heuristic(problem: &FONDProblem, mut heuristic: Box<dyn Heuristic>, config: &SearchConfig) -> (SearchResult, SearchStats) {
         let mut explored_nodes: u32 = 0;
         let mut max_depth = 0;
         let start_time = Instant::now();
         let mut search_graph = SearchGraph::new(problem);
+        search_graph.h_cache = HeuristicCache::new(config.cache_size);
+        search_graph.preferred_operators = config.preferred_operators;
+        search_graph.free_decompositions = config.free_decompositions;
+        if config.tip_selection == TipSelection::Novelty {
+            search_graph.init_novelty(NOVELTY_WIDTH);
+        }
+        if heuristic.needs_landmarks() {
+            search_graph.init_landmarks(problem.facts.count());
+        }
+        let mut limit_reached = false;
         while !search_graph.is_terminated() {
-            let n = search_graph.find_a_tip_node();
-            search_graph.expand(n, &h_type, false);
+            let out_of_nodes = config.node_limit.is_some_and(|limit| explored_nodes >= limit);
+            let out_of_time = config.time_limit.is_some_and(|limit| start_time.elapsed() >= limit);
+            if out_of_nodes || out_of_time {
+                limit_reached = true;
+                break;
+            }
+            let n = search_graph.select_tip_node(&config.tip_selection);
+            search_graph.expand(n, heuristic.as_mut(), config.weight, false);
             search_graph.backward_cost_revision(n);
             explored_nodes+=1;
             let depth = search_graph.ids.get(&n).unwrap().borrow().depth;
@@ -24,13 +48,21 @@ impl AOStarSearch {
                 max_depth = depth;
             }
         }
-        let result = search_graph.search_result(&problem.facts);
+        let result = if limit_reached {
+            SearchResult::LimitReached
+        } else {
+            search_graph.search_result(&problem.facts)
+        };
         let stats = SearchStats {
             max_depth: max_depth,
             search_nodes: search_graph.ids.len() as u32,
             explored_nodes: explored_nodes,
-            seach_time: start_time.elapsed()
+            seach_time: start_time.elapsed(),
+            landmarks: search_graph.landmarks.as_ref().map(|(x, _)| x.stats()),
+            cache_hits: search_graph.h_cache.hits,
+            cache_misses: search_graph.h_cache.misses,
+            undecomposable_nodes: search_graph.undecomposable_nodes,
         };
         (result, stats)
     }
-}
\ No newline at end of file
+}
@@ -102,6 +102,8 @@ impl SearchGraph {
 
     fn compute_min_cost(&self, connections: &NodeConnections) -> (f32, u32) {
         let (mut min_cost, mut arg_min) = (f32::INFINITY, u32::max_value());
+        // solved arcs are preferred, the cheapest one if there are several
+        let mut solved: Option<(f32, u32)> = None;
         for (i, arc) in connections.children.iter().enumerate() {
             let mut branch_cost = arc.cost;
             let mut is_solved = true;
@@ -114,13 +116,19 @@ impl SearchGraph {
                 }
             }
             if is_solved {
-                return (branch_cost, i as u32);
+                if solved.is_none_or(|(cost, _)| branch_cost < cost) {
+                    solved = Some((branch_cost, i as u32));
+                }
+                continue;
             }
             if branch_cost < min_cost {
                 min_cost = branch_cost;
                 arg_min = i as u32;
             }
         }
+        if let Some(result) = solved {
+            return result;
+        }
         if min_cost.is_infinite() {
             panic!("empty node connection")
         }
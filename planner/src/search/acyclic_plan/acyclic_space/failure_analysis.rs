@@ -0,0 +1,337 @@
+use std::collections::HashSet;
+
+use super::*;
+use crate::domain_description::Facts;
+use crate::heuristics::h_max;
+
+// Why a leaf of the counter-strategy fails
+#[derive(Debug, Clone, PartialEq)]
+pub enum FailureReason {
+    // the network is not empty, but no task can be progressed
+    DeadEnd,
+    // these tasks can never be decomposed, whatever the state
+    Undecomposable(Vec<String>),
+    // relaxed reachability (infinite h) shows that these tasks can not be
+    // accomplished from the state
+    Unreachable(Vec<String>),
+    // the node has already been refuted elsewhere in the counter-strategy
+    Refuted(u32),
+}
+
+#[derive(Debug, Clone)]
+pub struct FailedNode {
+    pub id: u32,
+    pub depth: u16,
+    pub state: Vec<String>,
+    // names of the remaining tasks (with repetitions)
+    pub tasks: Vec<String>,
+}
+
+// Refutation of a failed search node: for every choice the planner can
+// make, the environment picks an outcome that again leads to failure
+#[derive(Debug)]
+pub struct CounterStrategy {
+    pub node: FailedNode,
+    pub refutation: Refutation,
+}
+
+#[derive(Debug)]
+pub enum Refutation {
+    Leaf(FailureReason),
+    // (planner's choice, outcome picked by the environment, its refutation)
+    Choices(Vec<(String, usize, CounterStrategy)>),
+}
+
+#[derive(Debug)]
+pub struct FailureReport {
+    pub strategy: CounterStrategy,
+    // deepest leaf of the counter-strategy that is not refuted elsewhere,
+    // None if all of them are
+    pub deepest: Option<(FailedNode, FailureReason)>,
+}
+
+impl SearchGraph {
+    // Extracts a counter-strategy from the failed root
+    pub fn failure_report(&self, facts: &Facts) -> FailureReport {
+        let mut refuted = HashSet::new();
+        let strategy = self.refute(self.root, facts, &mut refuted);
+        let mut deepest: Option<(FailedNode, FailureReason)> = None;
+        let mut working_set = vec![&strategy];
+        while let Some(x) = working_set.pop() {
+            match &x.refutation {
+                Refutation::Leaf(reason) => {
+                    let is_deeper = match &deepest {
+                        Some((node, _)) => x.node.depth > node.depth,
+                        None => true,
+                    };
+                    if is_deeper && !matches!(reason, FailureReason::Refuted(_)) {
+                        deepest = Some((x.node.clone(), reason.clone()));
+                    }
+                },
+                Refutation::Choices(choices) => {
+                    working_set.extend(choices.iter().map(|(_, _, child)| child));
+                }
+            }
+        }
+        FailureReport { deepest, strategy }
+    }
+
+    fn refute(&self, id: u32, facts: &Facts, refuted: &mut HashSet<u32>) -> CounterStrategy {
+        let node = self.ids.get(&id).unwrap().borrow();
+        let failed_node = FailedNode::new(id, &node, facts);
+        if !refuted.insert(id) {
+            return CounterStrategy { node: failed_node, refutation: Refutation::Leaf(FailureReason::Refuted(id)) };
+        }
+        let connections = match &node.connections {
+            Some(connections) => connections,
+            None => {
+                let undecomposable = self.undecomposable_tasks(&node);
+                if !undecomposable.is_empty() {
+                    let reason = FailureReason::Undecomposable(undecomposable);
+                    return CounterStrategy { node: failed_node, refutation: Refutation::Leaf(reason) };
+                }
+                let unreachable = self.unreachable_tasks(&node);
+                let reason = if unreachable.is_empty() && progress(node.tn.clone(), node.state.clone()).is_empty() {
+                    FailureReason::DeadEnd
+                } else {
+                    FailureReason::Unreachable(unreachable)
+                };
+                return CounterStrategy { node: failed_node, refutation: Refutation::Leaf(reason) };
+            }
+        };
+        let mut choices = vec![];
+        for connector in connections.children.iter() {
+            // a connector without failed children can not be refuted, which
+            // only happens if the node did not fail through its children
+            let failed_child = match connector.children.iter()
+                .filter(|child| matches!(self.ids.get(child).unwrap().borrow().status, NodeStatus::Failed))
+                .min() {
+                Some(x) => x,
+                None => continue,
+            };
+            let outcome = match &connector.action_type {
+                ConnectionLabel::Decomposition(..) => 0,
+                ConnectionLabel::Execution(name, _) => {
+                    let child_state = self.ids.get(failed_child).unwrap().borrow().state.clone();
+                    match &*node.tn.domain.get_task(node.tn.domain.get_id(name)).borrow() {
+                        Task::Primitive(action) => action.transition(&node.state).iter()
+                            .position(|outcome| *outcome == *child_state)
+                            .unwrap(),
+                        Task::Compound(_) => panic!("{} is not primitive", name),
+                    }
+                },
+            };
+            let label = match &connector.action_type {
+                ConnectionLabel::Decomposition(task, method, _) => format!("{} ({})", task, method),
+                ConnectionLabel::Execution(name, _) => name.clone(),
+            };
+            choices.push((label, outcome, self.refute(*failed_child, facts, refuted)));
+        }
+        CounterStrategy { node: failed_node, refutation: Refutation::Choices(choices) }
+    }
+
+    fn undecomposable_tasks(&self, node: &SearchGraphNode) -> Vec<String> {
+        let mut tasks: Vec<String> = node.tn.count_tasks_with_frequency().into_keys()
+            .filter(|x| self.decomposable.get(*x as usize) == Some(&false))
+            .map(|x| node.tn.domain.get_task(x).borrow().get_name())
+            .collect();
+        tasks.sort();
+        tasks
+    }
+
+    // Tasks whose relaxed goal is not reachable from the node on its own
+    fn unreachable_tasks(&self, node: &SearchGraphNode) -> Vec<String> {
+        let (encoder, bijection) = match &self.relaxed_domain {
+            Some(x) => x,
+            None => return vec![],
+        };
+        let tasks: Vec<u32> = node.tn.count_tasks_with_frequency().into_keys().collect();
+        let task_ids: Vec<u32> = tasks.iter().map(|task| *bijection.get(task).unwrap()).collect();
+        let relaxed_state = encoder.compute_relaxed_state(&task_ids, &node.state);
+        let mut unreachable: Vec<String> = tasks.iter()
+            .zip(task_ids.iter())
+            .filter(|(_, relaxed_id)| {
+                let goal = encoder.compute_goal_state(&[**relaxed_id]);
+                h_max(&encoder.domain, &relaxed_state, &goal) == f32::INFINITY
+            })
+            .map(|(task, _)| node.tn.domain.get_task(*task).borrow().get_name())
+            .collect();
+        unreachable.sort();
+        unreachable
+    }
+}
+
+impl FailedNode {
+    fn new(id: u32, node: &SearchGraphNode, facts: &Facts) -> FailedNode {
+        // facts the problem does not name are shown by id
+        let mut state: Vec<String> = node.state.iter()
+            .map(|x| if *x < facts.count() { facts.get_fact(*x).clone() } else { format!("fact {}", x) })
+            .collect();
+        state.sort();
+        let mut tasks: Vec<String> = node.tn.get_nodes()
+            .map(|x| node.tn.get_task(x).borrow().get_name())
+            .collect();
+        tasks.sort();
+        FailedNode { id, depth: node.depth, state, tasks }
+    }
+}
+
+impl std::fmt::Display for FailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::DeadEnd => write!(f, "dead end, no task can be progressed"),
+            Self::Undecomposable(tasks) => write!(f, "tasks {:?} can never be decomposed", tasks),
+            Self::Unreachable(tasks) if tasks.is_empty() => write!(f, "relaxed goal is unreachable"),
+            Self::Unreachable(tasks) => write!(f, "unreachable tasks {:?}", tasks),
+            Self::Refuted(id) => write!(f, "node {} is refuted above", id),
+        }
+    }
+}
+
+impl std::fmt::Display for FailedNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "node {} (depth {}), tasks: {:?}, state: {:?}", self.id, self.depth, self.tasks, self.state)
+    }
+}
+
+impl CounterStrategy {
+    fn fmt_indented(&self, f: &mut std::fmt::Formatter<'_>, indent: usize) -> Result<(), std::fmt::Error> {
+        match &self.refutation {
+            Refutation::Leaf(reason) => writeln!(f, "{}: {}", self.node, reason),
+            Refutation::Choices(choices) => {
+                writeln!(f, "{}", self.node)?;
+                for (choice, outcome, child) in choices.iter() {
+                    write!(f, "{}{} -> outcome {}: ", "  ".repeat(indent + 1), choice, outcome)?;
+                    child.fmt_indented(f, indent + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for FailureReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "counter-strategy:")?;
+        self.strategy.fmt_indented(f, 0)?;
+        match &self.deepest {
+            Some((node, reason)) => writeln!(f, "deepest failure: {}: {}", node, reason),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::domain_description::{DomainTasks, FONDProblem};
+    use crate::task_network::{HTN, Method, PrimitiveAction, CompoundTask};
+
+    // m1 executes "nd" whose second outcome blocks "pa", m2 requires a
+    // fact that is never added
+    fn generate_problem() -> FONDProblem {
+        let nd = Task::Primitive(PrimitiveAction::new(
+            "nd".to_string(), 1, HashSet::new(),
+            vec![HashSet::from([0]), HashSet::from([1])], vec![HashSet::new(), HashSet::new()]
+        ));
+        let pa = Task::Primitive(PrimitiveAction::new(
+            "pa".to_string(), 1, HashSet::from([0]),
+            vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let seta = Task::Primitive(PrimitiveAction::new(
+            "seta".to_string(), 1, HashSet::new(),
+            vec![HashSet::from([0])], vec![HashSet::new()]
+        ));
+        let pc = Task::Primitive(PrimitiveAction::new(
+            "pc".to_string(), 1, HashSet::from([2]),
+            vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let t = Task::Compound(CompoundTask::new("t".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![nd, pa, seta, pc, t]));
+        let m1 = Method::new("m1".to_string(), HTN::new(
+            BTreeSet::from([1, 2, 3]), vec![(1, 2), (2, 3)], domain.clone(),
+            HashMap::from([(1, domain.get_id("nd")), (2, domain.get_id("pa")), (3, domain.get_id("seta"))])
+        ));
+        let m2 = Method::new("m2".to_string(), HTN::new(
+            BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("pc"))])
+        ));
+        let domain = domain.add_methods(vec![(domain.get_id("t"), m1), (domain.get_id("t"), m2)]);
+        let tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("t"))]));
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec!["a".to_string(), "b".to_string(), "c".to_string()]),
+            tasks: domain,
+            initial_state: HashSet::new(),
+            init_tn: tn
+        };
+        problem.collapse_tn();
+        problem
+    }
+
+    #[test]
+    pub fn counter_strategy_test() {
+        let problem = generate_problem();
+        let (result, stats) = AOStarSearch::run(&problem, HeuristicType::HAdd);
+        let report = match result {
+            SearchResult::NoSolution(report) => report,
+            _ => panic!("problem is unsolvable")
+        };
+        assert_eq!(stats.undecomposable_nodes, 1);
+        // collapsed top task has a single method
+        let choices = match &report.strategy.refutation {
+            Refutation::Choices(choices) => choices,
+            Refutation::Leaf(_) => panic!("root has been expanded")
+        };
+        assert_eq!(choices.len(), 1);
+        let choices = match &choices[0].2.refutation {
+            Refutation::Choices(choices) => choices,
+            Refutation::Leaf(_) => panic!("t has been expanded")
+        };
+        assert_eq!(choices.len(), 2);
+        for (choice, outcome, child) in choices.iter() {
+            assert_eq!(*outcome, 0);
+            if choice == "t (m2)" {
+                // c is never added, so pc fails before any heuristic is computed
+                let reason = FailureReason::Undecomposable(vec!["pc".to_string()]);
+                assert!(matches!(&child.refutation, Refutation::Leaf(x) if *x == reason));
+            } else {
+                // the environment picks the second outcome of "nd"
+                match &child.refutation {
+                    Refutation::Choices(x) => {
+                        assert_eq!(x.len(), 1);
+                        assert_eq!(x[0].0, "nd");
+                        assert_eq!(x[0].1, 1);
+                        assert!(matches!(x[0].2.refutation, Refutation::Leaf(FailureReason::DeadEnd)));
+                    },
+                    Refutation::Leaf(_) => panic!("m1 has been expanded")
+                }
+            }
+        }
+        let (node, reason) = report.deepest.unwrap();
+        assert_eq!(reason, FailureReason::DeadEnd);
+        assert_eq!(node.tasks, vec!["pa".to_string(), "seta".to_string()]);
+        assert_eq!(node.state, vec!["b".to_string()]);
+    }
+
+    // every leaf below the root is a node refuted above, as happens when a
+    // node is reached again through one of its successors
+    #[test]
+    pub fn refuted_leaves_test() {
+        let problem = generate_problem();
+        let mut graph = SearchGraph::new(&problem);
+        graph.expand(graph.root, &mut HeuristicType::HAdd, 1.0, false);
+        let child = graph.cursor - 1;
+        let label = ConnectionLabel::Decomposition("t".to_string(), "m1".to_string(), None);
+        graph.ids.get(&child).unwrap().borrow_mut().connections = Some(NodeConnections { children: vec![
+            Connector { children: HashSet::from([graph.root]), cost: 1.0, is_marked: false, action_type: label }
+        ]});
+        for id in [graph.root, child] {
+            graph.ids.get(&id).unwrap().borrow_mut().status = NodeStatus::Failed;
+        }
+        let report = graph.failure_report(&problem.facts);
+        assert!(report.deepest.is_none());
+        assert!(report.to_string().ends_with("node 1 is refuted above\n"));
+    }
+}
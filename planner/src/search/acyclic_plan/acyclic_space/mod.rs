@@ -1,9 +1,11 @@
 mod cost_revision;
 mod search_result;
 mod cf;
+mod failure_analysis;
 mod test_cases;
 
 use super::*;
 pub use search_result::SearchResult;
 use search_graph::*;
-pub use cf::AOStarSearch;
\ No newline at end of file
+pub use cf::AOStarSearch;
+pub use failure_analysis::{FailureReport, FailureReason, CounterStrategy, Refutation, FailedNode};
\ No newline at end of file
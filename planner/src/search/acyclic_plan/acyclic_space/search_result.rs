@@ -3,14 +3,17 @@ use super::*;
 #[derive(Debug)]
 pub enum SearchResult {
     Success(StrongPolicy),
-    NoSolution
+    NoSolution(FailureReport),
+    // search stopped before the root was solved or failed
+    LimitReached,
 }
 
 impl SearchResult {
     pub fn is_success(&self) -> bool {
         match self {
             SearchResult::Success(_) => true,
-            SearchResult::NoSolution => false
+            SearchResult::NoSolution(_) => false,
+            SearchResult::LimitReached => false
         }
     }
 }
@@ -18,7 +21,11 @@ impl SearchResult {
 impl std::fmt::Display for SearchResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
         match self {
-            Self::NoSolution => write!(f, "Problem has no solution"),
+            Self::NoSolution(report) => {
+                writeln!(f, "Problem has no solution")?;
+                report.fmt(f)
+            },
+            Self::LimitReached => write!(f, "Search limit reached"),
             Self::Success(x) => {
                 x.fmt(f)
             }
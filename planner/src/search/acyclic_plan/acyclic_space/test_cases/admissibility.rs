@@ -0,0 +1,76 @@
+use crate::search::{to_csv, HeuristicEvaluation, HeuristicReport, HeuristicType};
+
+use super::*;
+use super::failure::failure_problem;
+use super::recursion_test::navigation_problem;
+
+// Checks h <= h* on every node of the (bounded) search space of the test
+// problems, and that AO* with the heuristic solves the solvable ones
+fn check_admissibility(mut h_type: HeuristicType) {
+    for (name, problem) in test_problems() {
+        let expected = match name {
+            "conformant" | "navigation" => Some(3.0),
+            "failure" => Some(f32::INFINITY),
+            _ => None,
+        };
+        let evaluation = HeuristicEvaluation::new(&problem, 12, u32::MAX);
+        // the recursion of navigation is cut off by the depth bound
+        assert_eq!(evaluation.truncated, name == "navigation", "{}", name);
+        let report = evaluation.evaluate(name, &mut h_type);
+        assert!(report.nodes > 0);
+        assert!(
+            report.is_admissible(),
+            "{}: {:?} exceeds the optimal cost on {} nodes", name, h_type, report.violations
+        );
+        let (result, _) = AOStarSearch::run(&problem, h_type);
+        assert_eq!(result.is_success(), evaluation.root_cost.is_some_and(f32::is_finite), "{}", name);
+        if let Some(expected) = expected {
+            assert_eq!(evaluation.root_cost, Some(expected), "{}", name);
+        }
+    }
+}
+
+#[test]
+pub fn lmcut_admissibility_test() {
+    check_admissibility(HeuristicType::LMCut);
+}
+
+#[test]
+pub fn lp_admissibility_test() {
+    check_admissibility(HeuristicType::LP);
+}
+
+#[test]
+pub fn hmax_wc_admissibility_test() {
+    check_admissibility(HeuristicType::HMaxWC);
+}
+
+#[test]
+pub fn evaluation_report_test() {
+    let evaluation = HeuristicEvaluation::new(&failure_problem(), 12, u32::MAX);
+    let reports: Vec<HeuristicReport> = [HeuristicType::HMax, HeuristicType::LMCut].into_iter()
+        .map(|mut h| evaluation.evaluate(&h.to_string(), &mut h))
+        .collect();
+    for report in reports.iter() {
+        assert_eq!(report.nodes as usize, evaluation.nodes());
+        assert!(report.detected_dead_ends <= report.dead_ends);
+        assert!(report.is_admissible());
+    }
+    // the root is a dead end
+    assert!(reports[0].dead_ends > 0);
+    let csv = to_csv(&reports);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], HeuristicReport::CSV_HEADER);
+    assert!(lines[1].starts_with("hmax,") && lines[2].starts_with("lmcut,"));
+    // a tight node limit cuts the exploration short
+    let evaluation = HeuristicEvaluation::new(&navigation_problem(), 12, 2);
+    assert!(evaluation.truncated && evaluation.nodes() <= 2);
+    // so does the depth bound, the nodes cut off have an unknown cost and
+    // are not counted as dead ends
+    let evaluation = HeuristicEvaluation::new(&navigation_problem(), 1, u32::MAX);
+    assert!(evaluation.truncated);
+    assert_eq!(evaluation.root_cost, None);
+    let report = evaluation.evaluate("hmax", &mut HeuristicType::HMax);
+    assert_eq!((report.nodes, report.unknown, report.dead_ends), (1, 1, 0));
+}
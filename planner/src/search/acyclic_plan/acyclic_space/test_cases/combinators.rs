@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use crate::relaxation::RelaxedComposition;
+use crate::search::{Heuristic, HeuristicContext, HeuristicType, MaxHeuristic, WeightedSum, Alternation, SearchConfig};
+
+use super::*;
+use super::dag_test::dag_problem;
+use super::recursion_test::navigation_problem;
+
+// without guidance, the search is not guaranteed to terminate on recursive
+// problems
+const NODE_LIMIT: u32 = 1000;
+
+// no guidance at all
+#[derive(Debug)]
+pub struct Blind;
+
+impl Heuristic for Blind {
+    fn h_val(&mut self, _: &HTN, _: &HashSet<u32>, _: &HeuristicContext) -> f32 {
+        0.0
+    }
+}
+
+#[test]
+pub fn combinator_values_test() {
+    let problem = navigation_problem();
+    let (encoder, bijection) = RelaxedComposition::from_fond_problem(&problem);
+    let context = HeuristicContext {
+        encoder: &encoder, bijection: &bijection, landmarks: None, free_decompositions: false
+    };
+    let tn = &problem.init_tn;
+    let state = &problem.initial_state;
+    let add = HeuristicType::HAdd.h_val(tn, state, &context);
+    let max = HeuristicType::HMax.h_val(tn, state, &context);
+    assert!(add > max);
+    let mut h = MaxHeuristic::new(vec![Box::new(HeuristicType::HMax), Box::new(HeuristicType::HAdd)]);
+    assert_eq!(h.h_val(tn, state, &context), add);
+    let mut h = WeightedSum::new(vec![(2.0, Box::new(HeuristicType::HAdd)), (0.5, Box::new(HeuristicType::HMax))]);
+    assert_eq!(h.h_val(tn, state, &context), 2.0 * add + 0.5 * max);
+    let mut h = Alternation::new(vec![Box::new(HeuristicType::HAdd), Box::new(HeuristicType::HMax)]);
+    assert!(!h.is_cacheable());
+    let values: Vec<f32> = (0..3).map(|_| {
+        h.next_expansion();
+        h.h_val(tn, state, &context)
+    }).collect();
+    assert_eq!(values, vec![add, max, add]);
+}
+
+#[test]
+pub fn custom_heuristic_test() {
+    let config = SearchConfig { node_limit: Some(NODE_LIMIT), ..SearchConfig::default() };
+    let (result, stats) = AOStarSearch::run_with_heuristic(&dag_problem(), Box::new(Blind), &config);
+    assert!(result.is_success());
+    assert!(stats.cache_misses > 0);
+    let problem = navigation_problem();
+    // landmarks are tracked for the combined heuristic
+    let h = MaxHeuristic::new(vec![Box::new(HeuristicType::LandmarkCount), Box::new(HeuristicType::HAdd)]);
+    assert!(h.needs_landmarks());
+    let (result, stats) = AOStarSearch::run_with_heuristic(&problem, Box::new(h), &config);
+    assert!(result.is_success());
+    assert!(stats.landmarks.is_some());
+    assert_eq!(stats.cache_hits + stats.cache_misses, 0);
+}
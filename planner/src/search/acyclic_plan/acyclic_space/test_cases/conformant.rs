@@ -7,8 +7,7 @@ use super::{HTN, PrimitiveAction, CompoundTask, Task, Applicability};
 use super::{AOStarSearch, FONDProblem};
 
 #[cfg(test)]
-#[test]
-pub fn conformant_test() {
+pub fn conformant_problem() -> FONDProblem {
     use std::collections::BTreeSet;
     use crate::domain_description::{Facts, DomainTasks};
 
@@ -49,6 +48,20 @@ pub fn conformant_test() {
         init_tn: tn
     };
     problem.collapse_tn();
+    problem
+}
+
+#[test]
+pub fn conformant_test() {
+    let problem = conformant_problem();
     let (result, _) = AOStarSearch::run(&problem, crate::search::acyclic_plan::HeuristicType::HAdd);
     assert_eq!(result.is_success(), true);
+    if let SearchResult::Success(policy) = result {
+        let metrics = crate::search::PolicyMetrics::new(&policy);
+        assert_eq!(metrics.worst_case_actions, 3);
+        assert_eq!(metrics.worst_case_cost, 3);
+        assert_eq!(metrics.branches, 2);
+        assert_eq!(metrics.max_decomposition_depth, 1);
+        assert_eq!(metrics.size, policy.transitions.len());
+    }
 }
\ No newline at end of file
@@ -7,8 +7,7 @@ use super::{HTN, PrimitiveAction, CompoundTask, Task, Applicability};
 use super::{AOStarSearch, FONDProblem};
 
 #[cfg(test)]
-#[test]
-pub fn dag_test() {
+pub fn dag_problem() -> FONDProblem {
     use std::collections::BTreeSet;
     use crate::domain_description::{Facts, DomainTasks};
 
@@ -69,6 +68,12 @@ pub fn dag_test() {
         init_tn: tn
     };
     problem.collapse_tn();
+    problem
+}
+
+#[test]
+pub fn dag_test() {
+    let problem = dag_problem();
     let (result, _) = AOStarSearch::run(&problem, crate::search::acyclic_plan::HeuristicType::HAdd);
     assert_eq!(result.is_success(), true);
 }
\ No newline at end of file
@@ -7,8 +7,7 @@ use super::{HTN, PrimitiveAction, CompoundTask, Task, Applicability};
 use super::{AOStarSearch, FONDProblem};
 
 #[cfg(test)]
-#[test]
-pub fn decomposition_test() {
+pub fn decomposition_problem() -> FONDProblem {
     use std::collections::BTreeSet;
 
     use crate::domain_description::{Facts, DomainTasks};
@@ -114,6 +113,12 @@ pub fn decomposition_test() {
         init_tn: init_tn
     };
     problem.collapse_tn();
+    problem
+}
+
+#[test]
+pub fn decomposition_test() {
+    let problem = decomposition_problem();
     let (result, _) = AOStarSearch::run(&problem, crate::search::acyclic_plan::HeuristicType::HAdd);
     assert_eq!(result.is_success(), true);
 }
\ No newline at end of file
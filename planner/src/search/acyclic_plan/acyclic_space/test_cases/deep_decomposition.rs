@@ -0,0 +1,82 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Instant;
+
+use crate::domain_description::DomainTasks;
+use crate::task_network::Method;
+
+use super::*;
+
+const DEPTH: usize = 1000;
+
+// "t" decomposes into "a" and "t" without orderings, so every decomposition
+// adds a task to a partially ordered network
+fn growing_network() -> (HTN, Method, u32) {
+    let a = Task::Primitive(PrimitiveAction::new(
+        "a".to_string(), 1, HashSet::new(), vec![HashSet::new()], vec![HashSet::new()]
+    ));
+    let t = Task::Compound(CompoundTask::new("t".to_string(), vec![]));
+    let domain = Rc::new(DomainTasks::new(vec![a, t]));
+    let (a, t) = (domain.get_id("a"), domain.get_id("t"));
+    let subtasks = HTN::new(BTreeSet::from([1, 2]), vec![], domain.clone(), HashMap::from([(1, a), (2, t)]));
+    let domain = domain.add_methods(vec![(t, Method::new("m".to_string(), subtasks))]);
+    let method = match &*domain.get_task(t).borrow() {
+        Task::Compound(compound) => compound.methods[0].clone(),
+        _ => panic!("t is compound"),
+    };
+    let tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, t)]));
+    (tn, method, t)
+}
+
+// Every intermediate network of a deep decomposition
+fn decompose_deeply() -> Vec<HTN> {
+    let (tn, method, t) = growing_network();
+    let mut networks = vec![tn];
+    let mut t_node = 1;
+    for _ in 0..DEPTH {
+        let tn = networks.last().unwrap().decompose(t_node, &method);
+        // the subtasks are relabeled after the largest id of the network and
+        // of the method, "t" being the second one
+        t_node = t_node.max(2) + 2;
+        assert_eq!(tn.mappings[&t_node], t);
+        networks.push(tn);
+    }
+    networks
+}
+
+#[test]
+pub fn deep_decomposition_test() {
+    let networks = decompose_deeply();
+    // predecessors are not modified by their successors
+    for (depth, tn) in networks.iter().enumerate() {
+        assert_eq!(tn.count_tasks(), depth + 1);
+        assert_eq!(tn.get_unconstrained_tasks().len(), tn.count_tasks());
+    }
+    assert!(networks[0].is_totally_ordered());
+    assert!(!networks[DEPTH].is_totally_ordered());
+    // a decomposition replaces one task by two, only the leaves of the
+    // mappings holding them are copied
+    for pair in networks.windows(2) {
+        let (shared, leaves) = pair[1].mappings.shared_leaves(&pair[0].mappings);
+        assert!(leaves - shared <= 2, "{} of {} leaves copied", leaves - shared, leaves);
+    }
+}
+
+// Compares generating the networks of a deep decomposition with copying the
+// mappings and orderings of each of them, which is what every successor cost
+// before networks shared their structure
+#[test]
+pub fn deep_decomposition_benchmark() {
+    let start = Instant::now();
+    let networks = decompose_deeply();
+    let shared = start.elapsed();
+    let start = Instant::now();
+    for tn in networks.iter() {
+        let mappings: HashMap<u32, u32> = tn.mappings.iter().map(|(k, v)| (*k, *v)).collect();
+        let edges = tn.get_orderings();
+        assert_eq!((mappings.len(), edges.len()), (tn.count_tasks(), 0));
+    }
+    let copied = start.elapsed();
+    println!("decompositions: {:?}, copies: {:?}", shared, copied);
+    assert!(shared < copied);
+}
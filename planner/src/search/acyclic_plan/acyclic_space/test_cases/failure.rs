@@ -5,10 +5,10 @@ use crate::task_network::Method;
 
 use super::{HTN, PrimitiveAction, CompoundTask, Task, Applicability};
 use super::{AOStarSearch, FONDProblem};
+use crate::search::{FailureReason, HeuristicType};
 
 #[cfg(test)]
-#[test]
-pub fn failure_test() {
+pub fn failure_problem() -> FONDProblem {
     use std::collections::BTreeSet;
 
     use crate::domain_description::{Facts, DomainTasks};
@@ -64,6 +64,115 @@ pub fn failure_test() {
         init_tn: init_tn
     };
     problem.collapse_tn();
+    problem
+}
+
+#[test]
+pub fn failure_test() {
+    let problem = failure_problem();
     let (result, _) = AOStarSearch::run(&problem, crate::search::acyclic_plan::HeuristicType::HAdd);
     assert_eq!(result.is_success(), false);
-}
\ No newline at end of file
+    // only facts 0 and 1 are named
+    match result {
+        SearchResult::NoSolution(report) => assert_eq!(report.strategy.node.state, vec!["4".to_string(), "fact 4".to_string()]),
+        _ => panic!("problem is unsolvable"),
+    }
+}
+
+// t1 has no methods, so the root fails without being expanded
+#[test]
+pub fn undecomposable_root_test() {
+    let problem = failure_problem();
+    let (result, stats) = AOStarSearch::run(&problem, HeuristicType::HAdd);
+    assert!(!result.is_success());
+    assert_eq!(stats.explored_nodes, 0);
+    assert_eq!(stats.undecomposable_nodes, 1);
+}
+
+// Every task can be decomposed, but both methods of t1 fail depending on the
+// state: "flip" may delete 1 before "use1" needs it, and "lock" deletes 0
+// before "use0" needs it
+#[cfg(test)]
+pub fn dead_end_problem() -> FONDProblem {
+    use std::collections::BTreeSet;
+
+    use crate::domain_description::{Facts, DomainTasks};
+
+    let flip = Task::Primitive(PrimitiveAction::new(
+        "flip".to_string(),
+        1,
+        HashSet::new(),
+        vec![HashSet::new(), HashSet::new()],
+        vec![HashSet::new(), HashSet::from([1])]
+    ));
+    let use1 = Task::Primitive(PrimitiveAction::new(
+        "use1".to_string(),
+        1,
+        HashSet::from([1]),
+        vec![HashSet::new()],
+        vec![HashSet::new()]
+    ));
+    let lock = Task::Primitive(PrimitiveAction::new(
+        "lock".to_string(),
+        1,
+        HashSet::new(),
+        vec![HashSet::new()],
+        vec![HashSet::from([0])]
+    ));
+    let use0 = Task::Primitive(PrimitiveAction::new(
+        "use0".to_string(),
+        1,
+        HashSet::from([0]),
+        vec![HashSet::new()],
+        vec![HashSet::new()]
+    ));
+    let t1 = Task::Compound(CompoundTask::new(
+        "t1".to_string(),
+        vec![]
+    ));
+    let domain = Rc::new(DomainTasks::new(vec![flip, use1, lock, use0, t1]));
+    let method = |name: &str, first: &str, second: &str| Method::new(
+        name.to_string(),
+        HTN::new(
+            BTreeSet::from([1, 2]),
+            vec![(1, 2)],
+            domain.clone(),
+            HashMap::from([(1, domain.get_id(first)), (2, domain.get_id(second))])
+        )
+    );
+    let t1_id = domain.get_id("t1");
+    let domain = domain.add_methods(vec![
+        (t1_id, method("m1", "flip", "use1")),
+        (t1_id, method("m2", "lock", "use0")),
+    ]);
+    let init_tn = HTN::new(
+        BTreeSet::from([1]),
+        vec![],
+        domain.clone(),
+        HashMap::from([(1, t1_id)])
+    );
+    let mut problem = FONDProblem {
+        facts: Facts::new(vec!["0".to_string(), "1".to_string()]),
+        tasks: domain,
+        initial_state: HashSet::from([0, 1]),
+        init_tn
+    };
+    problem.collapse_tn();
+    problem
+}
+
+// the failure is only found by expanding the nodes and propagating the
+// dead ends back to the root
+#[test]
+pub fn dead_end_test() {
+    let problem = dead_end_problem();
+    let (result, stats) = AOStarSearch::run(&problem, HeuristicType::HAdd);
+    assert_eq!(stats.undecomposable_nodes, 0);
+    // the root, t1, and both networks of the methods
+    assert!(stats.explored_nodes >= 4);
+    // the state left by "flip" or "lock" makes the last task unreachable
+    match result {
+        SearchResult::NoSolution(report) => assert!(matches!(report.deepest.unwrap().1, FailureReason::Unreachable(_))),
+        _ => panic!("problem is unsolvable"),
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::domain_description::{DomainTasks, Facts};
+use crate::relaxation::RelaxedComposition;
+use crate::search::{Heuristic, HeuristicContext, HeuristicType, PolicyMetrics, TipSelection};
+use crate::task_network::Method;
+
+use super::*;
+use super::super::{ConnectionLabel, SearchGraph};
+
+// "t" is achieved by "a" through either "costly" (cost 5) or "cheap" (cost 1)
+fn method_costs_problem() -> FONDProblem {
+    let a = Task::Primitive(PrimitiveAction::new(
+        "a".to_string(), 1, HashSet::new(), vec![HashSet::from([0])], vec![HashSet::new()]
+    ));
+    let t = Task::Compound(CompoundTask::new("t".to_string(), vec![]));
+    let domain = Rc::new(DomainTasks::new(vec![a, t]));
+    let subtasks = || HTN::new(
+        BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("a"))])
+    );
+    let domain = domain.add_methods(vec![
+        (domain.get_id("t"), Method::with_cost("costly".to_string(), subtasks(), 5)),
+        (domain.get_id("t"), Method::with_cost("cheap".to_string(), subtasks(), 1)),
+    ]);
+    let init_tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("t"))]));
+    let mut problem = FONDProblem {
+        facts: Facts::new(vec!["0".to_string()]),
+        tasks: domain,
+        initial_state: HashSet::new(),
+        init_tn,
+    };
+    problem.collapse_tn();
+    problem
+}
+
+#[test]
+pub fn method_cost_policy_test() {
+    let problem = method_costs_problem();
+    let (result, _) = AOStarSearch::run(&problem, HeuristicType::HAdd);
+    let policy = match result {
+        SearchResult::Success(policy) => policy,
+        _ => panic!("method costs problem is solvable"),
+    };
+    let decompositions: Vec<_> = policy.transitions.iter()
+        .filter(|(_, output)| output.task == "t")
+        .map(|(_, output)| (output.method.as_str(), output.cost))
+        .collect();
+    assert_eq!(decompositions, vec![("cheap", 1)]);
+    // the top method has no cost, "cheap" and "a" cost 1 each
+    let metrics = PolicyMetrics::new(&policy);
+    assert_eq!(metrics.worst_case_cost, 2);
+    assert_eq!(metrics.expected_cost, 2.0);
+}
+
+#[test]
+pub fn method_cost_encoding_test() {
+    let problem = method_costs_problem();
+    let (encoder, _) = RelaxedComposition::from_fond_problem(&problem);
+    let cost = |name: &str| encoder.domain.actions.iter().find(|x| x.name == name).unwrap().cost;
+    assert_eq!(cost("costly"), 5);
+    assert_eq!(cost("cheap"), 1);
+}
+
+#[test]
+pub fn method_cost_heuristics_test() {
+    let problem = method_costs_problem();
+    let (encoder, bijection) = RelaxedComposition::from_fond_problem(&problem);
+    // "cheap" and "a" cost 1 each, the top method 0 or 1
+    for (free_decompositions, expected) in [(true, 2.0), (false, 3.0)] {
+        let context = HeuristicContext { encoder: &encoder, bijection: &bijection, landmarks: None, free_decompositions };
+        for mut h in [HeuristicType::TDGc, HeuristicType::TDGcR, HeuristicType::LP] {
+            let value = h.h_val(&problem.init_tn, &problem.initial_state, &context);
+            assert_eq!(value, expected, "{}", h);
+        }
+    }
+}
+
+// Searches the method costs problem until the root is solved or failed
+fn solved_graph(free_decompositions: bool) -> SearchGraph {
+    let problem = method_costs_problem();
+    let mut graph = SearchGraph::new(&problem);
+    graph.free_decompositions = free_decompositions;
+    let mut heuristic = HeuristicType::HAdd;
+    while !graph.is_terminated() {
+        let n = graph.select_tip_node(&TipSelection::MaxCost);
+        graph.expand(n, &mut heuristic, 1.0, false);
+        graph.backward_cost_revision(n);
+    }
+    graph
+}
+
+#[test]
+pub fn free_decompositions_test() {
+    for (free_decompositions, uncosted, root_cost) in [(true, 0.0, 2.0), (false, 1.0, 3.0)] {
+        let graph = solved_graph(free_decompositions);
+        let mut costs = vec![];
+        for node in graph.ids.values() {
+            let node = node.borrow();
+            let connectors = node.connections.iter().flat_map(|x| x.children.iter());
+            for connector in connectors {
+                if let ConnectionLabel::Decomposition(_, method, _) = &connector.action_type {
+                    costs.push((method.clone(), connector.cost));
+                }
+            }
+        }
+        // the top method, "costly" and "cheap"
+        assert_eq!(costs.len(), 3);
+        for (method, cost) in costs.iter() {
+            let expected = match method.as_str() {
+                "costly" => 5.0,
+                "cheap" => 1.0,
+                _ => uncosted,
+            };
+            assert_eq!(*cost, expected, "{}", method);
+        }
+        let root = graph.ids.get(&graph.root).unwrap().borrow();
+        assert!(root.status.is_terminal());
+        assert_eq!(root.cost, root_cost);
+    }
+}
@@ -4,8 +4,32 @@ mod decomposition;
 mod recursion_test;
 mod satelite_integration;
 mod dag_test;
+#[cfg(test)]
+mod admissibility;
+#[cfg(test)]
+mod combinators;
+#[cfg(test)]
+mod ordering;
+#[cfg(test)]
+mod width;
+#[cfg(test)]
+mod method_costs;
+#[cfg(test)]
+mod deep_decomposition;
 
 use super::{HTN, Task, PrimitiveAction, CompoundTask, Applicability};
 use super::cf::AOStarSearch;
 use crate::domain_description::FONDProblem;
-use super::SearchResult;
\ No newline at end of file
+use super::SearchResult;
+
+// The small problems the searches and heuristics are compared on
+#[cfg(test)]
+fn test_problems() -> Vec<(&'static str, FONDProblem)> {
+    vec![
+        ("conformant", conformant::conformant_problem()),
+        ("dag", dag_test::dag_problem()),
+        ("decomposition", decomposition::decomposition_problem()),
+        ("failure", failure::failure_problem()),
+        ("navigation", recursion_test::navigation_problem()),
+    ]
+}
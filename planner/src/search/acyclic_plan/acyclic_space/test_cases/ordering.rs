@@ -0,0 +1,82 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::domain_description::{DomainTasks, Facts};
+use crate::search::HeuristicType;
+use crate::task_network::Method;
+
+use super::*;
+
+// "top" decomposes into "prepare" and "make", which needs the fact added by
+// "prepare". The two methods have the same subtasks and only "early" orders
+// them so that "make" is applicable.
+fn method_order_problem(early_first: bool) -> FONDProblem {
+    let prepare = Task::Primitive(PrimitiveAction::new(
+        "prepare".to_string(), 1, HashSet::new(), vec![HashSet::from([0])], vec![HashSet::new()]
+    ));
+    let make = Task::Primitive(PrimitiveAction::new(
+        "make".to_string(), 1, HashSet::from([0]), vec![HashSet::from([1])], vec![HashSet::new()]
+    ));
+    let top = Task::Compound(CompoundTask::new("top".to_string(), vec![]));
+    let domain = Rc::new(DomainTasks::new(vec![prepare, make, top]));
+    let method = |name: &str, orderings: Vec<(u32, u32)>| Method::new(name.to_string(), HTN::new(
+        BTreeSet::from([1, 2]),
+        orderings,
+        domain.clone(),
+        HashMap::from([(1, domain.get_id("prepare")), (2, domain.get_id("make"))])
+    ));
+    let mut methods = vec![method("early", vec![(1, 2)]), method("late", vec![(2, 1)])];
+    if !early_first {
+        methods.reverse();
+    }
+    let top = domain.get_id("top");
+    let domain = domain.add_methods(methods.into_iter().map(|x| (top, x)).collect());
+    let init_tn = HTN::new(BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, top)]));
+    let mut problem = FONDProblem {
+        facts: Facts::new(vec!["0".to_string(), "1".to_string()]),
+        tasks: domain,
+        initial_state: HashSet::new(),
+        init_tn,
+    };
+    problem.collapse_tn();
+    problem
+}
+
+// the order-aware heuristic only reports dead ends that are dead ends
+#[test]
+pub fn ordered_add_test() {
+    for (name, problem) in test_problems() {
+        let (add, _) = AOStarSearch::run(&problem, HeuristicType::HAdd);
+        let (ordered, _) = AOStarSearch::run(&problem, HeuristicType::OrderedAdd);
+        assert_eq!(add.is_success(), ordered.is_success(), "{}", name);
+    }
+    // h_add cannot tell the two methods apart and explores "late" first,
+    // the order-aware heuristic reports it as a dead end when it is generated
+    let problem = method_order_problem(false);
+    let (_, add) = AOStarSearch::run(&problem, HeuristicType::HAdd);
+    let (_, ordered) = AOStarSearch::run(&problem, HeuristicType::OrderedAdd);
+    assert!(ordered.explored_nodes < add.explored_nodes);
+}
+
+// Networks with the same tasks but different orderings have different
+// values, whichever method is evaluated first
+#[test]
+pub fn method_order_test() {
+    let mut explored = vec![];
+    for early_first in [true, false] {
+        let problem = method_order_problem(early_first);
+        let (result, stats) = AOStarSearch::run(&problem, HeuristicType::OrderedAdd);
+        let policy = match result {
+            SearchResult::Success(policy) => policy,
+            _ => panic!("method order problem is solvable"),
+        };
+        let methods: Vec<_> = policy.transitions.iter()
+            .filter(|(_, output)| output.task == "top")
+            .map(|(_, output)| output.method.as_str())
+            .collect();
+        assert_eq!(methods, vec!["early"]);
+        assert_eq!(stats.cache_hits, 0);
+        explored.push(stats.explored_nodes);
+    }
+    assert_eq!(explored[0], explored[1]);
+}
@@ -5,8 +5,7 @@ use crate::domain_description::DomainTasks;
 use std::collections::{HashSet, BTreeSet, HashMap};
 use crate::task_network::Method;
 
-#[test]
-pub fn recursive_navigation_test() {
+pub fn navigation_problem() -> FONDProblem {
     let facts = Facts::new(vec![
         "at_w0".to_string(), "at_w1".to_string(), "at_w2".to_string(), "at_w3".to_string()
     ]);
@@ -78,6 +77,114 @@ pub fn recursive_navigation_test() {
         init_tn: tn
     };
     problem.collapse_tn();
+    problem
+}
+
+#[test]
+pub fn recursive_navigation_test() {
+    let problem = navigation_problem();
     let (result, _) = AOStarSearch::run(&problem, crate::search::acyclic_plan::HeuristicType::HAdd);
     assert_eq!(result.is_success(), true);
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn recursive_navigation_tdg_test() {
+    use crate::search::acyclic_plan::HeuristicType;
+    let problem = navigation_problem();
+    for h in [HeuristicType::TDGm, HeuristicType::TDGc, HeuristicType::TDGmR, HeuristicType::TDGcR] {
+        let (result, _) = AOStarSearch::run(&problem, h);
+        assert!(result.is_success(), "{:?} fails", h);
+    }
+}
+#[test]
+pub fn recursive_navigation_worst_case_test() {
+    use crate::search::{HeuristicType, SearchConfig};
+    let problem = navigation_problem();
+    let config = SearchConfig { node_limit: Some(1000), ..SearchConfig::default() };
+    for h in [HeuristicType::HAddWC, HeuristicType::HMaxWC] {
+        let (result, _) = AOStarSearch::run_with_config(&problem, h, &config);
+        assert!(result.is_success(), "{:?} fails", h);
+    }
+}
+#[test]
+pub fn recursive_navigation_preferred_test() {
+    use crate::search::{HeuristicType, SearchConfig, ConnectionLabel};
+    use crate::search::search_graph::SearchGraph;
+    let problem = navigation_problem();
+    let mut graph = SearchGraph::new(&problem);
+    graph.preferred_operators = true;
+    graph.expand(graph.root, &mut HeuristicType::HFF, 1.0, false);
+    // the relaxed plan reaches nav_abs through nav_to_w1
+    let child = graph.cursor - 1;
+    graph.expand(child, &mut HeuristicType::HFF, 1.0, false);
+    let node = graph.ids.get(&child).unwrap().borrow();
+    let connectors = &node.connections.as_ref().unwrap().children;
+    assert_eq!(connectors.len(), 3);
+    match &connectors[0].action_type {
+        ConnectionLabel::Decomposition(_, method, _) => assert_eq!(method, "nav_abs_m_2"),
+        x => panic!("unexpected connector {:?}", x),
+    }
+    let config = SearchConfig { preferred_operators: true, ..SearchConfig::default() };
+    let (result, _) = AOStarSearch::run_with_config(&problem, HeuristicType::HFF, &config);
+    assert!(result.is_success());
+}
+// Methods of different tasks may share a name, helpful decompositions are
+// told apart by their task
+#[test]
+pub fn preferred_method_name_clash_test() {
+    use crate::search::{HeuristicType, ConnectionLabel};
+    use crate::search::search_graph::SearchGraph;
+    // t1 -> "m" [b] | "ok" [a]; t2 -> "m" [a]; b needs fact 1, which no
+    // action achieves
+    let a = Task::Primitive(PrimitiveAction::new(
+        "a".to_string(), 1, HashSet::new(), vec![HashSet::from([0])], vec![HashSet::new()]
+    ));
+    let b = Task::Primitive(PrimitiveAction::new(
+        "b".to_string(), 1, HashSet::from([1]), vec![HashSet::new()], vec![HashSet::new()]
+    ));
+    let t1 = Task::Compound(CompoundTask::new("t1".to_string(), vec![]));
+    let t2 = Task::Compound(CompoundTask::new("t2".to_string(), vec![]));
+    let domain = Rc::new(DomainTasks::new(vec![a, b, t1, t2]));
+    let tn = |task: &str| HTN::new(
+        BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id(task))])
+    );
+    let domain = domain.add_methods(vec![
+        (domain.get_id("t1"), Method::new("m".to_string(), tn("b"))),
+        (domain.get_id("t1"), Method::new("ok".to_string(), tn("a"))),
+        (domain.get_id("t2"), Method::new("m".to_string(), tn("a"))),
+    ]);
+    let init_tn = HTN::new(
+        BTreeSet::from([1, 2]), vec![], domain.clone(),
+        HashMap::from([(1, domain.get_id("t1")), (2, domain.get_id("t2"))])
+    );
+    let mut problem = FONDProblem {
+        facts: Facts::new(vec!["0".to_string(), "1".to_string()]),
+        tasks: domain,
+        initial_state: HashSet::new(),
+        init_tn,
+    };
+    problem.collapse_tn();
+    let mut graph = SearchGraph::new(&problem);
+    graph.preferred_operators = true;
+    graph.expand(graph.root, &mut HeuristicType::HFF, 1.0, false);
+    let child = graph.cursor - 1;
+    graph.expand(child, &mut HeuristicType::HFF, 1.0, false);
+    let node = graph.ids.get(&child).unwrap().borrow();
+    let connectors: Vec<_> = node.connections.as_ref().unwrap().children.iter()
+        .map(|x| match &x.action_type {
+            ConnectionLabel::Decomposition(task, method, _) => (task.as_str(), method.as_str()),
+            x => panic!("unexpected connector {:?}", x),
+        })
+        .collect();
+    assert_eq!(connectors.len(), 3);
+    assert_eq!(connectors[2], ("t1", "m"));
+}
+#[test]
+pub fn recursive_navigation_landmark_test() {
+    use crate::search::acyclic_plan::HeuristicType;
+    let problem = navigation_problem();
+    let (result, stats) = AOStarSearch::run(&problem, HeuristicType::LandmarkCount);
+    assert!(result.is_success());
+    // nav_to_w3 needs at_w2, which needs at_w1
+    assert_eq!(stats.landmarks.unwrap().facts, 2);
+}
@@ -0,0 +1,89 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::domain_description::{DomainTasks, Facts};
+use crate::search::{HeuristicType, IWSearch, SearchConfig, TipSelection};
+
+use super::*;
+use super::combinators::Blind;
+use super::conformant::conformant_problem;
+use super::recursion_test::navigation_problem;
+
+// "toss" either leaves the state as it is or replaces 0 by 1, and "check"
+// needs 0. Only the second outcome reaches a new fact, and it is a dead end.
+fn toss_problem() -> FONDProblem {
+    let toss = Task::Primitive(PrimitiveAction::new(
+        "toss".to_string(),
+        1,
+        HashSet::new(),
+        vec![HashSet::new(), HashSet::from([1])],
+        vec![HashSet::new(), HashSet::from([0])]
+    ));
+    let check = Task::Primitive(PrimitiveAction::new(
+        "check".to_string(),
+        1,
+        HashSet::from([0]),
+        vec![HashSet::new()],
+        vec![HashSet::new()]
+    ));
+    let domain = Rc::new(DomainTasks::new(vec![toss, check]));
+    let init_tn = HTN::new(
+        BTreeSet::from([1, 2]),
+        vec![(1, 2)],
+        domain.clone(),
+        HashMap::from([(1, domain.get_id("toss")), (2, domain.get_id("check"))])
+    );
+    let mut problem = FONDProblem {
+        facts: Facts::new(vec!["0".to_string(), "1".to_string()]),
+        tasks: domain,
+        initial_state: HashSet::from([0]),
+        init_tn
+    };
+    problem.collapse_tn();
+    problem
+}
+
+#[test]
+pub fn iw_navigation_test() {
+    let problem = navigation_problem();
+    let result = IWSearch::run(&problem, 2);
+    let plan = result.plan.expect("navigation is deterministic");
+    let actions: Vec<&String> = plan.iter().filter(|x| x.starts_with("nav_to")).collect();
+    assert_eq!(actions, vec!["nav_to_w1", "nav_to_w2", "nav_to_w3"]);
+    assert!(result.width <= 2);
+}
+
+// p2 has two outcomes and is required
+#[test]
+pub fn iw_nondeterministic_test() {
+    let problem = conformant_problem();
+    let result = IWSearch::run(&problem, 2);
+    assert!(result.plan.is_none());
+    assert_eq!(result.width, 2);
+}
+
+#[test]
+pub fn novelty_tip_selection_test() {
+    let config = SearchConfig { tip_selection: TipSelection::Novelty, ..SearchConfig::default() };
+    for (name, problem) in test_problems() {
+        let (first_found, _) = AOStarSearch::run(&problem, HeuristicType::HAdd);
+        let (novelty, _) = AOStarSearch::run_with_config(&problem, HeuristicType::HAdd, &config);
+        assert_eq!(first_found.is_success(), novelty.is_success(), "{}", name);
+    }
+}
+
+
+// Without guidance both outcomes of "toss" have the same value. Min-cost
+// expands the first one, while novelty expands the new state first and
+// stops as soon as it fails.
+#[test]
+pub fn novelty_tie_test() {
+    let problem = toss_problem();
+    let min_cost = SearchConfig { tip_selection: TipSelection::MinCost, ..SearchConfig::default() };
+    let novelty = SearchConfig { tip_selection: TipSelection::Novelty, ..SearchConfig::default() };
+    let (min_cost_result, min_cost) = AOStarSearch::run_with_heuristic(&problem, Box::new(Blind), &min_cost);
+    let (novelty_result, novelty) = AOStarSearch::run_with_heuristic(&problem, Box::new(Blind), &novelty);
+    assert!(!min_cost_result.is_success());
+    assert!(!novelty_result.is_success());
+    assert!(novelty.explored_nodes < min_cost.explored_nodes);
+}
@@ -5,4 +5,4 @@ mod policy;
 use super::*;
 pub use acyclic_space::SearchResult;
 pub use acyclic_space::*;
-pub use policy::StrongPolicy;
\ No newline at end of file
+pub use policy::{StrongPolicy, PolicyMetrics, PolicyError, StateMachine};
\ No newline at end of file
@@ -0,0 +1,363 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain_description::Facts;
+use crate::task_network::Task;
+
+use super::StrongPolicy;
+
+// A finite-state controller that only contains primitive actions. Every
+// state executes one action; after execution, the observed outcome selects
+// the next state.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct StateMachine {
+    // None if the policy does not execute any action
+    pub initial: Option<usize>,
+    pub states: Vec<MachineState>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MachineState {
+    pub id: usize,
+    pub action: String,
+    // ordered by priority, the first transition whose condition holds is taken
+    pub transitions: Vec<MachineTransition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MachineTransition {
+    pub outcome: usize,
+    // facts that have to be observed
+    pub requires: Vec<String>,
+    // facts that must not be observed
+    pub forbids: Vec<String>,
+    // None if execution is finished
+    pub next: Option<usize>,
+}
+
+impl MachineTransition {
+    pub fn holds(&self, observed_facts: &HashSet<String>) -> bool {
+        self.requires.iter().all(|x| observed_facts.contains(x)) &&
+            !self.forbids.iter().any(|x| observed_facts.contains(x))
+    }
+}
+
+impl StateMachine {
+    // Next state after executing the action of "state" and observing
+    // "observed_facts", None if no transition matches
+    pub fn transition(&self, state: usize, observed_facts: &HashSet<String>) -> Option<Option<usize>> {
+        self.states[state].transitions.iter()
+            .find(|t| t.holds(observed_facts))
+            .map(|t| t.next)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    // Emits a standalone Rust module implementing the controller
+    pub fn to_rust_module(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "// Generated from a strong policy, do not edit.").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]").unwrap();
+        writeln!(out, "pub enum PolicyState {{").unwrap();
+        for state in self.states.iter() {
+            writeln!(out, "    S{},", state.id).unwrap();
+        }
+        writeln!(out, "    Done,").unwrap();
+        writeln!(out, "    Invalid,").unwrap();
+        writeln!(out, "}}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]").unwrap();
+        writeln!(out, "pub enum Action {{").unwrap();
+        writeln!(out, "    Execute(&'static str),").unwrap();
+        writeln!(out, "    Done,").unwrap();
+        writeln!(out, "    Invalid,").unwrap();
+        writeln!(out, "}}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "pub struct Controller {{").unwrap();
+        writeln!(out, "    state: PolicyState,").unwrap();
+        writeln!(out, "    started: bool,").unwrap();
+        writeln!(out, "}}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "impl Default for Controller {{").unwrap();
+        writeln!(out, "    fn default() -> Self {{").unwrap();
+        writeln!(out, "        Self::new()").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "impl Controller {{").unwrap();
+        writeln!(out, "    pub fn new() -> Self {{").unwrap();
+        writeln!(out, "        Controller {{ state: {}, started: false }}", StateMachine::state_name(self.initial)).unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    pub fn state(&self) -> PolicyState {{").unwrap();
+        writeln!(out, "        self.state").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out).unwrap();
+        writeln!(out, "    // Takes the facts observed after executing the previous action (or").unwrap();
+        writeln!(out, "    // the initial observation) and returns the next action to execute").unwrap();
+        writeln!(out, "    pub fn step(&mut self, observed_facts: &[&str]) -> Action {{").unwrap();
+        writeln!(out, "        let holds = |fact: &str| observed_facts.contains(&fact);").unwrap();
+        writeln!(out, "        if self.started {{").unwrap();
+        writeln!(out, "            self.state = match self.state {{").unwrap();
+        for state in self.states.iter() {
+            writeln!(out, "                PolicyState::S{} => {{", state.id).unwrap();
+            for (i, t) in state.transitions.iter().enumerate() {
+                let condition: Vec<String> = t.requires.iter().map(|x| format!("holds({:?})", x))
+                    .chain(t.forbids.iter().map(|x| format!("!holds({:?})", x)))
+                    .collect();
+                let condition = if condition.is_empty() { "true".to_string() } else { condition.join(" && ") };
+                let keyword = if i == 0 { "if" } else { "} else if" };
+                writeln!(out, "                    {} {} {{", keyword, condition).unwrap();
+                writeln!(out, "                        {}", StateMachine::state_name(t.next)).unwrap();
+            }
+            if state.transitions.is_empty() {
+                writeln!(out, "                    PolicyState::Invalid").unwrap();
+            } else {
+                writeln!(out, "                    }} else {{").unwrap();
+                writeln!(out, "                        PolicyState::Invalid").unwrap();
+                writeln!(out, "                    }}").unwrap();
+            }
+            writeln!(out, "                }},").unwrap();
+        }
+        writeln!(out, "                state => state,").unwrap();
+        writeln!(out, "            }};").unwrap();
+        writeln!(out, "        }}").unwrap();
+        writeln!(out, "        self.started = true;").unwrap();
+        writeln!(out, "        match self.state {{").unwrap();
+        for state in self.states.iter() {
+            writeln!(out, "            PolicyState::S{} => Action::Execute({:?}),", state.id, state.action).unwrap();
+        }
+        writeln!(out, "            PolicyState::Done => Action::Done,").unwrap();
+        writeln!(out, "            PolicyState::Invalid => Action::Invalid,").unwrap();
+        writeln!(out, "        }}").unwrap();
+        writeln!(out, "    }}").unwrap();
+        writeln!(out, "}}").unwrap();
+        out
+    }
+
+    fn state_name(state: Option<usize>) -> String {
+        match state {
+            Some(id) => format!("PolicyState::S{}", id),
+            None => "PolicyState::Done".to_string(),
+        }
+    }
+}
+
+impl StrongPolicy {
+    // Converts the policy into a controller over primitive actions.
+    // Decompositions are internal to the planner and are skipped. Outcomes
+    // are told apart by the facts that hold after them, as far as the state
+    // of the entry (complete, or partial in compacted policies) tells. Two
+    // outcomes that lead to different states of the controller but can not
+    // be told apart are an error.
+    pub fn to_state_machine(&self, facts: &Facts) -> Result<StateMachine, String> {
+        let executions: Vec<usize> = (0..self.transitions.len())
+            .filter(|i| self.transitions[*i].1.is_primitive())
+            .collect();
+        let state_id = |entry: Option<usize>| {
+            self.next_execution(entry).map(|e| executions.iter().position(|x| *x == e).unwrap())
+        };
+        let mut states = vec![];
+        for (id, entry) in executions.iter().enumerate() {
+            let (input, output) = &self.transitions[*entry];
+            let domain = &input.tn.domain;
+            let task = domain.get_task(domain.get_id(&output.task)).borrow();
+            let action = match &*task {
+                Task::Primitive(action) => action,
+                Task::Compound(_) => panic!("{} is not primitive", output.task)
+            };
+            let next: Vec<Option<usize>> = self.successors[*entry].iter().map(|x| state_id(*x)).collect();
+            // facts that hold after each outcome
+            let outcomes: Vec<BTreeSet<&String>> = (0..next.len()).map(|k| {
+                let deleted: HashSet<&String> = action.del_effects[k].iter().map(|x| facts.get_fact(*x)).collect();
+                input.state.iter()
+                    .filter(|x| !deleted.contains(x))
+                    .chain(action.add_effects[k].iter().map(|x| facts.get_fact(*x)))
+                    .collect()
+            }).collect();
+            let mut transitions = vec![];
+            for k in 0..next.len() {
+                let (mut requires, mut forbids) = (BTreeSet::new(), BTreeSet::new());
+                // only the outcomes that lead elsewhere have to be ruled out
+                for j in (0..next.len()).filter(|j| next[*j] != next[k]) {
+                    if outcomes[k] == outcomes[j] {
+                        return Err(format!(
+                            "outcomes {} and {} of {} lead to the same facts but not to the same state", k, j, output.task
+                        ));
+                    }
+                    requires.extend(outcomes[k].difference(&outcomes[j]).map(|x| x.to_string()));
+                    forbids.extend(outcomes[j].difference(&outcomes[k]).map(|x| x.to_string()));
+                }
+                transitions.push(MachineTransition {
+                    outcome: k,
+                    requires: requires.into_iter().collect(),
+                    forbids: forbids.into_iter().collect(),
+                    next: next[k],
+                });
+            }
+            // more specific outcomes are checked first
+            transitions.sort_by_key(|t| std::cmp::Reverse(t.requires.len() + t.forbids.len()));
+            states.push(MachineState { id, action: output.task.clone(), transitions });
+        }
+        let initial = if self.transitions.is_empty() { None } else { state_id(Some(0)) };
+        Ok(StateMachine { initial, states })
+    }
+
+    // follows decompositions until an action is executed
+    fn next_execution(&self, entry: Option<usize>) -> Option<usize> {
+        let mut current = entry;
+        while let Some(e) = current {
+            if self.transitions[e].1.is_primitive() {
+                return Some(e);
+            }
+            current = self.successors[e][0];
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::domain_description::{DomainTasks, FONDProblem};
+    use crate::search::{AOStarSearch, HeuristicType, SearchResult};
+    use crate::task_network::{HTN, PrimitiveAction, CompoundTask, Method, Applicability};
+
+    fn generate_problem() -> FONDProblem {
+        // "toss" either yields heads or tails, "fix" turns tails into heads
+        let toss = Task::Primitive(PrimitiveAction::new(
+            "toss".to_string(), 1, HashSet::new(),
+            vec![HashSet::from([0]), HashSet::from([1])], vec![HashSet::from([1]), HashSet::from([0])]
+        ));
+        let fix = Task::Primitive(PrimitiveAction::new(
+            "fix".to_string(), 1, HashSet::from([1]),
+            vec![HashSet::from([0])], vec![HashSet::from([1])]
+        ));
+        let noop = Task::Primitive(PrimitiveAction::new(
+            "noop".to_string(), 1, HashSet::from([0]),
+            vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let finish = Task::Compound(CompoundTask::new("finish".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![toss, fix, noop, finish]));
+        let m_fix = Method::new("m_fix".to_string(), HTN::new(
+            BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("fix"))])
+        ));
+        let m_noop = Method::new("m_noop".to_string(), HTN::new(
+            BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("noop"))])
+        ));
+        let id = domain.get_id("finish");
+        let domain = domain.add_methods(vec![(id, m_fix), (id, m_noop)]);
+        let tn = HTN::new(
+            BTreeSet::from([1, 2]), vec![(1, 2)], domain.clone(),
+            HashMap::from([(1, domain.get_id("toss")), (2, domain.get_id("finish"))])
+        );
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec!["heads".to_string(), "tails".to_string()]),
+            tasks: domain,
+            initial_state: HashSet::new(),
+            init_tn: tn
+        };
+        problem.collapse_tn();
+        problem
+    }
+
+    // "look" adds either a, which already holds, or b. "use" needs b and is
+    // cheaper than "fallback".
+    fn pre_existing_fact_problem() -> FONDProblem {
+        let look = Task::Primitive(PrimitiveAction::new(
+            "look".to_string(), 1, HashSet::new(),
+            vec![HashSet::from([0]), HashSet::from([1])], vec![HashSet::new(), HashSet::new()]
+        ));
+        let fallback = Task::Primitive(PrimitiveAction::new(
+            "fallback".to_string(), 1, HashSet::from([0]), vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let use_b = Task::Primitive(PrimitiveAction::new(
+            "use".to_string(), 1, HashSet::from([1]), vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let finish = Task::Compound(CompoundTask::new("finish".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![look, fallback, use_b, finish]));
+        let method = |name: &str, task: &str, cost: u32| Method::with_cost(name.to_string(), HTN::new(
+            BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id(task))])
+        ), cost);
+        let id = domain.get_id("finish");
+        let domain = domain.add_methods(vec![(id, method("m_fallback", "fallback", 5)), (id, method("m_use", "use", 1))]);
+        let tn = HTN::new(
+            BTreeSet::from([1, 2]), vec![(1, 2)], domain.clone(),
+            HashMap::from([(1, domain.get_id("look")), (2, domain.get_id("finish"))])
+        );
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec!["a".to_string(), "b".to_string()]),
+            tasks: domain,
+            initial_state: HashSet::from([0]),
+            init_tn: tn
+        };
+        problem.collapse_tn();
+        problem
+    }
+
+    #[test]
+    pub fn pre_existing_fact_test() {
+        let problem = pre_existing_fact_problem();
+        let mut policy = match AOStarSearch::run(&problem, HeuristicType::HAdd).0 {
+            SearchResult::Success(policy) => policy,
+            _ => panic!("problem is solvable")
+        };
+        let machine = policy.to_state_machine(&problem.facts).unwrap();
+        let initial = machine.initial.unwrap();
+        let next = |observed: &[&str]| {
+            let observed = observed.iter().map(|x| x.to_string()).collect();
+            machine.transition(initial, &observed).unwrap().map(|x| machine.states[x].action.clone())
+        };
+        // a still holds when b is added, so observing a does not mean that
+        // the first outcome occurred
+        assert_eq!(next(&["a"]), Some("fallback".to_string()));
+        assert_eq!(next(&["a", "b"]), Some("use".to_string()));
+        // if b held before "look", both outcomes lead to the same facts
+        let look = policy.transitions.iter().position(|(_, output)| output.task == "look").unwrap();
+        policy.transitions[look].0.state.insert("b".to_string());
+        assert!(policy.to_state_machine(&problem.facts).is_err());
+    }
+
+    #[test]
+    pub fn state_machine_test() {
+        let problem = generate_problem();
+        let policy = match AOStarSearch::run(&problem, HeuristicType::HAdd).0 {
+            SearchResult::Success(policy) => policy,
+            _ => panic!("problem is solvable")
+        };
+        let machine = policy.to_state_machine(&problem.facts).unwrap();
+        assert_eq!(machine.states.len(), 3);
+        let initial = machine.initial.unwrap();
+        assert_eq!(machine.states[initial].action, "toss");
+        // simulate both outcomes of the toss
+        let toss = match &*problem.tasks.get_task(problem.tasks.get_id("toss")).borrow() {
+            Task::Primitive(a) => a.clone(),
+            _ => panic!()
+        };
+        for outcome in toss.transition(&HashSet::new()) {
+            let observed: HashSet<String> = outcome.iter().map(|x| problem.facts.get_fact(*x).clone()).collect();
+            let next = machine.transition(initial, &observed).unwrap().unwrap();
+            if observed.contains("heads") {
+                assert_eq!(machine.states[next].action, "noop");
+            } else {
+                assert_eq!(machine.states[next].action, "fix");
+            }
+            assert_eq!(machine.transition(next, &HashSet::from(["heads".to_string()])), Some(None));
+        }
+        let json = machine.to_json();
+        let parsed: StateMachine = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, machine);
+        let module = machine.to_rust_module();
+        assert!(module.contains("pub fn step(&mut self, observed_facts: &[&str]) -> Action"));
+        assert!(module.contains("Action::Execute(\"toss\")"));
+        assert!(module.contains("holds(\"tails\") && !holds(\"heads\")"));
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::domain_description::Facts;
+use crate::task_network::{HTN, Task};
+
+use super::{StrongPolicy, PolicyNode, PolicyOutput};
+
+impl StrongPolicy {
+    // Replaces the state of every entry by the partial state its branch
+    // depends on, and merges entries that become indistinguishable
+    pub fn compact(&self, facts: &Facts) -> StrongPolicy {
+        let relevant = self.relevant_facts(facts);
+        let mut transitions: Vec<(PolicyNode, PolicyOutput)> = self.transitions.iter()
+            .zip(relevant.iter())
+            .map(|((input, output), rel)| {
+                let node = PolicyNode {
+                    state: input.state.intersection(rel).cloned().collect(),
+                    tn: input.tn.clone()
+                };
+                let output = PolicyOutput {
+                    task: output.task.clone(),
+                    method: output.method.clone(),
+                    cost: output.cost
+                };
+                (node, output)
+            }).collect();
+        let mut successors = self.successors.clone();
+        // representative[i] is the entry that replaces entry i
+        let mut representative: Vec<usize> = (0..transitions.len()).collect();
+        for i in 0..transitions.len() {
+            for j in 0..i {
+                if representative[j] != j || !StrongPolicy::is_mergeable(&transitions[j], &transitions[i]) {
+                    continue;
+                }
+                // redirecting i to j must not close a cycle
+                if StrongPolicy::reaches(&successors, j, i) {
+                    continue;
+                }
+                representative[i] = j;
+                for succ in successors.iter_mut().flatten().flatten() {
+                    if *succ == i {
+                        *succ = j;
+                    }
+                }
+                break;
+            }
+        }
+        // compact indices
+        let mut new_index = HashMap::new();
+        for (i, rep) in representative.iter().enumerate() {
+            if *rep == i {
+                new_index.insert(i, new_index.len());
+            }
+        }
+        let successors = successors.into_iter()
+            .enumerate()
+            .filter(|(i, _)| representative[*i] == *i)
+            .map(|(_, succ)| {
+                succ.into_iter().map(|x| x.map(|s| new_index[&s])).collect()
+            }).collect();
+        let transitions = transitions.drain(..)
+            .enumerate()
+            .filter(|(i, _)| representative[*i] == *i)
+            .map(|(_, entry)| entry)
+            .collect();
+        StrongPolicy { transitions, successors, makespan: self.makespan }
+    }
+
+    // Regresses the preconditions of the actions in each branch, i.e.,
+    // computes the facts every entry actually depends on
+    fn relevant_facts(&self, facts: &Facts) -> Vec<HashSet<String>> {
+        let mut relevant = vec![HashSet::new(); self.transitions.len()];
+        for entry in self.topological_order() {
+            let (input, output) = &self.transitions[entry];
+            let successors = &self.successors[entry];
+            let mut result = HashSet::new();
+            if output.is_primitive() {
+                let domain = &input.tn.domain;
+                let task = domain.get_task(domain.get_id(&output.task)).borrow();
+                if let Task::Primitive(action) = &*task {
+                    result.extend(action.pre_cond.iter().map(|x| facts.get_fact(*x).clone()));
+                    for (k, succ) in successors.iter().enumerate() {
+                        if let Some(s) = succ {
+                            let added: HashSet<&String> = match action.add_effects.get(k) {
+                                Some(add) => add.iter().map(|x| facts.get_fact(*x)).collect(),
+                                None => HashSet::new()
+                            };
+                            result.extend(relevant[*s].iter().filter(|x| !added.contains(x)).cloned());
+                        }
+                    }
+                } else {
+                    panic!("{} is not primitive", output.task)
+                }
+            } else {
+                for s in successors.iter().flatten() {
+                    result.extend(relevant[*s].iter().cloned());
+                }
+            }
+            relevant[entry] = result;
+        }
+        relevant
+    }
+
+    fn is_mergeable(e1: &(PolicyNode, PolicyOutput), e2: &(PolicyNode, PolicyOutput)) -> bool {
+        let ((n1, o1), (n2, o2)) = (e1, e2);
+        o1.task == o2.task && o1.method == o2.method && n1.state == n2.state &&
+            HTN::is_isomorphic(&n1.tn, &n2.tn)
+    }
+
+    // whether "to" can be reached from "from"
+    fn reaches(successors: &[Vec<Option<usize>>], from: usize, to: usize) -> bool {
+        let mut stack = vec![from];
+        let mut visited = HashSet::from([from]);
+        while let Some(x) = stack.pop() {
+            if x == to {
+                return true;
+            }
+            for s in successors[x].iter().flatten() {
+                if visited.insert(*s) {
+                    stack.push(*s);
+                }
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use rand::Rng;
+
+use crate::domain_description::FONDProblem;
+use crate::task_network::{HTN, Task, CompoundTask, Applicability};
+
+use super::{StrongPolicy, PolicyOutput};
+
+#[derive(Debug, PartialEq)]
+pub enum PolicyError {
+    // no entry matches the (state, network) pair
+    Uncovered(Vec<String>),
+    // the output of an entry cannot be applied to the network
+    Inapplicable(String, String),
+    // execution returns to a previously seen (state, network) pair
+    Cycle,
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Self::Uncovered(state) => write!(f, "policy does not cover state {:?}", state),
+            Self::Inapplicable(task, method) => write!(f, "policy output {} ({}) is not applicable", task, method),
+            Self::Cycle => write!(f, "policy execution is cyclic"),
+        }
+    }
+}
+
+impl StrongPolicy {
+    // Finds the entry responsible for a (state, network) pair. An entry
+    // matches if its (possibly partial) state holds and its network is
+    // isomorphic to the given one.
+    pub fn lookup(&self, state: &HashSet<String>, tn: &HTN) -> Option<usize> {
+        self.transitions.iter().position(|(input, _)| {
+            input.state.is_subset(state) && HTN::is_isomorphic(&input.tn, tn)
+        })
+    }
+
+    // Executes an entry on a network, returning the successor (state,
+    // network) pairs for every outcome
+    pub fn apply(&self, entry: usize, tn: &HTN, state: &HashSet<u32>)
+        -> Result<Vec<(HashSet<u32>, HTN)>, PolicyError> {
+        let output = &self.transitions[entry].1;
+        let inapplicable = || PolicyError::Inapplicable(output.task.clone(), output.method.clone());
+        for id in tn.get_unconstrained_tasks() {
+            let task = tn.get_task(id).borrow();
+            if task.get_name() != output.task {
+                continue;
+            }
+            match &*task {
+                Task::Primitive(action) if output.is_primitive() => {
+                    if !action.is_applicable(state) {
+                        return Err(inapplicable());
+                    }
+                    let new_tn = tn.apply_action(id);
+                    return Ok(action.transition(state).into_iter()
+                        .map(|s| (s, new_tn.clone()))
+                        .collect());
+                },
+                Task::Compound(CompoundTask { methods, .. }) if !output.is_primitive() => {
+                    let method = methods.iter().find(|m| m.name == output.method).ok_or_else(inapplicable)?;
+                    return Ok(vec![(state.clone(), tn.decompose(id, method))]);
+                },
+                _ => {}
+            }
+        }
+        Err(inapplicable())
+    }
+
+    // Checks that following the policy from the initial network of the
+    // problem always ends in an empty network, regardless of the outcomes.
+    // Returns the number of visited (state, network) pairs.
+    pub fn verify(&self, problem: &FONDProblem) -> Result<u32, PolicyError> {
+        let mut checked = 0;
+        // working stack of (state, network, depth on the current path)
+        let mut stack = vec![(problem.initial_state.clone(), Rc::new(problem.init_tn.clone()), 0)];
+        let mut path: Vec<(HashSet<u32>, Rc<HTN>)> = vec![];
+        while let Some((state, tn, depth)) = stack.pop() {
+            path.truncate(depth);
+            checked += 1;
+            if tn.is_empty() {
+                continue;
+            }
+            if path.iter().any(|(s, t)| *s == state && HTN::is_isomorphic(t, &tn)) {
+                return Err(PolicyError::Cycle);
+            }
+            let named_state: HashSet<String> = state.iter()
+                .map(|x| problem.facts.get_fact(*x).clone())
+                .collect();
+            let entry = match self.lookup(&named_state, &tn) {
+                Some(entry) => entry,
+                None => {
+                    let mut named_state: Vec<String> = named_state.into_iter().collect();
+                    named_state.sort();
+                    return Err(PolicyError::Uncovered(named_state));
+                }
+            };
+            for (new_state, new_tn) in self.apply(entry, &tn, &state)? {
+                stack.push((new_state, Rc::new(new_tn), depth + 1));
+            }
+            path.push((state, tn));
+        }
+        Ok(checked)
+    }
+
+    // Executes the policy once, the environment picks outcomes at random.
+    // Returns the executed actions along with their costs.
+    pub fn simulate<R: Rng>(&self, problem: &FONDProblem, rng: &mut R) -> Result<Vec<(String, u32)>, PolicyError> {
+        let mut trace = vec![];
+        let mut state = problem.initial_state.clone();
+        let mut tn = problem.init_tn.clone();
+        let mut history: Vec<(HashSet<u32>, HTN)> = vec![];
+        while !tn.is_empty() {
+            if history.iter().any(|(s, t)| *s == state && HTN::is_isomorphic(t, &tn)) {
+                return Err(PolicyError::Cycle);
+            }
+            let named_state: HashSet<String> = state.iter()
+                .map(|x| problem.facts.get_fact(*x).clone())
+                .collect();
+            let entry = match self.lookup(&named_state, &tn) {
+                Some(entry) => entry,
+                None => {
+                    let mut named_state: Vec<String> = named_state.into_iter().collect();
+                    named_state.sort();
+                    return Err(PolicyError::Uncovered(named_state));
+                }
+            };
+            let mut successors = self.apply(entry, &tn, &state)?;
+            let output = &self.transitions[entry].1;
+            if output.is_primitive() {
+                trace.push((output.task.clone(), output.cost));
+            }
+            let (new_state, new_tn) = successors.swap_remove(rng.gen_range(0..successors.len()));
+            history.push((state, tn));
+            state = new_state;
+            tn = new_tn;
+        }
+        Ok(trace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::*;
+    use crate::domain_description::{DomainTasks, Facts};
+    use crate::search::{AOStarSearch, HeuristicType, SearchResult};
+    use crate::task_network::{PrimitiveAction, Method};
+
+    // A conformant-like problem: p1 and p2 (non-deterministic) must both
+    // be executed before p3, while "irrelevant" is never required
+    fn generate_problem() -> FONDProblem {
+        let p1 = Task::Primitive(PrimitiveAction::new(
+            "p1".to_string(), 1, HashSet::from([0]),
+            vec![HashSet::from([1])], vec![HashSet::new()]
+        ));
+        let p2 = Task::Primitive(PrimitiveAction::new(
+            "p2".to_string(), 1, HashSet::new(),
+            vec![HashSet::from([2]), HashSet::from([2, 3])], vec![HashSet::new(), HashSet::from([4])]
+        ));
+        let p3 = Task::Primitive(PrimitiveAction::new(
+            "p3".to_string(), 1, HashSet::from([1, 2]),
+            vec![HashSet::new()], vec![HashSet::new()]
+        ));
+        let t1 = Task::Compound(CompoundTask::new("t1".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![p1, p2, p3, t1]));
+        let m = Method::new("m".to_string(), HTN::new(
+            BTreeSet::from([1, 2]), vec![(1, 2)], domain.clone(),
+            HashMap::from([(1, domain.get_id("p2")), (2, domain.get_id("p3"))])
+        ));
+        let domain = domain.add_methods(vec![(domain.get_id("t1"), m)]);
+        let tn = HTN::new(
+            BTreeSet::from([1, 2]), vec![(1, 2)], domain.clone(),
+            HashMap::from([(1, domain.get_id("p1")), (2, domain.get_id("t1"))])
+        );
+        let mut problem = FONDProblem {
+            facts: Facts::new(vec![
+                "0".to_string(), "1".to_string(), "2".to_string(),
+                "3".to_string(), "4".to_string(), "irrelevant".to_string()
+            ]),
+            tasks: domain,
+            initial_state: HashSet::from([0, 4, 5]),
+            init_tn: tn
+        };
+        problem.collapse_tn();
+        problem
+    }
+
+    fn solve(problem: &FONDProblem) -> StrongPolicy {
+        match AOStarSearch::run(problem, HeuristicType::HAdd).0 {
+            SearchResult::Success(policy) => policy,
+            _ => panic!("problem is solvable")
+        }
+    }
+
+    #[test]
+    pub fn full_policy_verification_test() {
+        let problem = generate_problem();
+        let policy = solve(&problem);
+        assert!(policy.verify(&problem).is_ok());
+    }
+
+    #[test]
+    pub fn compaction_test() {
+        let problem = generate_problem();
+        let policy = solve(&problem);
+        let compact = policy.compact(&problem.facts);
+        assert!(compact.transitions.len() <= policy.transitions.len());
+        for (input, output) in compact.transitions.iter() {
+            assert!(!input.state.contains("irrelevant"));
+            if output.task == "p3" {
+                assert_eq!(input.state, HashSet::from(["1".to_string(), "2".to_string()]));
+            }
+        }
+        // both outcomes of p2 lead to the same partial state for p3
+        assert_eq!(compact.transitions.len(), policy.transitions.len() - 1);
+        assert_eq!(compact.verify(&problem), Ok(8));
+    }
+
+    #[test]
+    pub fn simulation_test() {
+        use rand::{SeedableRng, rngs::StdRng};
+        let problem = generate_problem();
+        let policy = solve(&problem);
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10 {
+            let trace = policy.simulate(&problem, &mut rng).unwrap();
+            let actions: Vec<&str> = trace.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(actions, vec!["p1", "p2", "p3"]);
+        }
+    }
+
+    #[test]
+    pub fn uncovered_state_test() {
+        let problem = generate_problem();
+        let mut policy = solve(&problem);
+        policy.transitions.retain(|(_, output)| output.task != "p3");
+        assert!(matches!(policy.verify(&problem), Err(PolicyError::Uncovered(_))));
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::rc::Rc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::domain_description::DomainTasks;
+use crate::task_network::HTN;
+
+use super::{StrongPolicy, PolicyNode, PolicyOutput};
+
+// Self-contained description of a policy, tasks are referred to by name
+#[derive(Debug, Serialize, Deserialize)]
+struct RawPolicy {
+    makespan: u16,
+    entries: Vec<RawEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawEntry {
+    state: Vec<String>,
+    // node id -> task name
+    tasks: BTreeMap<u32, String>,
+    orderings: Vec<(u32, u32)>,
+    task: String,
+    method: String,
+    cost: u32,
+    successors: Vec<Option<usize>>,
+}
+
+impl StrongPolicy {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.to_json_value()).unwrap()
+    }
+
+    pub fn to_json_value(&self) -> serde_json::Value {
+        let entries = self.transitions.iter()
+            .zip(self.successors.iter())
+            .map(|((input, output), successors)| {
+                let mut state: Vec<String> = input.state.iter().cloned().collect();
+                state.sort();
+                let tasks = input.tn.get_nodes()
+                    .map(|x| (x, input.tn.get_task(x).borrow().get_name()))
+                    .collect();
+                let mut orderings = input.tn.get_orderings();
+                orderings.sort();
+                RawEntry {
+                    state,
+                    tasks,
+                    orderings,
+                    task: output.task.clone(),
+                    method: output.method.clone(),
+                    cost: output.cost,
+                    successors: successors.clone(),
+                }
+            }).collect();
+        serde_json::to_value(RawPolicy { makespan: self.makespan, entries }).unwrap()
+    }
+
+    // Reads a policy written by to_json, task names are resolved in the
+    // given domain
+    pub fn from_json(json: &str, domain: &Rc<DomainTasks>) -> Result<StrongPolicy, String> {
+        let raw: RawPolicy = serde_json::from_str(json).map_err(|e| format!("malformed policy: {}", e))?;
+        let n = raw.entries.len();
+        let mut transitions = vec![];
+        let mut successors = vec![];
+        for (i, entry) in raw.entries.into_iter().enumerate() {
+            let mut mappings = HashMap::new();
+            for (id, name) in entry.tasks.iter() {
+                if !domain.contains_name(name) {
+                    return Err(format!("entry {}: undefined task {}", i, name));
+                }
+                mappings.insert(*id, domain.get_id(name));
+            }
+            let nodes: BTreeSet<u32> = entry.tasks.keys().cloned().collect();
+            if entry.orderings.iter().any(|(x, y)| !nodes.contains(x) || !nodes.contains(y)) {
+                return Err(format!("entry {}: ordering over undefined nodes", i));
+            }
+            if entry.successors.iter().flatten().any(|x| *x >= n) {
+                return Err(format!("entry {}: successor out of range", i));
+            }
+            let tn = HTN::new(nodes, entry.orderings, domain.clone(), mappings);
+            let input = PolicyNode { state: entry.state.into_iter().collect(), tn: Rc::new(tn) };
+            let output = PolicyOutput { task: entry.task, method: entry.method, cost: entry.cost };
+            transitions.push((input, output));
+            successors.push(entry.successors);
+        }
+        Ok(StrongPolicy { transitions, successors, makespan: raw.makespan })
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "digraph policy {{").unwrap();
+        writeln!(out, "\tgoal [shape=doublecircle];").unwrap();
+        for (i, (input, output)) in self.transitions.iter().enumerate() {
+            let mut state: Vec<&String> = input.state.iter().collect();
+            state.sort();
+            let label = if output.is_primitive() {
+                output.task.clone()
+            } else {
+                format!("{} ({})", output.task, output.method)
+            };
+            let shape = if output.is_primitive() { "box" } else { "ellipse" };
+            writeln!(out, "\t{} [shape={}, label={:?}, tooltip={:?}];", i, shape, label, format!("{:?}", state)).unwrap();
+        }
+        for (i, successors) in self.successors.iter().enumerate() {
+            let labeled = successors.len() > 1;
+            for (k, succ) in successors.iter().enumerate() {
+                let target = match succ {
+                    Some(s) => s.to_string(),
+                    None => "goal".to_string(),
+                };
+                if labeled {
+                    writeln!(out, "\t{} -> {} [label=\"{}\"];", i, target, k).unwrap();
+                } else {
+                    writeln!(out, "\t{} -> {};", i, target).unwrap();
+                }
+            }
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task_network::{Task, PrimitiveAction, CompoundTask, Method};
+
+    #[test]
+    pub fn json_round_trip_test() {
+        let p1 = Task::Primitive(PrimitiveAction::new(
+            "p1".to_string(), 1, HashSet::new(),
+            vec![HashSet::from([0]), HashSet::new()], vec![HashSet::new(), HashSet::new()]
+        ));
+        let t1 = Task::Compound(CompoundTask::new("t1".to_string(), vec![]));
+        let domain = Rc::new(DomainTasks::new(vec![p1, t1]));
+        let m1 = Method::new("m1".to_string(), HTN::new(
+            BTreeSet::from([1]), vec![], domain.clone(), HashMap::from([(1, domain.get_id("p1"))])
+        ));
+        let domain = domain.add_methods(vec![(domain.get_id("t1"), m1)]);
+        let tn = |nodes: Vec<(u32, &str)>| Rc::new(HTN::new(
+            nodes.iter().map(|(x, _)| *x).collect(), vec![], domain.clone(),
+            nodes.iter().map(|(x, name)| (*x, domain.get_id(name))).collect()
+        ));
+        let policy = StrongPolicy {
+            transitions: vec![
+                (PolicyNode { state: HashSet::new(), tn: tn(vec![(1, "t1")]) },
+                    PolicyOutput { task: "t1".to_string(), method: "m1".to_string(), cost: 0 }),
+                (PolicyNode { state: HashSet::from(["a".to_string()]), tn: tn(vec![(2, "p1")]) },
+                    PolicyOutput { task: "p1".to_string(), method: "ε".to_string(), cost: 1 }),
+            ],
+            successors: vec![vec![Some(1)], vec![None, None]],
+            makespan: 2,
+        };
+        let parsed = StrongPolicy::from_json(&policy.to_json(), &domain).unwrap();
+        assert_eq!(parsed.successors, policy.successors);
+        assert_eq!(parsed.makespan, 2);
+        for ((i1, o1), (i2, o2)) in parsed.transitions.iter().zip(policy.transitions.iter()) {
+            assert_eq!(i1.state, i2.state);
+            assert!(HTN::is_isomorphic(&i1.tn, &i2.tn));
+            assert_eq!((&o1.task, &o1.method, o1.cost), (&o2.task, &o2.method, o2.cost));
+        }
+        let dot = policy.to_dot();
+        assert!(dot.contains("1 -> goal [label=\"1\"];"));
+        assert!(dot.contains("0 -> 1;"));
+        let missing = StrongPolicy::from_json(&policy.to_json(), &Rc::new(DomainTasks::new(vec![])));
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use super::StrongPolicy;
+
+// Quality measures of a strong policy. Costs account for the actions and
+// the methods that have a cost, depth only for decompositions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyMetrics {
+    // maximum total cost over all execution branches
+    pub worst_case_cost: u32,
+    // maximum number of primitive actions over all execution branches
+    pub worst_case_actions: u32,
+    // expected total cost under the outcome distribution
+    pub expected_cost: f64,
+    // number of distinct execution branches
+    pub branches: u64,
+    // maximum number of decompositions over all execution branches
+    pub max_decomposition_depth: u32,
+    // number of policy entries
+    pub size: usize,
+}
+
+impl PolicyMetrics {
+    // Metrics under uniformly distributed outcomes
+    pub fn new(policy: &StrongPolicy) -> PolicyMetrics {
+        PolicyMetrics::with_distribution(policy, &HashMap::new())
+    }
+
+    // Metrics under a given outcome distribution. The distribution maps an
+    // action name to the probability of each of its outcomes; actions that
+    // are not present (or whose number of outcomes does not match) are
+    // assumed to be uniformly distributed.
+    pub fn with_distribution(policy: &StrongPolicy, distribution: &HashMap<String, Vec<f64>>) -> PolicyMetrics {
+        let n = policy.transitions.len();
+        let mut cost = vec![0; n];
+        let mut actions = vec![0; n];
+        let mut expected = vec![0.0; n];
+        let mut branches = vec![0; n];
+        let mut depth = vec![0; n];
+        for entry in policy.topological_order() {
+            let output = &policy.transitions[entry].1;
+            let successors = &policy.successors[entry];
+            let probabilities = match distribution.get(&output.task) {
+                Some(p) if p.len() == successors.len() => p.clone(),
+                _ => vec![1.0 / successors.len() as f64; successors.len()],
+            };
+            let (mut max_cost, mut max_actions, mut max_depth) = (0, 0, 0);
+            let (mut expectation, mut n_branches) = (0.0, 0u64);
+            for (succ, p) in successors.iter().zip(probabilities.iter()) {
+                match succ {
+                    Some(s) => {
+                        max_cost = max_cost.max(cost[*s]);
+                        max_actions = max_actions.max(actions[*s]);
+                        max_depth = max_depth.max(depth[*s]);
+                        expectation += p * expected[*s];
+                        n_branches = n_branches.saturating_add(branches[*s]);
+                    },
+                    None => {
+                        n_branches = n_branches.saturating_add(1);
+                    }
+                }
+            }
+            cost[entry] = max_cost + output.cost;
+            expected[entry] = expectation + output.cost as f64;
+            if output.is_primitive() {
+                actions[entry] = max_actions + 1;
+                depth[entry] = max_depth;
+            } else {
+                actions[entry] = max_actions;
+                depth[entry] = max_depth + 1;
+            }
+            branches[entry] = n_branches;
+        }
+        // the first entry is the root of the policy
+        if n == 0 {
+            return PolicyMetrics {
+                worst_case_cost: 0, worst_case_actions: 0, expected_cost: 0.0,
+                branches: 1, max_decomposition_depth: 0, size: 0
+            };
+        }
+        PolicyMetrics {
+            worst_case_cost: cost[0],
+            worst_case_actions: actions[0],
+            expected_cost: expected[0],
+            branches: branches[0],
+            max_decomposition_depth: depth[0],
+            size: n,
+        }
+    }
+}
+
+impl std::fmt::Display for PolicyMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "worst-case cost: {}", self.worst_case_cost)?;
+        writeln!(f, "worst-case # of actions: {}", self.worst_case_actions)?;
+        writeln!(f, "expected cost: {:.3}", self.expected_cost)?;
+        writeln!(f, "# of execution branches: {}", self.branches)?;
+        writeln!(f, "max decomposition depth: {}", self.max_decomposition_depth)?;
+        writeln!(f, "policy size: {}", self.size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashSet};
+    use std::rc::Rc;
+
+    use super::*;
+    use super::super::{PolicyNode, PolicyOutput};
+    use crate::domain_description::DomainTasks;
+    use crate::task_network::HTN;
+
+    fn generate_policy() -> StrongPolicy {
+        let domain = Rc::new(DomainTasks::new(vec![]));
+        let tn = Rc::new(HTN::new(BTreeSet::new(), vec![], domain, HashMap::new()));
+        let node = || PolicyNode { state: HashSet::new(), tn: tn.clone() };
+        StrongPolicy {
+            transitions: vec![
+                (node(), PolicyOutput { task: "t1".to_string(), method: "m1".to_string(), cost: 0 }),
+                (node(), PolicyOutput { task: "nd".to_string(), method: "ε".to_string(), cost: 2 }),
+                (node(), PolicyOutput { task: "p".to_string(), method: "ε".to_string(), cost: 1 }),
+            ],
+            successors: vec![vec![Some(1)], vec![Some(2), None], vec![None]],
+            makespan: 2,
+        }
+    }
+
+    #[test]
+    pub fn uniform_metrics_test() {
+        let metrics = PolicyMetrics::new(&generate_policy());
+        assert_eq!(metrics.worst_case_cost, 3);
+        assert_eq!(metrics.worst_case_actions, 2);
+        assert_eq!(metrics.expected_cost, 2.5);
+        assert_eq!(metrics.branches, 2);
+        assert_eq!(metrics.max_decomposition_depth, 1);
+        assert_eq!(metrics.size, 3);
+    }
+
+    #[test]
+    pub fn given_distribution_test() {
+        let distribution = HashMap::from([("nd".to_string(), vec![0.2, 0.8])]);
+        let metrics = PolicyMetrics::with_distribution(&generate_policy(), &distribution);
+        assert!((metrics.expected_cost - 2.2).abs() < 1e-9);
+        assert_eq!(metrics.worst_case_cost, 3);
+    }
+}
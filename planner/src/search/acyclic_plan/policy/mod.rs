@@ -1,8 +1,16 @@
 mod strong_policy;
 mod node;
+mod metrics;
+mod compaction;
+mod execution;
+mod codegen;
+mod export;
 
 use super::*;
 
-pub use strong_policy::StrongPolicy;
-use node::PolicyNode;
+pub use strong_policy::{StrongPolicy, PolicyOutput};
+pub use metrics::PolicyMetrics;
+pub use execution::PolicyError;
+pub use codegen::{StateMachine, MachineState, MachineTransition};
+pub use node::PolicyNode;
 use search_graph::SearchGraph;
\ No newline at end of file
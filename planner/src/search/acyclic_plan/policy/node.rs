@@ -1,6 +1,8 @@
 use std::collections::HashSet;
 use std::rc::Rc;
 use super::HTN;
+// The state is either complete or, in compacted policies, restricted to
+// the facts the rest of the branch depends on
 #[derive(Debug)]
 pub struct PolicyNode{
     pub state: HashSet<String>,
@@ -1,7 +1,7 @@
 use std::{collections::{HashSet, LinkedList, HashMap}, vec};
 use std::rc::Rc;
 
-use crate::{domain_description::{DomainTasks, Facts}, task_network::HTN};
+use crate::{domain_description::{DomainTasks, Facts}, task_network::{HTN, Task, Applicability}};
 
 use super::*;
 
@@ -9,12 +9,25 @@ use super::*;
 #[derive(Debug)]
 pub struct PolicyOutput{
     pub task: String,
-    pub method: String 
+    pub method: String,
+    // cost of the executed action or of the method (0 for methods without
+    // a cost)
+    pub cost: u32,
+}
+
+impl PolicyOutput {
+    pub fn is_primitive(&self) -> bool {
+        self.method == "ε"
+    }
 }
 
 #[derive(Debug)]
 pub struct StrongPolicy {
     pub transitions: Vec<(PolicyNode, PolicyOutput)>,
+    // successors[i][k] is the entry that follows entry i when outcome k
+    // is observed (decompositions have a single outcome), None if the
+    // branch terminates in a goal node
+    pub successors: Vec<Vec<Option<usize>>>,
     pub makespan: u16,
 }
 
@@ -22,6 +35,8 @@ impl StrongPolicy {
     pub fn new(facts: &Facts, computation_history: &SearchGraph) -> StrongPolicy {
         // vec of (state, vec(exectuted_task_names), new_task)
         let mut policy = vec![];
+        let mut entry_ids: Vec<u32> = vec![];
+        let mut indices: HashMap<u32, usize> = HashMap::new();
         let mut visited = HashSet::new();
         let mut working_set: LinkedList<u32> = LinkedList::from([computation_history.root]);
         let mut makespan = u16::MIN;;
@@ -50,21 +65,27 @@ impl StrongPolicy {
                         }
                         // Check whether transition is decomposition or primitive action execution
                         match &marked.action_type {
-                            ConnectionLabel::Decomposition(name, method) => {
+                            ConnectionLabel::Decomposition(name, method, cost) => {
                                 let output = PolicyOutput {
                                     task: name.clone(),
-                                    method: method.clone()
+                                    method: method.clone(),
+                                    cost: cost.unwrap_or(0)
                                 };
+                                indices.insert(id, policy.len());
+                                entry_ids.push(id);
                                 policy.push((input, output));
                                 for child in marked.children.iter(){
                                     working_set.push_back(*child);
                                 }
                             },
-                            ConnectionLabel::Execution(name, _) => {
+                            ConnectionLabel::Execution(name, cost) => {
                                 let output = PolicyOutput {
                                     task: name.clone(),
-                                    method: "ε".to_string()
+                                    method: "ε".to_string(),
+                                    cost: *cost
                                 };
+                                indices.insert(id, policy.len());
+                                entry_ids.push(id);
                                 policy.push((input, output));
                                 for child in marked.children.iter() {
                                     working_set.push_back(*child);
@@ -77,11 +98,72 @@ impl StrongPolicy {
                     }
                 }
                 None => {
-                    
+
+                }
+            }
+        }
+        let successors = entry_ids.iter().map(|id| {
+            StrongPolicy::outcome_successors(computation_history, *id)
+                .into_iter()
+                .map(|child| indices.get(&child).cloned())
+                .collect()
+        }).collect();
+        StrongPolicy { transitions: policy, successors, makespan }
+    }
+
+    // Orders the children of the marked connector of a node by the outcome
+    // they correspond to
+    fn outcome_successors(computation_history: &SearchGraph, id: u32) -> Vec<u32> {
+        let node = computation_history.ids.get(&id).unwrap().borrow();
+        let marked = node.get_marked_connection().unwrap();
+        match &marked.action_type {
+            ConnectionLabel::Decomposition(..) => {
+                marked.children.iter().cloned().collect()
+            },
+            ConnectionLabel::Execution(name, _) => {
+                let task_id = node.tn.domain.get_id(name);
+                let outcomes = match &*node.tn.domain.get_task(task_id).borrow() {
+                    Task::Primitive(action) => action.transition(node.state.as_ref()),
+                    Task::Compound(_) => panic!("{} is not primitive", name)
+                };
+                outcomes.iter().map(|outcome| {
+                    *marked.children.iter().find(|child| {
+                        computation_history.ids.get(child).unwrap().borrow().state.as_ref() == outcome
+                    }).expect("outcome is not covered by the policy")
+                }).collect()
+            }
+        }
+    }
+
+    // Entries of the policy ordered such that every entry comes after
+    // all of its successors
+    pub fn topological_order(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.transitions.len());
+        let mut visited = vec![false; self.transitions.len()];
+        for start in 0..self.transitions.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut stack = vec![(start, 0)];
+            while let Some((entry, cursor)) = stack.pop() {
+                let next = self.successors[entry].iter().skip(cursor).enumerate().find_map(|(i, succ)| {
+                    match succ {
+                        Some(s) if !visited[*s] => Some((cursor + i, *s)),
+                        _ => None,
+                    }
+                });
+                match next {
+                    Some((i, succ)) => {
+                        stack.push((entry, i + 1));
+                        visited[succ] = true;
+                        stack.push((succ, 0));
+                    },
+                    None => order.push(entry),
                 }
-            } 
+            }
         }
-        StrongPolicy { transitions: policy, makespan: makespan }
+        order
     }
 }
 
@@ -93,4 +175,4 @@ impl std::fmt::Display for StrongPolicy {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
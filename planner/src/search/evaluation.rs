@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use super::{progress, ConnectionLabel, Heuristic, HeuristicContext, HTN};
+use crate::domain_description::FONDProblem;
+use crate::relaxation::RelaxedComposition;
+
+// task network, state and bounded optimal cost of a node
+type Sample = (Rc<HTN>, Rc<HashSet<u32>>, Option<f32>);
+
+// Optimal worst-case costs (decompositions cost 0 unless their method has a
+// cost, as in the relaxed composition) of the nodes of
+// the search space within a bounded number of progression steps. Bounded
+// costs over-approximate the optimal ones, so every reported admissibility
+// violation is a real one. Nodes whose cost depends on the part of the
+// search space beyond the bounds have an unknown (None) cost.
+pub struct HeuristicEvaluation {
+    // every non-goal node
+    samples: Vec<Sample>,
+    pub root_cost: Option<f32>,
+    // whether the depth or the node limit cut the exploration short
+    pub truncated: bool,
+    encoder: RelaxedComposition,
+    bijection: HashMap<u32, u32>,
+}
+
+// Comparison of a heuristic with the optimal costs
+#[derive(Debug, Clone)]
+pub struct HeuristicReport {
+    pub heuristic: String,
+    pub nodes: u32,
+    // nodes whose optimal cost is unknown, they are left out of the other
+    // fields
+    pub unknown: u32,
+    // nodes where the heuristic exceeds the optimal cost
+    pub violations: u32,
+    pub max_violation: f32,
+    // mean of |h* - h| over the nodes where both are finite
+    pub average_error: f32,
+    // mean of h / h* over the nodes with a finite positive optimal cost
+    pub informedness: f32,
+    // nodes without a solution, and those of them with an infinite
+    // heuristic value
+    pub dead_ends: u32,
+    pub detected_dead_ends: u32,
+}
+
+impl HeuristicEvaluation {
+    // Explores at most node_limit nodes up to the given depth
+    pub fn new(problem: &FONDProblem, depth: u16, node_limit: u32) -> HeuristicEvaluation {
+        let (encoder, bijection) = RelaxedComposition::from_fond_problem(problem);
+        let mut evaluation = HeuristicEvaluation {
+            samples: vec![],
+            root_cost: None,
+            truncated: false,
+            encoder,
+            bijection,
+        };
+        let mut budget = node_limit;
+        evaluation.root_cost = evaluation.optimal_cost(
+            Rc::new(problem.init_tn.clone()),
+            Rc::new(problem.initial_state.clone()),
+            depth,
+            &mut budget
+        );
+        evaluation
+    }
+
+    // Nodes cut off by the bounds have an unknown cost and are not sampled.
+    // A node is a dead end only if every expansion provably fails, and has
+    // a finite (bounded) cost if some expansion is solved within the bounds.
+    fn optimal_cost(&mut self, tn: Rc<HTN>, state: Rc<HashSet<u32>>, depth: u16, budget: &mut u32) -> Option<f32> {
+        if tn.is_goal() {
+            return Some(0.0);
+        }
+        if depth == 0 || *budget == 0 {
+            self.truncated = true;
+            return None;
+        }
+        *budget -= 1;
+        let mut best = Some(f32::INFINITY);
+        for expansion in progress(tn.clone(), state.clone()) {
+            let step_cost = match expansion.connection_label {
+                ConnectionLabel::Execution(_, cost) => cost as f32,
+                ConnectionLabel::Decomposition(_, _, cost) => cost.unwrap_or(0) as f32,
+            };
+            // an outcome that provably fails makes the expansion fail even
+            // if the cost of another outcome is unknown
+            let mut worst_case = Some(0.0);
+            for next in expansion.states.iter() {
+                let cost = self.optimal_cost(expansion.tn.clone(), next.clone(), depth - 1, budget);
+                worst_case = match (worst_case, cost) {
+                    (Some(x), Some(y)) => Some(x.max(y)),
+                    (Some(x), None) | (None, Some(x)) if x.is_infinite() => Some(x),
+                    _ => None,
+                };
+            }
+            // a solved expansion bounds the cost even if the cost of
+            // another one is unknown
+            best = match (best, worst_case.map(|x| step_cost + x)) {
+                (Some(x), Some(y)) => Some(x.min(y)),
+                (Some(x), None) | (None, Some(x)) if x.is_finite() => Some(x),
+                _ => None,
+            };
+        }
+        self.samples.push((tn, state, best));
+        best
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn evaluate(&self, name: &str, heuristic: &mut dyn Heuristic) -> HeuristicReport {
+        assert!(!heuristic.needs_landmarks(), "{} depends on the path to a node", name);
+        let context = HeuristicContext {
+            encoder: &self.encoder,
+            bijection: &self.bijection,
+            landmarks: None,
+            // as in the optimal costs
+            free_decompositions: true,
+        };
+        let mut report = HeuristicReport {
+            heuristic: name.to_string(),
+            nodes: self.samples.len() as u32,
+            unknown: 0,
+            violations: 0,
+            max_violation: 0.0,
+            average_error: 0.0,
+            informedness: 0.0,
+            dead_ends: 0,
+            detected_dead_ends: 0,
+        };
+        let (mut errors, mut ratios) = (vec![], vec![]);
+        for (tn, state, cost) in self.samples.iter() {
+            let cost = match cost {
+                Some(cost) => cost,
+                None => {
+                    report.unknown += 1;
+                    continue;
+                }
+            };
+            let h = heuristic.h_val(tn, state, &context);
+            if h > *cost {
+                report.violations += 1;
+                report.max_violation = report.max_violation.max(h - cost);
+            }
+            if *cost == f32::INFINITY {
+                report.dead_ends += 1;
+                if h == f32::INFINITY {
+                    report.detected_dead_ends += 1;
+                }
+            } else if h != f32::INFINITY {
+                errors.push((cost - h).abs());
+                if *cost > 0.0 {
+                    ratios.push(h / cost);
+                }
+            }
+        }
+        let mean = |x: Vec<f32>| if x.is_empty() { 0.0 } else { x.iter().sum::<f32>() / x.len() as f32 };
+        report.average_error = mean(errors);
+        report.informedness = mean(ratios);
+        report
+    }
+}
+
+impl HeuristicReport {
+    pub const CSV_HEADER: &'static str =
+        "heuristic,nodes,unknown,violations,max_violation,average_error,informedness,dead_ends,detected_dead_ends";
+
+    pub fn is_admissible(&self) -> bool {
+        self.violations == 0
+    }
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{:.3},{:.3},{},{}",
+            self.heuristic, self.nodes, self.unknown, self.violations, self.max_violation,
+            self.average_error, self.informedness, self.dead_ends, self.detected_dead_ends
+        )
+    }
+}
+
+pub fn to_csv(reports: &[HeuristicReport]) -> String {
+    let mut csv = HeuristicReport::CSV_HEADER.to_string() + "\n";
+    for report in reports.iter() {
+        csv += &report.to_csv_row();
+        csv += "\n";
+    }
+    csv
+}
@@ -1,5 +1,69 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum HeuristicType{
     HFF,
     HAdd,
-    HMax
-}
\ No newline at end of file
+    HMax,
+    // h_add and h_max with the outcomes of nondeterministic actions picked
+    // by an adversary
+    HAddWC,
+    HMaxWC,
+    // h_add evaluated along the ordering constraints of the network
+    OrderedAdd,
+    // admissible LM-cut on the relaxed composition
+    LMCut,
+    // number of task and fact landmarks still to be achieved
+    LandmarkCount,
+    // operator-counting LP over TDG flow and LM-cut landmark constraints
+    LP,
+    // minimal number of modifications (actions and methods) in the TDG
+    TDGm,
+    // minimal cost of the actions and methods in the TDG
+    TDGc,
+    // TDG_m and TDG_c restricted to relaxed reachable actions
+    TDGmR,
+    TDGcR,
+}
+
+impl std::str::FromStr for HeuristicType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hff" | "ff" => Ok(HeuristicType::HFF),
+            "hadd" | "add" => Ok(HeuristicType::HAdd),
+            "hmax" | "max" => Ok(HeuristicType::HMax),
+            "hadd-wc" => Ok(HeuristicType::HAddWC),
+            "hmax-wc" => Ok(HeuristicType::HMaxWC),
+            "hadd-o" => Ok(HeuristicType::OrderedAdd),
+            "lmcut" => Ok(HeuristicType::LMCut),
+            "lmcount" => Ok(HeuristicType::LandmarkCount),
+            "lp" => Ok(HeuristicType::LP),
+            "tdgm" => Ok(HeuristicType::TDGm),
+            "tdgc" => Ok(HeuristicType::TDGc),
+            "tdgm-r" => Ok(HeuristicType::TDGmR),
+            "tdgc-r" => Ok(HeuristicType::TDGcR),
+            _ => Err(format!("unknown heuristic {} (expected hff, hadd, hmax, hadd-wc, hmax-wc, hadd-o, lmcut, lmcount, lp, tdgm, tdgc, tdgm-r or tdgc-r)", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for HeuristicType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            HeuristicType::HFF => "hff",
+            HeuristicType::HAdd => "hadd",
+            HeuristicType::HMax => "hmax",
+            HeuristicType::HAddWC => "hadd-wc",
+            HeuristicType::HMaxWC => "hmax-wc",
+            HeuristicType::OrderedAdd => "hadd-o",
+            HeuristicType::LMCut => "lmcut",
+            HeuristicType::LandmarkCount => "lmcount",
+            HeuristicType::LP => "lp",
+            HeuristicType::TDGm => "tdgm",
+            HeuristicType::TDGc => "tdgc",
+            HeuristicType::TDGmR => "tdgm-r",
+            HeuristicType::TDGcR => "tdgc-r",
+        };
+        write!(f, "{}", name)
+    }
+}
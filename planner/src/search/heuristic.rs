@@ -0,0 +1,195 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{HTN, HeuristicType};
+use crate::heuristics::{
+    Landmarks, h_add, h_add_wc, h_ff, h_lmcount, h_lmcut, h_lp, h_max, h_max_wc, h_ordered, h_tdg
+};
+use crate::relaxation::RelaxedComposition;
+
+// What a heuristic can look at besides the node's task network and state
+pub struct HeuristicContext<'a> {
+    pub encoder: &'a RelaxedComposition,
+    // task ids of the problem -> task ids of the relaxed composition
+    pub bijection: &'a HashMap<u32, u32>,
+    // landmarks of the root and the fact landmarks accepted on the path to
+    // the node, only available if the heuristic needs them
+    pub landmarks: Option<(&'a Landmarks, &'a HashSet<u32>)>,
+    // whether decompositions by methods without a cost are free, as in
+    // SearchConfig
+    pub free_decompositions: bool,
+}
+
+// Estimate of the cost of solving a task network from a state. Returning
+// infinity marks the node as a dead end.
+pub trait Heuristic: std::fmt::Debug {
+    fn h_val(&mut self, tn: &HTN, state: &HashSet<u32>, context: &HeuristicContext) -> f32;
+
+    // whether values only depend on the state and the task multiset, in which
+    // case they are cached
+    fn is_cacheable(&self) -> bool {
+        true
+    }
+
+    // whether the search has to track landmarks for the context
+    fn needs_landmarks(&self) -> bool {
+        false
+    }
+
+    // called once per expansion, before the new nodes are evaluated
+    fn next_expansion(&mut self) {}
+}
+
+impl Heuristic for HeuristicType {
+    fn h_val(&mut self, tn: &HTN, state: &HashSet<u32>, context: &HeuristicContext) -> f32 {
+        let (encoder, free) = (context.encoder, context.free_decompositions);
+        // number of occurrences of every task, in the relaxed composition
+        let tasks: HashMap<u32, u32> = tn.count_tasks_with_frequency().iter()
+            .map(|(task, count)| (*context.bijection.get(task).unwrap(), *count))
+            .collect();
+        let task_ids: Vec<u32> = tasks.keys().cloned().collect();
+        let relaxed_state = || encoder.compute_relaxed_state(&task_ids, state);
+        let goal_state = || encoder.compute_goal_state(&task_ids);
+        let h_relaxed = match self {
+            HeuristicType::HFF => h_ff(&encoder.domain, &relaxed_state(), &goal_state()),
+            HeuristicType::HAdd => h_add(&encoder.domain, &relaxed_state(), &goal_state()),
+            HeuristicType::HMax => h_max(&encoder.domain, &relaxed_state(), &goal_state()),
+            HeuristicType::HAddWC => h_add_wc(&encoder.domain, encoder.outcomes(), &relaxed_state(), &goal_state()),
+            // repeated tasks may be decomposed into nothing, compensating for
+            // them would break admissibility
+            HeuristicType::HMaxWC => {
+                return h_max_wc(&encoder.domain, encoder.outcomes(), &relaxed_state(), &goal_state())
+            },
+            HeuristicType::LMCut => return h_lmcut(&encoder.domain, &relaxed_state(), &goal_state()),
+            // the remaining heuristics handle repeated tasks themselves
+            HeuristicType::OrderedAdd => return h_ordered(tn, state, encoder, context.bijection),
+            HeuristicType::LP => return h_lp(encoder, &tasks, &relaxed_state(), &goal_state(), free),
+            HeuristicType::TDGm => return h_tdg(encoder.tdg(), &tasks, state, true, false, free),
+            HeuristicType::TDGc => return h_tdg(encoder.tdg(), &tasks, state, false, false, free),
+            HeuristicType::TDGmR => return h_tdg(encoder.tdg(), &tasks, state, true, true, free),
+            HeuristicType::TDGcR => return h_tdg(encoder.tdg(), &tasks, state, false, true, free),
+            HeuristicType::LandmarkCount => {
+                let (landmarks, accepted) = context.landmarks.expect("landmarks are not initialized");
+                return h_lmcount(landmarks, &tasks, accepted);
+            },
+        };
+        // compensate for the repetition of tasks
+        h_relaxed + tasks.values().map(|count| (count - 1) as f32).sum::<f32>()
+    }
+
+    // landmark counts depend on the path to the node and the order-aware
+    // h_add on the orderings of the network
+    fn is_cacheable(&self) -> bool {
+        !matches!(self, HeuristicType::LandmarkCount | HeuristicType::OrderedAdd)
+    }
+
+    fn needs_landmarks(&self) -> bool {
+        *self == HeuristicType::LandmarkCount
+    }
+}
+
+// Maximum of several heuristics
+#[derive(Debug)]
+pub struct MaxHeuristic {
+    heuristics: Vec<Box<dyn Heuristic>>,
+}
+
+impl MaxHeuristic {
+    pub fn new(heuristics: Vec<Box<dyn Heuristic>>) -> MaxHeuristic {
+        MaxHeuristic { heuristics }
+    }
+}
+
+impl Heuristic for MaxHeuristic {
+    fn h_val(&mut self, tn: &HTN, state: &HashSet<u32>, context: &HeuristicContext) -> f32 {
+        self.heuristics.iter_mut()
+            .map(|h| h.h_val(tn, state, context))
+            .fold(0.0, f32::max)
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.heuristics.iter().all(|h| h.is_cacheable())
+    }
+
+    fn needs_landmarks(&self) -> bool {
+        self.heuristics.iter().any(|h| h.needs_landmarks())
+    }
+
+    fn next_expansion(&mut self) {
+        self.heuristics.iter_mut().for_each(|h| h.next_expansion());
+    }
+}
+
+// Weighted sum of several heuristics, a dead end for any of them is a dead
+// end for the sum
+#[derive(Debug)]
+pub struct WeightedSum {
+    heuristics: Vec<(f32, Box<dyn Heuristic>)>,
+}
+
+impl WeightedSum {
+    pub fn new(heuristics: Vec<(f32, Box<dyn Heuristic>)>) -> WeightedSum {
+        WeightedSum { heuristics }
+    }
+}
+
+impl Heuristic for WeightedSum {
+    fn h_val(&mut self, tn: &HTN, state: &HashSet<u32>, context: &HeuristicContext) -> f32 {
+        let mut total = 0.0;
+        for (weight, h) in self.heuristics.iter_mut() {
+            let value = h.h_val(tn, state, context);
+            if value == f32::INFINITY {
+                return f32::INFINITY;
+            }
+            total += *weight * value;
+        }
+        total
+    }
+
+    fn is_cacheable(&self) -> bool {
+        self.heuristics.iter().all(|(_, h)| h.is_cacheable())
+    }
+
+    fn needs_landmarks(&self) -> bool {
+        self.heuristics.iter().any(|(_, h)| h.needs_landmarks())
+    }
+
+    fn next_expansion(&mut self) {
+        self.heuristics.iter_mut().for_each(|(_, h)| h.next_expansion());
+    }
+}
+
+// Evaluates the nodes of each expansion with the next heuristic in turn
+#[derive(Debug)]
+pub struct Alternation {
+    heuristics: Vec<Box<dyn Heuristic>>,
+    current: usize,
+}
+
+impl Alternation {
+    pub fn new(heuristics: Vec<Box<dyn Heuristic>>) -> Alternation {
+        assert!(!heuristics.is_empty(), "alternation between no heuristics");
+        // the first expansion moves on to the first heuristic
+        let current = heuristics.len() - 1;
+        Alternation { heuristics, current }
+    }
+}
+
+impl Heuristic for Alternation {
+    fn h_val(&mut self, tn: &HTN, state: &HashSet<u32>, context: &HeuristicContext) -> f32 {
+        self.heuristics[self.current].h_val(tn, state, context)
+    }
+
+    // the value of a node depends on the expansion it was created in
+    fn is_cacheable(&self) -> bool {
+        false
+    }
+
+    fn needs_landmarks(&self) -> bool {
+        self.heuristics.iter().any(|h| h.needs_landmarks())
+    }
+
+    fn next_expansion(&mut self) {
+        self.current = (self.current + 1) % self.heuristics.len();
+        self.heuristics.iter_mut().for_each(|h| h.next_expansion());
+    }
+}
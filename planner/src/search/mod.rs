@@ -1,12 +1,20 @@
 mod acyclic_plan;
 mod search_stats;
 mod h_type;
+mod heuristic;
+mod search_config;
 mod progression;
 mod search_graph;
+mod width;
+mod evaluation;
 
 use super::task_network::{HTN, Applicability, Task, CompoundTask, PrimitiveAction};
 use search_stats::SearchStats;
 pub use h_type::HeuristicType;
+pub use heuristic::{Heuristic, HeuristicContext, MaxHeuristic, WeightedSum, Alternation};
+pub use search_config::{SearchConfig, TipSelection};
 pub use acyclic_plan::*;
+pub use width::{IWSearch, IWResult, NoveltyTable, Atom};
+pub use evaluation::{HeuristicEvaluation, HeuristicReport, to_csv};
 use progression::*;
 use search_graph::*;
\ No newline at end of file
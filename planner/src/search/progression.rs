@@ -4,6 +4,7 @@ use std::{
     rc::Rc,
 };
 use super::*;
+use crate::relaxation::AppliedTask;
 
 pub fn progress(tn: Rc<HTN>, state: Rc<HashSet<u32>>) -> Vec<NodeExpansion> {
     if tn.is_goal() {
@@ -21,6 +22,7 @@ pub fn progress(tn: Rc<HTN>, state: Rc<HashSet<u32>>) -> Vec<NodeExpansion> {
                     let new_state = a.transition(state.as_ref())[0].clone();
                     expansions.push(NodeExpansion {
                         connection_label: ConnectionLabel::Execution(a.name.clone(), a.cost),
+                        applied_task: (tn.mappings[p], None),
                         tn: Rc::new(new_tn),
                         states: vec![Rc::new(new_state)]
                     });
@@ -34,6 +36,7 @@ pub fn progress(tn: Rc<HTN>, state: Rc<HashSet<u32>>) -> Vec<NodeExpansion> {
                         }).collect();
                     expansions.push(NodeExpansion {
                         connection_label: ConnectionLabel::Execution(a.name.clone(), a.cost),
+                        applied_task: (tn.mappings[p], None),
                         tn: new_tn,
                         states: new_states
                     });
@@ -46,13 +49,15 @@ pub fn progress(tn: Rc<HTN>, state: Rc<HashSet<u32>>) -> Vec<NodeExpansion> {
         if let Task::Compound(
             CompoundTask { name, methods }
         ) = &*tn.get_task(*abstract_id).borrow() {
-            for method in methods.iter() {
+            for (i, method) in methods.iter().enumerate() {
                 let new_tn = Rc::new(tn.decompose(*abstract_id, method));
                 expansions.push(NodeExpansion {
                     connection_label: ConnectionLabel::Decomposition(
                         name.clone(),
                         method.name.clone(),
+                        method.cost,
                     ),
+                    applied_task: (tn.mappings[abstract_id], Some(i)),
                     tn: new_tn,
                     states: vec![state.clone()]
                 });
@@ -66,6 +71,9 @@ pub fn progress(tn: Rc<HTN>, state: Rc<HashSet<u32>>) -> Vec<NodeExpansion> {
 #[derive(Debug)]
 pub struct NodeExpansion {
     pub connection_label: ConnectionLabel,
+    // task of the domain that is executed or decomposed (with the index of
+    // the method)
+    pub applied_task: AppliedTask,
     pub tn: Rc<HTN>,
     pub states: Vec<Rc<HashSet<u32>>>,
 }
@@ -73,14 +81,14 @@ pub struct NodeExpansion {
 #[derive(Debug)]
 pub enum ConnectionLabel {
     Execution(String, u32),
-    // task name - method name
-    Decomposition(String, String),
+    // task name - method name - method cost (if given)
+    Decomposition(String, String, Option<u32>),
 }
 
 impl ConnectionLabel {
     pub fn is_decomposition(&self) -> bool {
         match &self {
-            ConnectionLabel::Decomposition(_, _) => true,
+            ConnectionLabel::Decomposition(..) => true,
             _ => false,
         }
     }
@@ -88,7 +96,7 @@ impl ConnectionLabel {
     pub fn get_label(&self) -> String {
         match self {
             Self::Execution(name, _) => name.clone(),
-            Self::Decomposition(name, method) => name.clone() + &format!("_{}", method),
+            Self::Decomposition(name, method, _) => name.clone() + &format!("_{}", method),
         }
     }
 }
@@ -179,7 +187,7 @@ mod tests {
         let exp_t1: Vec<&NodeExpansion> = expansion
             .iter()
             .filter(|x| match x.connection_label {
-                ConnectionLabel::Decomposition(_, _) => true,
+                ConnectionLabel::Decomposition(..) => true,
                 _ => false,
             })
             .collect();
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use super::search_graph::DEFAULT_CACHE_SIZE;
+
+// Strategy used to pick the next node to expand among the unexpanded nodes
+// of the current partial solution
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TipSelection {
+    // first unexpanded node found by a breadth-first traversal
+    FirstFound,
+    Deepest,
+    Shallowest,
+    // node with the lowest heuristic value
+    MinCost,
+    // node with the highest heuristic value
+    MaxCost,
+    // node with the lowest heuristic value, ties are broken by the novelty
+    // of the node when it was generated
+    Novelty,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchConfig {
+    pub tip_selection: TipSelection,
+    // heuristic values of new nodes are multiplied by this factor
+    pub weight: f32,
+    // maximum number of node expansions
+    pub node_limit: Option<u32>,
+    pub time_limit: Option<Duration>,
+    // maximum number of cached heuristic values, 0 disables the cache
+    pub cache_size: usize,
+    // expand successors in the relaxed plan of a node first
+    pub preferred_operators: bool,
+    // decompositions by methods without a cost are free instead of costing 1
+    pub free_decompositions: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            tip_selection: TipSelection::FirstFound,
+            weight: 1.0,
+            node_limit: None,
+            time_limit: None,
+            cache_size: DEFAULT_CACHE_SIZE,
+            preferred_operators: false,
+            free_decompositions: false,
+        }
+    }
+}
+
+impl std::str::FromStr for TipSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-found" => Ok(TipSelection::FirstFound),
+            "deepest" => Ok(TipSelection::Deepest),
+            "shallowest" => Ok(TipSelection::Shallowest),
+            "min-cost" => Ok(TipSelection::MinCost),
+            "max-cost" => Ok(TipSelection::MaxCost),
+            "novelty" => Ok(TipSelection::Novelty),
+            _ => Err(format!(
+                "unknown tip selection {} (expected first-found, deepest, shallowest, min-cost, max-cost or novelty)", s
+            )),
+        }
+    }
+}
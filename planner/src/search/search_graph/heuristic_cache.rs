@@ -0,0 +1,89 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use super::HTN;
+
+// state and multiset of tasks (task id -> number of occurrences)
+type CacheKey = (BTreeSet<u32>, BTreeMap<u32, u32>);
+
+// Bounded map from (state, task multiset) to heuristic values. Only the
+// heuristics that ignore the orderings and the path to the node are cached
+// (see Heuristic::is_cacheable). When full, the oldest entries are evicted
+// first.
+#[derive(Debug)]
+pub struct HeuristicCache {
+    capacity: usize,
+    values: HashMap<CacheKey, f32>,
+    order: VecDeque<CacheKey>,
+    pub hits: u32,
+    pub misses: u32,
+}
+
+impl HeuristicCache {
+    pub fn new(capacity: usize) -> HeuristicCache {
+        HeuristicCache { capacity, values: HashMap::new(), order: VecDeque::new(), hits: 0, misses: 0 }
+    }
+
+    pub fn key(tn: &HTN, state: &HashSet<u32>) -> CacheKey {
+        (state.iter().cloned().collect(), tn.count_tasks_with_frequency().into_iter().collect())
+    }
+
+    pub fn get(&mut self, key: &CacheKey) -> Option<f32> {
+        match self.values.get(key) {
+            Some(value) => {
+                self.hits += 1;
+                Some(*value)
+            },
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: CacheKey, value: f32) {
+        if self.capacity == 0 || self.values.contains_key(&key) {
+            return;
+        }
+        if self.values.len() == self.capacity {
+            let oldest = self.order.pop_front().unwrap();
+            self.values.remove(&oldest);
+        }
+        self.order.push_back(key.clone());
+        self.values.insert(key, value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(state: Vec<u32>, tasks: Vec<(u32, u32)>) -> CacheKey {
+        (state.into_iter().collect(), tasks.into_iter().collect())
+    }
+
+    #[test]
+    pub fn eviction_test() {
+        let mut cache = HeuristicCache::new(2);
+        cache.insert(key(vec![0], vec![(1, 1)]), 1.0);
+        cache.insert(key(vec![0], vec![(1, 2)]), 2.0);
+        assert_eq!(cache.get(&key(vec![0], vec![(1, 1)])), Some(1.0));
+        assert_eq!(cache.get(&key(vec![1], vec![(1, 1)])), None);
+        cache.insert(key(vec![1], vec![(1, 1)]), 3.0);
+        assert_eq!(cache.len(), 2);
+        // the oldest entry is evicted
+        assert_eq!(cache.get(&key(vec![0], vec![(1, 1)])), None);
+        assert_eq!(cache.get(&key(vec![0], vec![(1, 2)])), Some(2.0));
+        assert_eq!((cache.hits, cache.misses), (2, 2));
+        let mut disabled = HeuristicCache::new(0);
+        disabled.insert(key(vec![], vec![]), 0.0);
+        assert_eq!(disabled.len(), 0);
+    }
+}
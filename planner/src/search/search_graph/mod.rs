@@ -1,8 +1,10 @@
 mod search_graph;
 mod search_node;
 mod connectors;
+mod heuristic_cache;
 use super::*;
 
 pub use search_graph::*;
 pub use search_node::*;
 pub use connectors::*;
+pub use heuristic_cache::HeuristicCache;
@@ -1,17 +1,23 @@
 use h_type::HeuristicType;
 
 use crate::domain_description::{ClassicalDomain, Facts};
-use crate::relaxation::OutcomeDeterminizer;
 use std::borrow::BorrowMut;
 use std::collections::{HashMap, HashSet, LinkedList, BTreeSet};
 use std::vec;
 
 use super::*;
 use crate::relaxation::RelaxedComposition;
+use crate::heuristics::{Landmarks, ff_relaxed_plan};
+use crate::relaxation::{ActionOrigin, AppliedTask};
 use crate::domain_description::FONDProblem;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+// maximum number of cached heuristic values
+pub const DEFAULT_CACHE_SIZE: usize = 100_000;
+// tuple size used for novelty-based tip selection
+pub const NOVELTY_WIDTH: usize = 2;
+
 // TODO: convert ids to a regular vector/array
 #[derive(Debug)]
 pub struct SearchGraph {
@@ -20,16 +26,38 @@ pub struct SearchGraph {
     // Keeps teack of maximum u32 ID used in the tree
     pub cursor: u32,
     pub relaxed_domain: Option<(RelaxedComposition, HashMap<u32, u32>)>,
+    // landmarks of the root and the fact landmarks accepted on the path to
+    // each node, only used by the landmark-count heuristic
+    pub landmarks: Option<(Landmarks, HashMap<u32, HashSet<u32>>)>,
+    pub h_cache: HeuristicCache,
+    // whether successors in the relaxed plan of a node come first
+    pub preferred_operators: bool,
+    // atoms seen so far and the novelty of each node when it was generated,
+    // only used by novelty-based tip selection
+    pub novelty: Option<(NoveltyTable, HashMap<u32, usize>)>,
+    // whether decompositions by methods without a cost are free, they cost 1
+    // otherwise
+    pub free_decompositions: bool,
+    // whether each task of the domain can ever be decomposed, new nodes with
+    // a task that can not fail right away
+    pub decomposable: Vec<bool>,
+    // number of nodes failed because of such a task
+    pub undecomposable_nodes: u32,
+}
+
+// Whether no task of the network is known to be undecomposable, tasks
+// missing from "decomposable" are assumed to be decomposable
+fn is_decomposable(decomposable: &[bool], tn: &HTN) -> bool {
+    tn.mappings.values().all(|x| decomposable.get(*x as usize) != Some(&false))
 }
 
 impl SearchGraph  {
     pub fn new(problem: &FONDProblem) -> SearchGraph {
         let initial_tn = problem.init_tn.clone();
         // relaxed domain
-        let (outcome_det, bijection) = OutcomeDeterminizer::from_fond_problem(&problem);
-        let relaxed = RelaxedComposition::new(&outcome_det);
+        let (relaxed, bijection) = RelaxedComposition::from_fond_problem(problem);
         // initial node
-        let compute_node = SearchGraphNode {
+        let mut compute_node = SearchGraphNode {
             parents: None,
             tn: Rc::new(initial_tn),
             state: Rc::new(problem.initial_state.clone()),
@@ -38,12 +66,100 @@ impl SearchGraph  {
             status: NodeStatus::OnGoing,
             depth: 0,
         };
+        let decomposable = problem.decomposable_tasks();
+        let mut undecomposable_nodes = 0;
+        if !is_decomposable(&decomposable, &compute_node.tn) {
+            compute_node.status = NodeStatus::Failed;
+            compute_node.cost = f32::INFINITY;
+            undecomposable_nodes += 1;
+        }
         // search graph
         SearchGraph {
             ids: HashMap::from([(1, RefCell::new(compute_node))]),
             root: 1,
             cursor: 2,
             relaxed_domain: Some((relaxed, bijection)),
+            landmarks: None,
+            h_cache: HeuristicCache::new(DEFAULT_CACHE_SIZE),
+            preferred_operators: false,
+            novelty: None,
+            free_decompositions: false,
+            decomposable,
+            undecomposable_nodes,
+        }
+    }
+
+    // Starts tracking the novelty of new nodes
+    pub fn init_novelty(&mut self, width: usize) {
+        let mut table = NoveltyTable::new(width);
+        let root = self.ids.get(&self.root).unwrap().borrow();
+        let novelty = table.novelty(&NoveltyTable::atoms(&root.tn, &root.state));
+        drop(root);
+        self.novelty = Some((table, HashMap::from([(self.root, novelty)])));
+    }
+
+    // Extracts the landmarks of the root, num_facts is the number of facts
+    // of the (non-relaxed) problem
+    pub fn init_landmarks(&mut self, num_facts: u32) {
+        let (encoder, bijection) = self.relaxed_domain.as_ref().unwrap();
+        let root = self.ids.get(&self.root).unwrap().borrow();
+        let tasks: Vec<u32> = root.tn.count_tasks_with_frequency().keys()
+            .map(|x| *bijection.get(x).unwrap())
+            .collect();
+        let state = encoder.compute_relaxed_state(&tasks, &root.state);
+        let goal = encoder.compute_goal_state(&tasks);
+        let landmarks = Landmarks::new(&encoder.domain, encoder.tdg(), &tasks, &state, &goal, num_facts);
+        let accepted = landmarks.accept(&HashSet::new(), &root.state);
+        drop(root);
+        self.landmarks = Some((landmarks, HashMap::from([(self.root, accepted)])));
+    }
+
+    fn h_val(&mut self, id: u32, tn: &HTN, state: &HashSet<u32>, heuristic: &mut dyn Heuristic) -> f32 {
+        let (encoder, bijection) = match &self.relaxed_domain {
+            Some((encoder, bijection)) => (encoder, bijection),
+            None => return 0.0,
+        };
+        let context = HeuristicContext {
+            encoder,
+            bijection,
+            landmarks: self.landmarks.as_ref().map(|(x, accepted)| (x, accepted.get(&id).unwrap())),
+            free_decompositions: self.free_decompositions,
+        };
+        if !heuristic.is_cacheable() {
+            return heuristic.h_val(tn, state, &context);
+        }
+        let key = HeuristicCache::key(tn, state);
+        match self.h_cache.get(&key) {
+            Some(h) => h,
+            None => {
+                let h = heuristic.h_val(tn, state, &context);
+                self.h_cache.insert(key, h);
+                h
+            }
+        }
+    }
+
+    // Tasks (of the relaxed composition) applied by the actions and methods
+    // of the h_ff relaxed plan of a node
+    fn helpful_operators(&self, id: u32) -> HashSet<AppliedTask> {
+        let (encoder, bijection) = match &self.relaxed_domain {
+            Some(x) => x,
+            None => return HashSet::new(),
+        };
+        let node = self.ids.get(&id).unwrap().borrow();
+        let tasks: Vec<u32> = node.tn.count_tasks_with_frequency().keys()
+            .map(|x| *bijection.get(x).unwrap())
+            .collect();
+        let state = encoder.compute_relaxed_state(&tasks, &node.state);
+        let goal = encoder.compute_goal_state(&tasks);
+        match ff_relaxed_plan(&encoder.domain, &state, &goal) {
+            Some(plan) => plan.into_iter().map(|a| match (encoder.origin(a), encoder.applied_task(a)) {
+                // choosing an outcome stands for executing the nondeterministic
+                // action
+                (ActionOrigin::Outcome, (task, _)) => (task, None),
+                (_, applied) => applied,
+            }).collect(),
+            None => HashSet::new(),
         }
     }
 
@@ -59,7 +175,7 @@ impl SearchGraph  {
         let root = self.ids.get(&self.root).unwrap().borrow();
         match root.status {
             NodeStatus::Solved => SearchResult::Success(StrongPolicy::new(facts, self)),
-            NodeStatus::Failed => SearchResult::NoSolution,
+            NodeStatus::Failed => SearchResult::NoSolution(self.failure_report(facts)),
             NodeStatus::OnGoing => panic!("computation not terminated"),
         }
     }
@@ -143,27 +259,95 @@ impl SearchGraph  {
         return candidate
     }
 
+    pub fn select_tip_node(&self, strategy: &TipSelection) -> u32 {
+        let key = |id: &u32| {
+            let node = self.ids.get(id).unwrap().borrow();
+            (node.depth, node.cost)
+        };
+        let tips = self.tip_nodes();
+        let selected = match strategy {
+            TipSelection::FirstFound => None,
+            TipSelection::Deepest => tips.iter().max_by_key(|x| key(x).0),
+            TipSelection::Shallowest => tips.iter().min_by_key(|x| key(x).0),
+            TipSelection::MinCost => tips.iter().min_by(|x, y| key(x).1.total_cmp(&key(y).1)),
+            TipSelection::MaxCost => tips.iter().max_by(|x, y| key(x).1.total_cmp(&key(y).1)),
+            TipSelection::Novelty => {
+                let novelty = |id: &u32| match &self.novelty {
+                    Some((_, values)) => values.get(id).cloned().unwrap_or(usize::MAX),
+                    None => usize::MAX,
+                };
+                tips.iter().min_by(|x, y| key(x).1.total_cmp(&key(y).1).then(novelty(x).cmp(&novelty(y))))
+            },
+        };
+        match selected {
+            Some(x) => *x,
+            None => self.find_a_tip_node(),
+        }
+    }
+
+    // Unexpanded non-terminal nodes of the marked partial solution
+    fn tip_nodes(&self) -> BTreeSet<u32> {
+        let mut tips = BTreeSet::new();
+        let mut visited = HashSet::from([self.root]);
+        let mut working_set = LinkedList::from([self.root]);
+        while let Some(x) = working_set.pop_front() {
+            let node = self.ids.get(&x).unwrap().borrow();
+            if node.is_terminal() {
+                continue;
+            }
+            match &node.connections {
+                Some(_) => {
+                    if let Some(marked) = node.get_marked_connection() {
+                        for child in marked.children.iter() {
+                            if visited.insert(*child) {
+                                working_set.push_back(*child);
+                            }
+                        }
+                    }
+                },
+                None => {
+                    tips.insert(x);
+                }
+            }
+        }
+        tips
+    }
+
     // TODO: better interface, decouple heuristic from graph
-    pub fn expand(&mut self, id: u32, h_type: &HeuristicType, skip_heuristic: bool) {
+    pub fn expand(&mut self, id: u32, heuristic: &mut dyn Heuristic, weight: f32, skip_heuristic: bool) {
         // if node's successor's has already been found, skip
         if let Some(_) = self.ids.get(&id).unwrap().borrow().connections {
             return;
         }
         // compute successors
         let node = self.ids.get(&id).unwrap().borrow();
-        let node_successors = progress(node.tn.clone(), node.state.clone());
+        let mut node_successors = progress(node.tn.clone(), node.state.clone());
         drop(node);
+        if let (true, Some((_, bijection))) = (self.preferred_operators, &self.relaxed_domain) {
+            let helpful = self.helpful_operators(id);
+            // ties in the connector costs are broken by the order
+            node_successors.sort_by_key(|x| {
+                let (task, method) = x.applied_task;
+                !helpful.contains(&(bijection[&task], method))
+            });
+        }
         let depth = self.ids.get(&id).unwrap().borrow().depth.clone();
         // Case where node is terminal, terminate expansion
         if node_successors.len() == 0 {
             self.mark_as_terminal(id);
             return;
         }
+        heuristic.next_expansion();
         let mut connectors = vec![];
         for expansion in node_successors.into_iter() {
+            let cost = match &expansion.connection_label {
+                ConnectionLabel::Decomposition(_, _, Some(cost)) => *cost as f32,
+                ConnectionLabel::Decomposition(_, _, None) if self.free_decompositions => 0.0,
+                _ => 1.0,
+            };
             let mut hyperarc = Connector {
                 children: HashSet::new(),
-                cost: 1.0,
+                cost,
                 is_marked: false,
                 action_type: expansion.connection_label
             };
@@ -171,21 +355,27 @@ impl SearchGraph  {
                 let visited_before = self.visited(expansion.tn.as_ref(), state.as_ref());
                 match visited_before {
                     Some(x) => {
-                        self.ids.get(&x).unwrap().borrow_mut().add_parent(x);
+                        self.ids.get(&x).unwrap().borrow_mut().add_parent(id);
                         hyperarc.children.insert(x);
                     },
                     None => {
+                        if let Some((landmarks, accepted)) = &mut self.landmarks {
+                            let parent_accepted = accepted.get(&id).cloned().unwrap_or_default();
+                            accepted.insert(self.cursor, landmarks.accept(&parent_accepted, state.as_ref()));
+                        }
+                        if let Some((table, values)) = &mut self.novelty {
+                            values.insert(self.cursor, table.novelty(&NoveltyTable::atoms(&expansion.tn, state)));
+                        }
                         let mut node_label = NodeStatus::OnGoing;
                         let mut h = 0.0;
                         if expansion.tn.is_goal() {
                             node_label = NodeStatus::Solved;
+                        } else if !is_decomposable(&self.decomposable, &expansion.tn) {
+                            node_label = NodeStatus::Failed;
+                            h = f32::INFINITY;
+                            self.undecomposable_nodes += 1;
                         } else if !skip_heuristic {
-                            match &self.relaxed_domain {
-                                Some((encoder, bijection)) => {
-                                    h = SearchGraphNode::h_val(expansion.tn.as_ref(), state.as_ref(), encoder, bijection, &h_type)
-                                },
-                                None => {}
-                            }
+                            h = weight * self.h_val(self.cursor, expansion.tn.as_ref(), state.as_ref(), heuristic);
                             if h == f32::INFINITY {
                                 node_label = NodeStatus::Failed;
                             }
@@ -237,7 +427,7 @@ mod tests {
                 Connector { children: HashSet::from([3, 4]), cost: 1.0, is_marked: true,
                     action_type: ConnectionLabel::Execution("p2".to_string(), 2)},
                 Connector { children: HashSet::from([5]), cost: 0.0, is_marked: false,
-                    action_type: ConnectionLabel::Decomposition("t1".to_string(), "m1".to_string())},
+                    action_type: ConnectionLabel::Decomposition("t1".to_string(), "m1".to_string(), None)},
             ]}),
             cost: 2.0,
             status: NodeStatus::OnGoing,
@@ -258,7 +448,7 @@ mod tests {
             state: Rc::new(HashSet::new()),
             connections: Some(NodeConnections { children: vec![
                 Connector { children: HashSet::from([6]), cost: 1.0, is_marked: true,
-                    action_type: ConnectionLabel::Decomposition("t1".to_string(), "m3".to_string())}
+                    action_type: ConnectionLabel::Decomposition("t1".to_string(), "m3".to_string(), None)}
             ]}),
             cost: 2.0,
             status: NodeStatus::OnGoing,
@@ -324,7 +514,14 @@ mod tests {
             ]),
             root: 1,
             cursor: 9,
-            relaxed_domain: None
+            relaxed_domain: None,
+            landmarks: None,
+            h_cache: HeuristicCache::new(0),
+            preferred_operators: false,
+            novelty: None,
+            free_decompositions: false,
+            decomposable: vec![],
+            undecomposable_nodes: 0,
         }
     }
 
@@ -336,11 +533,21 @@ mod tests {
         assert_eq!([4, 6].contains(&tip_node), true);
     }
 
+    #[test]
+    pub fn tip_selection_test() {
+        let tree = generate_tree();
+        tree.ids.get(&4).unwrap().borrow_mut().status = NodeStatus::OnGoing;
+        assert_eq!(tree.select_tip_node(&TipSelection::Deepest), 6);
+        assert_eq!(tree.select_tip_node(&TipSelection::Shallowest), 4);
+        assert_eq!(tree.select_tip_node(&TipSelection::MinCost), 4);
+        assert_eq!(tree.select_tip_node(&TipSelection::MaxCost), 6);
+    }
+
     // TODO: Sometimes panic by attempting to add parent to root
     #[test]
     pub fn expansion_test() {
         let mut tree = generate_tree();
-        tree.expand(6, &HeuristicType::HFF, false);
+        tree.expand(6, &mut HeuristicType::HFF, 1.0, false);
         assert_eq!(tree.ids.contains_key(&9), true);
         assert_eq!(tree.ids.len(), 9);
         let n = tree.ids.get(&6).unwrap().borrow();
@@ -359,6 +566,17 @@ mod tests {
         }
     }
 
+    // a node reached again records every node it was reached from, so that
+    // cost revisions reach all of them
+    #[test]
+    pub fn add_parent_test() {
+        let tree = generate_tree();
+        let mut node = tree.ids.get(&6).unwrap().borrow_mut();
+        node.add_parent(5);
+        node.add_parent(5);
+        assert_eq!(node.parents, Some(vec![3, 5]));
+    }
+
     #[test]
     pub fn cycle_detection_test() {
         let t1 = Task::Compound(CompoundTask::new("t1".to_string(), vec![]));
@@ -384,7 +602,14 @@ mod tests {
             ids: HashMap::from([(1, RefCell::new(n1))]),
             root: 1,
             cursor: 2,
-            relaxed_domain: None
+            relaxed_domain: None,
+            landmarks: None,
+            h_cache: HeuristicCache::new(0),
+            preferred_operators: false,
+            novelty: None,
+            free_decompositions: false,
+            decomposable: vec![],
+            undecomposable_nodes: 0,
         };
         let visited = graph.visited(&
             HTN::new(
@@ -1,9 +1,9 @@
-use std::{collections::{HashSet, HashMap}, rc::Rc};
+use std::{collections::HashSet, rc::Rc};
 
 use super::*;
-use super::{HTN, PrimitiveAction, Task, CompoundTask, h_type, HeuristicType};
+use super::{HTN, PrimitiveAction, Task, CompoundTask, h_type};
 
-use crate::{task_network::Applicability, relaxation::RelaxedComposition, heuristics::*};
+use crate::task_network::Applicability;
 
 #[derive(Debug)]
 pub struct SearchGraphNode {
@@ -58,10 +58,11 @@ impl SearchGraphNode {
     }
 
     pub fn add_parent(&mut self, id: u32) {
-        self.parents = match &self.parents {
+        match &mut self.parents {
             Some(parents) => {
-                let p = parents.clone();
-                Some(p)
+                if !parents.contains(&id) {
+                    parents.push(id);
+                }
             },
             None => {
                 panic!("attempting to add parent to root");
@@ -76,29 +77,4 @@ impl SearchGraphNode {
     pub fn is_goal(&self) -> bool {
         self.tn.is_empty()
     }
-
-    pub fn h_val(tn: &HTN, state: &HashSet<u32>, encoder: &RelaxedComposition, bijection: &HashMap<u32, u32>, h_type: &HeuristicType) -> f32 {
-        let occurances = tn.count_tasks_with_frequency();
-        let task_ids = occurances.iter().map(|(task, _)| {
-            *bijection.get(task).unwrap()
-        }).collect();
-        let relaxed_state = encoder.compute_relaxed_state(
-            &task_ids,
-            state
-        );
-        let goal_state = encoder.compute_goal_state(&task_ids);
-        let mut val = match h_type {
-            HeuristicType::HFF => h_ff(&encoder.domain, &relaxed_state, &goal_state),
-            HeuristicType::HAdd => h_add(&encoder.domain, &relaxed_state, &goal_state),
-            HeuristicType::HMax => h_max(&encoder.domain, &relaxed_state, &goal_state),
-        };
-        
-        // Compensate for the repetition of tasks
-        for (_, count) in occurances {
-            if count > 1 {
-                val += (count - 1) as f32
-            }
-        }
-        val
-    }
 }
\ No newline at end of file
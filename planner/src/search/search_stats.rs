@@ -1,10 +1,19 @@
 use std::time::Duration;
 
+use crate::heuristics::LandmarkStats;
+
 pub struct SearchStats {
     pub max_depth: u16,
     pub search_nodes: u32,
     pub explored_nodes: u32,
     pub seach_time: Duration,
+    // landmarks of the initial node (landmark-count heuristic only)
+    pub landmarks: Option<LandmarkStats>,
+    // lookups in the heuristic cache
+    pub cache_hits: u32,
+    pub cache_misses: u32,
+    // nodes failed right away because of a task that can not be decomposed
+    pub undecomposable_nodes: u32,
 }
 
 impl std::fmt::Display for SearchStats {
@@ -12,6 +21,14 @@ impl std::fmt::Display for SearchStats {
         writeln!(f, "max depth: {}", self.max_depth);
         writeln!(f, "# of search nodes: {}", self.search_nodes);
         writeln!(f, "# of explored nodes: {}", self.explored_nodes);
+        writeln!(f, "heuristic cache hits: {}", self.cache_hits);
+        writeln!(f, "heuristic cache misses: {}", self.cache_misses);
+        writeln!(f, "# of undecomposable nodes: {}", self.undecomposable_nodes);
+        if let Some(landmarks) = &self.landmarks {
+            writeln!(f, "# of fact landmarks: {}", landmarks.facts);
+            writeln!(f, "# of task landmarks: {}", landmarks.tasks);
+            writeln!(f, "# of method landmarks: {}", landmarks.methods);
+        }
         let time = self.seach_time.as_secs_f64();
         writeln!(f, "search duration: {}", time.trunc())
     }
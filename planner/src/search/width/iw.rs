@@ -0,0 +1,86 @@
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+
+use super::*;
+use crate::domain_description::FONDProblem;
+
+// Iterated width over the progression space. Only expansions with a single
+// outcome are followed, so it solves the deterministic fragment of a problem
+// and a failure does not prove unsolvability.
+pub struct IWSearch {
+
+}
+
+#[derive(Debug)]
+pub struct IWResult {
+    // labels of the connectors from the initial node to a goal node
+    pub plan: Option<Vec<String>>,
+    // width of the last iteration
+    pub width: usize,
+    // number of generated nodes in all iterations
+    pub generated: u32,
+}
+
+// Task network, state and (parent, label of the connector from the parent)
+type IWNode = (Rc<HTN>, Rc<HashSet<u32>>, Option<(usize, String)>);
+
+impl IWSearch {
+    // Runs IW(1), IW(2), ... until a plan is found or max_width is exceeded
+    pub fn run(problem: &FONDProblem, max_width: usize) -> IWResult {
+        let mut generated = 0;
+        for width in 1..=max_width {
+            let (plan, count) = IWSearch::iw(problem, width);
+            generated += count;
+            if plan.is_some() {
+                return IWResult { plan, width, generated };
+            }
+        }
+        IWResult { plan: None, width: max_width, generated }
+    }
+
+    // Breadth-first search that prunes nodes whose novelty exceeds the width
+    fn iw(problem: &FONDProblem, width: usize) -> (Option<Vec<String>>, u32) {
+        let tn = Rc::new(problem.init_tn.clone());
+        let state = Rc::new(problem.initial_state.clone());
+        if tn.is_goal() {
+            return (Some(vec![]), 1);
+        }
+        let mut table = NoveltyTable::new(width);
+        table.novelty(&NoveltyTable::atoms(&tn, &state));
+        let mut nodes: Vec<IWNode> = vec![(tn, state, None)];
+        let mut queue = VecDeque::from([0]);
+        let mut generated = 1;
+        while let Some(id) = queue.pop_front() {
+            let (tn, state, _) = &nodes[id];
+            for expansion in progress(tn.clone(), state.clone()) {
+                if expansion.states.len() != 1 {
+                    continue;
+                }
+                generated += 1;
+                let state = expansion.states[0].clone();
+                let label = expansion.connection_label.get_label();
+                if expansion.tn.is_goal() {
+                    nodes.push((expansion.tn, state, Some((id, label))));
+                    return (Some(IWSearch::extract_plan(&nodes, nodes.len() - 1)), generated);
+                }
+                if table.novelty(&NoveltyTable::atoms(&expansion.tn, &state)) > width {
+                    continue;
+                }
+                nodes.push((expansion.tn, state, Some((id, label))));
+                queue.push_back(nodes.len() - 1);
+            }
+        }
+        (None, generated)
+    }
+
+    fn extract_plan(nodes: &[IWNode], goal: usize) -> Vec<String> {
+        let mut plan = vec![];
+        let mut cursor = goal;
+        while let Some((parent, label)) = &nodes[cursor].2 {
+            plan.push(label.clone());
+            cursor = *parent;
+        }
+        plan.reverse();
+        plan
+    }
+}
@@ -0,0 +1,7 @@
+mod novelty;
+mod iw;
+
+use super::*;
+
+pub use novelty::{Atom, NoveltyTable};
+pub use iw::{IWSearch, IWResult};
@@ -0,0 +1,90 @@
+use std::collections::HashSet;
+
+use super::HTN;
+
+// Occurrences of a task beyond this are not distinguished, which keeps the
+// number of atoms (and so of novel nodes) finite in recursive domains
+pub const MAX_OCCURRENCES: u32 = 4;
+
+// State facts and tasks present in the network. A task comes with its
+// number of occurrences, so that recursive decompositions which only repeat
+// tasks can be novel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Atom {
+    Fact(u32),
+    Task(u32, u32),
+}
+
+// Tuples of atoms (of at most "width" atoms) seen so far
+#[derive(Debug)]
+pub struct NoveltyTable {
+    width: usize,
+    seen: HashSet<Vec<Atom>>,
+}
+
+impl NoveltyTable {
+    pub fn new(width: usize) -> NoveltyTable {
+        NoveltyTable { width, seen: HashSet::new() }
+    }
+
+    pub fn atoms(tn: &HTN, state: &HashSet<u32>) -> Vec<Atom> {
+        let mut atoms: Vec<Atom> = state.iter().map(|x| Atom::Fact(*x))
+            .chain(tn.count_tasks_with_frequency().into_iter().map(|(task, count)| Atom::Task(task, count.min(MAX_OCCURRENCES))))
+            .collect();
+        atoms.sort();
+        atoms
+    }
+
+    // Size of the smallest tuple of the atoms that has not been seen before
+    // (width + 1 if there is none). All tuples are recorded as seen.
+    pub fn novelty(&mut self, atoms: &[Atom]) -> usize {
+        let mut novelty = self.width + 1;
+        for size in (1..=self.width).rev() {
+            let mut tuple = vec![];
+            if self.record(atoms, size, &mut tuple) {
+                novelty = size;
+            }
+        }
+        novelty
+    }
+
+    // records the tuples of the given size extending "tuple", returns true
+    // if one of them is new
+    fn record(&mut self, atoms: &[Atom], size: usize, tuple: &mut Vec<Atom>) -> bool {
+        if tuple.len() == size {
+            return self.seen.insert(tuple.clone());
+        }
+        let mut is_new = false;
+        for (i, atom) in atoms.iter().enumerate() {
+            tuple.push(*atom);
+            is_new |= self.record(&atoms[i + 1..], size, tuple);
+            tuple.pop();
+        }
+        is_new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn novelty_test() {
+        let mut table = NoveltyTable::new(2);
+        let atoms = |facts: Vec<u32>, tasks: Vec<u32>| -> Vec<Atom> {
+            facts.into_iter().map(Atom::Fact).chain(tasks.into_iter().map(|x| Atom::Task(x, 1))).collect()
+        };
+        assert_eq!(table.novelty(&atoms(vec![0, 1], vec![0])), 1);
+        // task 0 and fact 0 are different atoms
+        assert_eq!(table.novelty(&atoms(vec![], vec![1])), 1);
+        assert_eq!(table.novelty(&atoms(vec![0], vec![1])), 2);
+        assert_eq!(table.novelty(&atoms(vec![0, 1], vec![1])), 2);
+        // only the pair of tasks is new
+        assert_eq!(table.novelty(&atoms(vec![0, 1], vec![0, 1])), 2);
+        assert_eq!(table.novelty(&atoms(vec![0, 1], vec![0])), 3);
+        assert_eq!(table.novelty(&atoms(vec![], vec![])), 3);
+        let mut table = NoveltyTable::new(1);
+        assert_eq!(table.novelty(&atoms(vec![0, 1], vec![])), 1);
+        assert_eq!(table.novelty(&atoms(vec![1, 0], vec![])), 2);
+    }
+}
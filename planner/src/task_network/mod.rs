@@ -1,9 +1,11 @@
 mod applicability;
 mod network;
+mod sequence;
 mod task_structs;
 
 
 pub use network::HTN;
+pub use sequence::TaskSequence;
 pub use task_structs::{CompoundTask, Task, Method, PrimitiveAction};
 pub use applicability::Applicability;
-use crate::graph_lib::{Graph, vf2_isomorphism};
\ No newline at end of file
+use crate::graph_lib::{Graph, PersistentMap, vf2_isomorphism};
\ No newline at end of file
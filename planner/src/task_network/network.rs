@@ -1,8 +1,10 @@
 use crate::domain_description::DomainTasks;
 
-use super::Graph;
+use super::{Graph, PersistentMap};
+use super::TaskSequence;
 use super::vf2_isomorphism;
 use super::task_structs::{CompoundTask, Method, PrimitiveAction, Task};
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet, BTreeSet};
 use std::fmt::{self, write};
 use rand::distributions::DistString;
@@ -13,10 +15,57 @@ use std::cell::RefCell;
 
 #[derive(Debug, Clone)]
 pub struct HTN {
-    network: Graph,
+    network: Network,
     pub domain: Rc<DomainTasks>, // TODO: Convert to Weak
-    // A mapping from task id in the network to its ID in the domain
-    pub mappings: HashMap<u32, u32>,
+    // A mapping from task id in the network to its ID in the domain, shared
+    // between a network and its successors
+    pub mappings: PersistentMap<u32>,
+}
+
+// A partially ordered graph followed by a sequence, every leaf of the graph
+// preceding the first node of the sequence (these edges are not stored).
+// The graph is empty whenever the orderings are total (including networks
+// with at most one task), so decomposing the first task of a sequence only
+// builds a graph for the subtasks and shares the rest of the sequence.
+#[derive(Debug, Clone)]
+struct Network {
+    graph: Graph,
+    sequence: TaskSequence,
+}
+
+impl Network {
+    // Moves the nodes of the graph in front of the sequence if they are
+    // totally ordered. Graphs are kept transitively reduced, so checking that
+    // they form a chain rejects the partial orders in constant time.
+    fn new(graph: Graph, sequence: TaskSequence) -> Network {
+        match graph.is_chain().then(|| graph.total_order()).flatten() {
+            Some(order) => Network { graph: Graph::default(), sequence: sequence.prepend(&order) },
+            None => Network { graph, sequence },
+        }
+    }
+
+    fn is_totally_ordered(&self) -> bool {
+        self.graph.count_nodes() == 0
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = u32> + '_ {
+        self.graph.nodes().cloned().chain(self.sequence.iter())
+    }
+
+    fn sequence_orderings(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.sequence.iter().zip(self.sequence.iter().skip(1))
+    }
+
+    // The orderings as a single graph, only built to compare networks that
+    // are not totally ordered or to change a node inside the sequence
+    fn to_graph(&self) -> Cow<'_, Graph> {
+        if self.sequence.is_empty() {
+            return Cow::Borrowed(&self.graph);
+        }
+        let sequence = Graph::new(self.sequence.iter().collect(), self.sequence_orderings().collect());
+        let leaves = self.graph.get_leaf_nodes().into_iter().collect();
+        Cow::Owned(self.graph.add_subgraph(sequence, leaves, BTreeSet::new()))
+    }
 }
 
 impl HTN {
@@ -26,11 +75,19 @@ impl HTN {
         domain: Rc<DomainTasks>,
         mappings: HashMap<u32, u32>,
     ) -> HTN {
-        HTN {
-            network: Graph::new(tasks, orderings),
-            mappings,
-            domain: domain
-        }
+        // implied orderings are dropped, so that networks with the same partial
+        // order have the same edges (decompositions keep them reduced)
+        let graph = Graph::new(tasks, orderings);
+        let graph = graph.transitive_reduction().unwrap_or(graph);
+        HTN::from_graph(graph, domain, mappings.into_iter().collect())
+    }
+
+    fn from_graph(graph: Graph, domain: Rc<DomainTasks>, mappings: PersistentMap<u32>) -> HTN {
+        HTN { network: Network::new(graph, TaskSequence::default()), domain, mappings }
+    }
+
+    pub fn is_totally_ordered(&self) -> bool {
+        self.network.is_totally_ordered()
     }
 
     pub fn is_goal(&self) -> bool {
@@ -42,12 +99,17 @@ impl HTN {
     }
 
     pub fn get_orderings(&self) -> Vec<(u32,u32)>{
-        self.network.get_edges()
+        let mut orderings = self.network.graph.get_edges();
+        if let Some(first) = self.network.sequence.first() {
+            orderings.extend(self.network.graph.get_leaf_nodes().into_iter().map(|x| (x, first)));
+        }
+        orderings.extend(self.network.sequence_orderings());
+        orderings
     }
 
     pub fn get_all_tasks(&self) -> Vec<&RefCell<Task>> {
-        self.network.nodes.iter().map(|id| {
-            self.get_task(*id)
+        self.get_nodes().map(|id| {
+            self.get_task(id)
         }).collect()
     }
 
@@ -69,17 +131,17 @@ impl HTN {
     }
 
     pub fn get_all_tasks_with_ids(&self) -> Vec<(&RefCell<Task>, u32)> {
-        self.network.nodes.iter().map(|id| {
-            (self.get_task(*id), *id)
+        self.get_nodes().map(|id| {
+            (self.get_task(id), id)
         }).collect()
     }
 
     pub fn count_tasks(&self) -> usize {
-        self.network.count_nodes()
+        self.network.graph.count_nodes() + self.network.sequence.len()
     }
 
     pub fn is_empty(&self) -> bool {
-        self.network.count_nodes() == 0
+        self.count_tasks() == 0
     }
 
     pub fn get_task(&self, node_id: u32) -> &std::cell::RefCell<Task> {
@@ -90,11 +152,24 @@ impl HTN {
     }
 
     pub fn get_unconstrained_tasks(&self) -> BTreeSet<u32> {
-        self.network.get_unconstrained_nodes()
+        if self.network.is_totally_ordered() {
+            self.network.sequence.first().into_iter().collect()
+        } else {
+            self.network.graph.get_unconstrained_nodes()
+        }
     }
 
     pub fn get_incoming_edges(&self, id: u32) -> BTreeSet<u32> {
-        self.network.get_incoming_edges(id)
+        if self.network.graph.contains(id) {
+            self.network.graph.get_incoming_edges(id)
+        } else if self.network.sequence.first() == Some(id) {
+            self.network.graph.get_leaf_nodes().into_iter().collect()
+        } else {
+            self.network.sequence_orderings()
+                .filter(|(_, next)| *next == id)
+                .map(|(previous, _)| previous)
+                .collect()
+        }
     }
 
     pub fn decompose(&self, id: u32, method: &Method) -> HTN {
@@ -105,37 +180,41 @@ impl HTN {
             _ => {}
         }
         // Changing IDs
-        let network_max_id = self.network.nodes.iter().max().unwrap();
-        let subgraph_max_id = method.decomposition.network.nodes.iter().max().unwrap();
-        let max_id = *network_max_id.max(subgraph_max_id) + 1;
+        let network_max_id = self.mappings.max_key().unwrap();
+        let subgraph_max_id = method.decomposition.mappings.max_key().unwrap_or(0);
+        let max_id = network_max_id.max(subgraph_max_id) + 1;
         let relabeled_subgraph = HTN::relabel_nodes(&method.decomposition, max_id);
-        // Creating Graph
-        let mut new_graph = self.network.clone();
-        let outgoing_edges = self.network.get_outgoing_edges(id);
-        let incoming_edges = self.network.get_incoming_edges(id);
-        new_graph = new_graph.remove_node(id);
-        new_graph = new_graph.add_subgraph(
-            Graph::new(
-                relabeled_subgraph.get_nodes().clone(),
-                relabeled_subgraph.network.get_edges(),
-            ),
-            incoming_edges,
-            outgoing_edges,
-        );
         let mut new_mappings = self.mappings.clone();
         new_mappings.remove(&id);
         for (id, m) in relabeled_subgraph.mappings.iter() {
             new_mappings.insert(*id,*m);
         }
-        let new_nodes = self.network.nodes
-                                                    .iter()
-                                                    .filter(|x| **x != id)
-                                                    .cloned()
-                                                    .collect::<BTreeSet<u32>>()
-                                                    .union(&relabeled_subgraph.get_nodes())
-                                                    .cloned()
-                                                    .collect();
-        HTN::new(new_nodes, new_graph.get_edges(), self.domain.clone(), new_mappings )
+        let subtasks = relabeled_subgraph.network;
+        // the subtasks of the first task of a sequence take its place, the
+        // rest of the sequence following them
+        if self.network.is_totally_ordered() && self.network.sequence.first() == Some(id) {
+            let sequence: Vec<u32> = subtasks.sequence.iter().collect();
+            let sequence = self.network.sequence.rest().prepend(&sequence);
+            return HTN {
+                network: Network { graph: subtasks.graph, sequence },
+                domain: self.domain.clone(),
+                mappings: new_mappings,
+            };
+        }
+        // Creating Graph, which only includes the sequence if the task is in it
+        let (graph, sequence) = if self.network.graph.contains(id) {
+            (Cow::Borrowed(&self.network.graph), self.network.sequence.clone())
+        } else {
+            (self.network.to_graph(), TaskSequence::default())
+        };
+        let outgoing_edges = graph.get_outgoing_edges(id);
+        let incoming_edges = graph.get_incoming_edges(id);
+        let new_graph = graph.remove_node(id).add_subgraph(
+            subtasks.to_graph().into_owned(),
+            incoming_edges,
+            outgoing_edges,
+        );
+        HTN { network: Network::new(new_graph, sequence), domain: self.domain.clone(), mappings: new_mappings }
     }
 
     pub fn change_mappings(&mut self, changes: Vec<(u32, u32)>) {
@@ -150,10 +229,13 @@ impl HTN {
     }
 
     pub fn relabel_nodes(tn: &HTN, start_index: u32) -> HTN {
-        let new_ids: HashMap<u32, u32> = tn.network.nodes.iter().cloned().zip(start_index..).collect();
-        let mut subgraph = tn.network.clone();
-        subgraph.change_ids(&new_ids);
-        let new_mappings: HashMap<u32, u32> = tn.mappings.iter().map(|(k, v)| {
+        let new_ids: HashMap<u32, u32> = tn.get_nodes().zip(start_index..).collect();
+        let mut graph = tn.network.graph.clone();
+        let graph_ids = new_ids.iter().filter(|(x, _)| graph.contains(**x)).map(|(x, y)| (*x, *y)).collect();
+        graph.change_ids(&graph_ids);
+        let sequence: Vec<u32> = tn.network.sequence.iter().map(|x| new_ids[&x]).collect();
+        let network = Network { graph, sequence: TaskSequence::new(&sequence) };
+        let new_mappings: PersistentMap<u32> = tn.mappings.iter().map(|(k, v)| {
             match new_ids.get(k) {
                 Some(new_id) => {
                     (*new_id, *v)
@@ -163,7 +245,7 @@ impl HTN {
                 }
             }
         }).collect();
-        HTN { network: subgraph, domain: tn.domain.clone(), mappings: new_mappings }
+        HTN { network, domain: tn.domain.clone(), mappings: new_mappings }
     }
 
     pub fn get_all_task_mappings(&self) -> Vec<u32>{
@@ -178,13 +260,19 @@ impl HTN {
         }
         let mut new_mapping = self.mappings.clone();
         new_mapping.remove(&id);
-        let new_graph = self.network.remove_node(id);
-        HTN { network: new_graph, mappings: new_mapping, domain: self.domain.clone()}
+        let network = if self.network.is_totally_ordered() && self.network.sequence.first() == Some(id) {
+            Network { graph: Graph::default(), sequence: self.network.sequence.rest() }
+        } else if self.network.graph.contains(id) {
+            Network::new(self.network.graph.remove_node(id), self.network.sequence.clone())
+        } else {
+            Network::new(self.network.to_graph().remove_node(id), TaskSequence::default())
+        };
+        HTN { network, mappings: new_mapping, domain: self.domain.clone() }
     }
 
     fn is_approximately_isomorphic(tn1: &HTN, tn2: &HTN) -> bool {
-        let layers_1 = tn1.network.to_layers();
-        let layers_2 = tn2.network.to_layers();
+        let layers_1 = tn1.network.to_graph().to_layers();
+        let layers_2 = tn2.network.to_graph().to_layers();
         if layers_1.len() != layers_2.len() {
             return false;
         }
@@ -199,6 +287,15 @@ impl HTN {
     }
 
     pub fn is_isomorphic(tn1: &HTN, tn2: &HTN) -> bool {
+        match (tn1.is_totally_ordered(), tn2.is_totally_ordered()) {
+            // sequences are equivalent if they have the same tasks in the same order
+            (true, true) => {
+                let (s1, s2) = (&tn1.network.sequence, &tn2.network.sequence);
+                return s1.len() == s2.len() && s1.iter().zip(s2.iter()).all(|(x, y)| tn1.mappings[&x] == tn2.mappings[&y]);
+            },
+            (false, false) => {},
+            _ => return false,
+        }
         let tasks1 = tn1.count_tasks_with_frequency();
         let tasks2 = tn2.count_tasks_with_frequency();
         for (t, count) in tasks1.iter() {
@@ -211,7 +308,7 @@ impl HTN {
             }
         }
         if HTN::is_approximately_isomorphic(tn1, tn2) {
-            vf2_isomorphism(&tn1.network, &tn2.network, &tn1.mappings, &tn2.mappings)
+            vf2_isomorphism(&tn1.network.to_graph(), &tn2.network.to_graph(), &tn1.mappings, &tn2.mappings)
         } else {
             return false
         }
@@ -253,8 +350,9 @@ impl HTN {
         (u_c, u_a)
     }
 
-    pub fn get_nodes(&self) -> &BTreeSet<u32> {
-        &self.network.nodes
+    // The nodes of the graph in increasing order, then the ones of the sequence
+    pub fn get_nodes(&self) -> impl Iterator<Item = u32> + '_ {
+        self.network.nodes()
     }
     
     pub fn contains_task(&self, name: &str) -> bool {
@@ -275,10 +373,10 @@ impl HTN {
 impl fmt::Display for HTN {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         writeln!(f, "digraph g {{");
-        for node in self.get_nodes().iter() {
-            writeln!(f, "\t{} [label={}];", *node, self.get_task(*node).borrow().get_name());
+        for node in self.get_nodes() {
+            writeln!(f, "\t{} [label={}];", node, self.get_task(node).borrow().get_name());
         }
-        for (i,j) in self.network.get_edges() {
+        for (i,j) in self.get_orderings() {
             writeln!(f, "\t{}->{};", i, j);
         }
         writeln!(f, "}}");
@@ -425,9 +523,9 @@ mod tests {
             let result = network.decompose(3, &methods[0]);
             assert_eq!(result.count_tasks(), 8);
             assert_eq!(result.get_unconstrained_tasks(), BTreeSet::from([1, 2]));
-            assert_eq!(Graph::convert_edges_to_vec(&result.network.edges).len(), 8);
+            assert_eq!(result.get_orderings().len(), 8);
             assert_eq!(result.contains_task("Construct"), false);
-            assert_eq!(result.network.edges.get(&1).unwrap().len(), 1);
+            assert_eq!(result.get_orderings().iter().filter(|(x, _)| *x == 1).count(), 1);
        }
        else {
         panic!()
@@ -474,6 +572,19 @@ mod tests {
         assert_eq!(result, false);
     }
 
+    #[test]
+    pub fn implied_orderings_test() {
+        let domain = Rc::new(create_initial_tasks());
+        let alpha = HashMap::from([(1, 0), (2, 1), (3, 2), (4, 3)]);
+        let htn1 = HTN::new(BTreeSet::from([1, 2, 3, 4]), vec![(1, 3), (2, 3), (3, 4)], domain.clone(), alpha.clone());
+        // (1, 4) and (2, 4) follow from the other orderings
+        let htn2 = HTN::new(
+            BTreeSet::from([1, 2, 3, 4]), vec![(1, 3), (2, 3), (3, 4), (1, 4), (2, 4)], domain.clone(), alpha
+        );
+        assert_eq!(htn2.get_orderings().len(), 3);
+        assert!(HTN::is_isomorphic(&htn1, &htn2));
+    }
+
     #[test]
     pub fn is_primitive_test() {
         let domain = Rc::new(create_initial_tasks());
@@ -512,7 +623,7 @@ mod tests {
         assert_eq!(new_htn.is_primitive(3), false);
         assert_eq!(new_htn.mappings.contains_key(&2), false);
         let new_htn_2 = new_htn.apply_action(1);
-        assert_eq!(new_htn_2.get_nodes(), &BTreeSet::from([3,4]));
+        assert_eq!(new_htn_2.get_nodes().collect::<BTreeSet<u32>>(), BTreeSet::from([3,4]));
         assert_eq!(new_htn_2.is_primitive(3), false);
         assert_eq!(new_htn_2.mappings.contains_key(&1), false);
     }
@@ -582,7 +693,7 @@ mod tests {
         let orderings: Vec<(u32, u32)> = vec![];
         let network = HTN::new(t.clone(), orderings, domain.clone(), alpha);
         let new_tn = HTN::relabel_nodes(&network, 2);
-        assert_eq!(new_tn.get_nodes().len(), 4);
+        assert_eq!(new_tn.get_nodes().count(), 4);
         assert_eq!(new_tn.get_task(2).borrow().get_name(), format!("ObtainPermit"));
         assert_eq!(new_tn.get_task(3).borrow().get_name(), format!("HireBuilder"));
         assert_eq!(new_tn.get_task(4).borrow().get_name(), format!("Construct"));
@@ -625,6 +736,85 @@ mod tests {
         assert_eq!(problem.init_tn.get_unconstrained_tasks(), BTreeSet::from([1]));
     }
 
+    #[test]
+    pub fn total_order_test() {
+        let domain = Rc::new(create_initial_tasks());
+        let alpha = HashMap::from([(1, 0), (2, 1), (3, 2), (4, 3)]);
+        // the orderings need not be transitively closed
+        let htn = HTN::new(BTreeSet::from([1, 2, 3, 4]), vec![(2, 1), (1, 3), (3, 4)], domain.clone(), alpha.clone());
+        assert!(htn.is_totally_ordered());
+        assert_eq!(htn.get_unconstrained_tasks(), BTreeSet::from([2]));
+        assert_eq!(htn.get_incoming_edges(3), BTreeSet::from([1]));
+        let partial = HTN::new(BTreeSet::from([1, 2, 3, 4]), vec![(1, 3), (2, 3), (3, 4)], domain.clone(), alpha.clone());
+        assert!(!partial.is_totally_ordered());
+        // executing the first tasks of the partial order leaves a sequence
+        assert!(partial.apply_action(1).apply_action(2).is_totally_ordered());
+        // a single unconstrained task does not make the order total
+        let diamond = HTN::new(BTreeSet::from([1, 2, 3, 4]), vec![(1, 2), (1, 4), (2, 3), (4, 3)], domain.clone(), alpha.clone());
+        assert!(!diamond.is_totally_ordered());
+        assert!(!diamond.apply_action(1).is_totally_ordered());
+        assert!(diamond.apply_action(1).apply_action(2).is_totally_ordered());
+        assert!(HTN::new(BTreeSet::new(), vec![], domain.clone(), HashMap::new()).is_totally_ordered());
+    }
+
+    #[test]
+    pub fn sequence_decomposition_test() {
+        let (t1, t2, ..) = decomposition_tasks();
+        let domain = Rc::new(DomainTasks::new(vec![
+            t1, t2, Task::Compound(CompoundTask::new("Construct".to_string(), vec![]))
+        ]));
+        let method = Method::new("m".to_string(), HTN::new(
+            BTreeSet::from([1, 2]), vec![(1, 2)], domain.clone(), HashMap::from([(1, 0), (2, 1)])
+        ));
+        let htn = HTN::new(
+            BTreeSet::from([1, 2]), vec![(1, 2)], domain.clone(), HashMap::from([(1, 2), (2, 0)])
+        );
+        let result = htn.decompose(1, &method);
+        assert!(result.is_totally_ordered());
+        assert_eq!(result.get_orderings(), vec![(3, 4), (4, 2)]);
+        assert_eq!(result.get_unconstrained_tasks(), BTreeSet::from([3]));
+        // sequences with the same tasks are equivalent regardless of node ids
+        let other = HTN::new(
+            BTreeSet::from([5, 6, 7]), vec![(5, 6), (6, 7)], domain.clone(), HashMap::from([(5, 0), (6, 1), (7, 0)])
+        );
+        assert!(HTN::is_isomorphic(&result, &other));
+        let reversed = HTN::new(
+            BTreeSet::from([5, 6, 7]), vec![(5, 6), (6, 7)], domain.clone(), HashMap::from([(5, 1), (6, 0), (7, 0)])
+        );
+        assert!(!HTN::is_isomorphic(&result, &reversed));
+    }
+
+    #[test]
+    pub fn partial_prefix_test() {
+        let (t1, t2, ..) = decomposition_tasks();
+        let domain = Rc::new(DomainTasks::new(vec![
+            t1, t2, Task::Compound(CompoundTask::new("Construct".to_string(), vec![]))
+        ]));
+        let method = Method::new("m".to_string(), HTN::new(
+            BTreeSet::from([1, 2]), vec![], domain.clone(), HashMap::from([(1, 0), (2, 1)])
+        ));
+        let htn = HTN::new(
+            BTreeSet::from([1, 2]), vec![(1, 2)], domain.clone(), HashMap::from([(1, 2), (2, 0)])
+        );
+        // the unordered subtasks both precede the rest of the sequence
+        let result = htn.decompose(1, &method);
+        assert!(!result.is_totally_ordered());
+        assert_eq!(result.count_tasks(), 3);
+        assert_eq!(result.get_unconstrained_tasks(), BTreeSet::from([3, 4]));
+        assert_eq!(result.get_incoming_edges(2), BTreeSet::from([3, 4]));
+        let mut orderings = result.get_orderings();
+        orderings.sort();
+        assert_eq!(orderings, vec![(3, 2), (4, 2)]);
+        let other = HTN::new(
+            BTreeSet::from([5, 6, 7]), vec![(5, 7), (6, 7)], domain.clone(), HashMap::from([(5, 0), (6, 1), (7, 0)])
+        );
+        assert!(HTN::is_isomorphic(&result, &other));
+        let rest = result.apply_action(4);
+        assert!(rest.is_totally_ordered());
+        assert_eq!(rest.get_orderings(), vec![(3, 2)]);
+        assert_eq!(rest.apply_action(3).get_unconstrained_tasks(), BTreeSet::from([2]));
+    }
+
     #[test]
     pub fn recursive_decomposition_test() {
         let t = Task::Compound(CompoundTask { name: "recursive".to_owned(), methods: vec![] });
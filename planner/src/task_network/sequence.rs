@@ -0,0 +1,89 @@
+use std::rc::Rc;
+
+// Persistent list of the nodes of a totally ordered network. Removing the
+// first node or putting nodes in front of the list shares the rest of it.
+#[derive(Debug, Clone, Default)]
+pub struct TaskSequence {
+    head: Option<Rc<Cell>>,
+    len: usize,
+}
+
+#[derive(Debug)]
+struct Cell {
+    node: u32,
+    next: Option<Rc<Cell>>,
+}
+
+impl TaskSequence {
+    pub fn new(nodes: &[u32]) -> TaskSequence {
+        TaskSequence::default().prepend(nodes)
+    }
+
+    pub fn first(&self) -> Option<u32> {
+        self.head.as_ref().map(|cell| cell.node)
+    }
+
+    // The sequence without its first node
+    pub fn rest(&self) -> TaskSequence {
+        match &self.head {
+            Some(cell) => TaskSequence { head: cell.next.clone(), len: self.len - 1 },
+            None => TaskSequence::default(),
+        }
+    }
+
+    // "nodes" followed by this sequence
+    pub fn prepend(&self, nodes: &[u32]) -> TaskSequence {
+        let mut head = self.head.clone();
+        for node in nodes.iter().rev() {
+            head = Some(Rc::new(Cell { node: *node, next: head }));
+        }
+        TaskSequence { head, len: self.len + nodes.len() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        std::iter::successors(self.head.as_deref(), |cell| cell.next.as_deref()).map(|cell| cell.node)
+    }
+}
+
+// Cells are released one after the other, a recursive drop would overflow
+// the stack on long sequences
+impl Drop for TaskSequence {
+    fn drop(&mut self) {
+        let mut head = self.head.take();
+        while let Some(cell) = head {
+            match Rc::try_unwrap(cell) {
+                Ok(mut cell) => head = cell.next.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn sharing_test() {
+        let sequence = TaskSequence::new(&[1, 2, 3]);
+        assert_eq!(sequence.first(), Some(1));
+        let rest = sequence.rest();
+        let extended = rest.prepend(&[4, 5]);
+        assert_eq!(extended.iter().collect::<Vec<u32>>(), vec![4, 5, 2, 3]);
+        assert_eq!(extended.len(), 4);
+        // the original sequence is unchanged
+        drop(rest);
+        assert_eq!(sequence.iter().collect::<Vec<u32>>(), vec![1, 2, 3]);
+        assert!(TaskSequence::new(&[7]).rest().is_empty());
+        let long = TaskSequence::new(&(0..1_000_000).collect::<Vec<u32>>());
+        assert_eq!(long.len(), 1_000_000);
+    }
+}
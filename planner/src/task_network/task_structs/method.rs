@@ -7,6 +7,8 @@ use crate::task_network::network::HTN;
 pub struct Method{
     pub name: String,
     pub decomposition: HTN,
+    // none when the domain does not give a cost
+    pub cost: Option<u32>,
 }
 
 impl Method {
@@ -14,6 +16,11 @@ impl Method {
         Method {
             name: name,
             decomposition: decomposition,
+            cost: None,
         }
     }
+
+    pub fn with_cost(name: String, decomposition: HTN, cost: u32) -> Method {
+        Method { name, decomposition, cost: Some(cost) }
+    }
 }
\ No newline at end of file